@@ -55,7 +55,7 @@ impl Model<CarRecorder> for Car {
         let _ = scheduler.timeout(
             rng,
             EventTimer::WeightedIndex(vec![(5, 3), (10, 2), (15, 1)]),
-            0,
+            Priority::NORMAL,
             CarEvent::StartCharge,
         );
     }
@@ -91,7 +91,7 @@ impl BulkEvents<CarRecorder, CarEvent> for Car {
                     let _ = scheduler.timeout(
                         rng,
                         EventTimer::WeightedIndex(vec![(2, 3), (3, 2), (5, 1)]),
-                        0,
+                        Priority::NORMAL,
                         CarEvent::EndCharge,
                     );
                 }
@@ -101,7 +101,7 @@ impl BulkEvents<CarRecorder, CarEvent> for Car {
                     let _ = scheduler.timeout(
                         rng,
                         EventTimer::WeightedIndex(vec![(5, 3), (10, 2), (15, 1)]),
-                        0,
+                        Priority::NORMAL,
                         CarEvent::StartCharge,
                     );
                 }
@@ -113,7 +113,7 @@ impl BulkEvents<CarRecorder, CarEvent> for Car {
 
                 if self.fuel == Self::MAX_FUEL {
                     scheduler.clear();
-                    let _ = scheduler.immediate(rng, 0, CarEvent::EndCharge);
+                    let _ = scheduler.immediate(rng, Priority::NORMAL, CarEvent::EndCharge);
                 }
             } else if self.status == CarStatus::Driving {
                 println!("drive the car");
@@ -170,7 +170,9 @@ fn main() {
     let mut rng = thread_rng();
     let model = Car::new();
     let mut simulator = Simulator::create_from(&mut rng, model, CarRecorder::default());
-    simulator.run_until_in_bulk_event(&mut rng, |model| model.status != CarStatus::EngineStop);
+    simulator.run_until_in_bulk_event(&mut rng, |model, _, _, _| {
+        model.status != CarStatus::EngineStop
+    });
 
     println!();
     let recorder = simulator.get_recorder();
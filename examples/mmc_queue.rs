@@ -0,0 +1,149 @@
+use rand::{thread_rng, Rng};
+use sim_by_fired_event::event::{Event, EventScheduler, EventTimer, Priority, TimeSampler};
+use sim_by_fired_event::model::{Model, StepEachEvent};
+use sim_by_fired_event::processes::ArrivalProcess;
+use sim_by_fired_event::resources::{QueueDiscipline, Resource, ResourceRequest};
+use sim_by_fired_event::{FrameIndex, Simulator};
+use std::collections::BTreeMap;
+
+const FRAME_COUNT: u64 = 100_000;
+// mean frames between the arrivals
+const MEAN_INTER_ARRIVAL: f64 = 4.0;
+// mean frames of the service
+const MEAN_SERVICE: f64 = 10.0;
+// count of the servers
+const SERVERS: usize = 3;
+
+type CustomerId = u64;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum QueueEvent {
+    Arrive,
+    StartService(CustomerId),
+    Depart(CustomerId),
+}
+
+impl Event for QueueEvent {}
+
+#[derive(Debug, Default, Clone)]
+struct QueueRecorder {
+    waits: Vec<u64>,
+    busy_frames: u64,
+    served: u64,
+}
+
+impl QueueRecorder {
+    fn mean_wait(&self) -> f64 {
+        if self.waits.is_empty() {
+            0.0
+        } else {
+            self.waits.iter().sum::<u64>() as f64 / self.waits.len() as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Counter {
+    frame: FrameIndex,
+    next_customer: CustomerId,
+    arrived_at: BTreeMap<CustomerId, u64>,
+    servers: Resource<CustomerId, QueueEvent>,
+    service: EventTimer,
+}
+
+impl Counter {
+    fn new() -> Self {
+        Counter {
+            frame: 0,
+            next_customer: 0,
+            arrived_at: BTreeMap::new(),
+            servers: Resource::new(SERVERS).with_discipline(QueueDiscipline::Fifo),
+            service: EventTimer::Sampled(TimeSampler::new(|rng| {
+                -MEAN_SERVICE * (1.0 - rng.gen::<f64>()).ln()
+            })),
+        }
+    }
+}
+
+impl Model<QueueRecorder> for Counter {
+    type ModelEvent = QueueEvent;
+
+    fn initialize<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        _recorder: &mut QueueRecorder,
+        scheduler: &mut EventScheduler<QueueEvent>,
+    ) {
+        let arrivals = ArrivalProcess::exponential(MEAN_INTER_ARRIVAL)
+            .expect("mean inter-arrival time is at least 1 frame");
+        let _ = arrivals.install(rng, scheduler, Priority::NORMAL, QueueEvent::Arrive);
+    }
+
+    fn start_frame_at(&mut self, _recorder: &mut QueueRecorder, frame: FrameIndex) {
+        self.frame = frame;
+    }
+
+    fn finish_frame(&mut self, recorder: &mut QueueRecorder) {
+        recorder.busy_frames += self.servers.in_use() as u64;
+    }
+}
+
+impl StepEachEvent<QueueRecorder, QueueEvent> for Counter {
+    fn step_each_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut QueueRecorder,
+        scheduler: &mut EventScheduler<QueueEvent>,
+        _priority: Priority,
+        event: QueueEvent,
+    ) {
+        match event {
+            QueueEvent::Arrive => {
+                let customer = self.next_customer;
+                self.next_customer += 1;
+                self.arrived_at.insert(customer, self.frame);
+                let request = ResourceRequest::new(
+                    customer,
+                    Priority::NORMAL,
+                    QueueEvent::StartService(customer),
+                );
+                let _ = self.servers.request(rng, scheduler, request);
+            }
+            QueueEvent::StartService(customer) => {
+                let arrived_at = self.arrived_at.remove(&customer).unwrap();
+                recorder.waits.push(self.frame - arrived_at);
+                let _ = scheduler.timeout(
+                    rng,
+                    self.service.clone(),
+                    Priority::NORMAL,
+                    QueueEvent::Depart(customer),
+                );
+            }
+            QueueEvent::Depart(customer) => {
+                let _ = self.servers.release(rng, scheduler, &customer);
+                recorder.served += 1;
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut rng = thread_rng();
+    let mut simulator = Simulator::create_from(&mut rng, Counter::new(), Default::default());
+    // the granted customer start the service in the same frame
+    simulator.get_scheduler_as_mut().set_cascade(Some(1));
+    simulator.run_n_each_event(&mut rng, FRAME_COUNT);
+
+    let recorder = simulator.get_recorder();
+    let model = simulator.get_model();
+    println!("M/M/{} queue for {} frames", SERVERS, FRAME_COUNT);
+    println!("served: {}", recorder.served);
+    println!("mean wait: {:.2} frames", recorder.mean_wait());
+    println!(
+        "utilization: {:.3} (offered {:.3})",
+        recorder.busy_frames as f64 / (FRAME_COUNT * SERVERS as u64) as f64,
+        MEAN_SERVICE / MEAN_INTER_ARRIVAL / SERVERS as f64
+    );
+    println!("waiting at the end: {}", model.servers.queue_len());
+    println!("stats: {:?}", model.servers.stats());
+}
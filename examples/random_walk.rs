@@ -1,7 +1,7 @@
 use rand::distributions::Distribution;
 use rand::distributions::Uniform;
 use rand::{thread_rng, Rng};
-use sim_by_fired_event::event::{Event, EventScheduler, EventTimer, Schedule};
+use sim_by_fired_event::event::{Event, EventScheduler, EventTimer, Priority, Schedule};
 use sim_by_fired_event::model::{BulkEvents, Model};
 use sim_by_fired_event::Simulator;
 use std::collections::BTreeMap;
@@ -81,7 +81,7 @@ impl Walker {
                 pattern: schedule,
             },
             Schedule::Everytime => Walker {
-                name: format!("everytime"),
+                name: "everytime".to_string(),
                 position: INITIAL_POSITION,
                 pattern: schedule,
             },
@@ -95,6 +95,11 @@ impl Walker {
                 position: INITIAL_POSITION,
                 pattern: schedule,
             },
+            Schedule::Periodic { period, offset } => Walker {
+                name: format!("periodic_{}_{}", period, offset),
+                position: INITIAL_POSITION,
+                pattern: schedule,
+            },
         }
     }
 
@@ -111,7 +116,7 @@ impl Walker {
         index: usize,
     ) {
         let schedule = self.pattern.clone();
-        let _ = scheduler.schedule(rng, schedule, 0, Walk { index });
+        let _ = scheduler.schedule(rng, schedule, Priority::NORMAL, Walk { index });
     }
 }
 
@@ -127,7 +132,7 @@ fn get_all_patterns() -> Vec<Schedule> {
 
     result.push(Immediate);
     result.push(Everytime);
-    for (index, timer) in vec![
+    for (index, timer) in [
         EventTimer::Time(10),
         EventTimer::Uniform(1, 10, true),
         EventTimer::WeightedIndex(vec![(1, 2), (5, 5), (10, 2)]),
@@ -178,7 +183,7 @@ impl BulkEvents<Recorder, Walk> for WalkerList {
         rng: &mut R,
         recorder: &mut Recorder,
         _scheduler: &mut EventScheduler<Self::ModelEvent>,
-        fired_events: Vec<(u8, Self::ModelEvent)>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
     ) {
         print!("fired:");
         for (_, fired) in fired_events.iter() {
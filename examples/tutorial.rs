@@ -1,25 +1,26 @@
 use rand::{thread_rng, Rng};
+use sim_by_fired_event::clock::VirtualClock;
 use sim_by_fired_event::event::{Event, EventScheduler, EventTimer, Priority};
 use sim_by_fired_event::model::{Model, StepEachEvent};
+use sim_by_fired_event::realtime::Pacing;
 use sim_by_fired_event::Simulator;
 use std::collections::HashMap;
 use std::iter::FromIterator;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 /// This tutorial example is timeline simulation
 
 // Create Model structure
-#[derive(Debug, Eq, PartialEq, Ord, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 struct TimelineItem {
     account: String,
     message: String,
     created_at: Duration,
 }
 
-impl PartialOrd<TimelineItem> for TimelineItem {
-    fn partial_cmp(&self, other: &TimelineItem) -> Option<std::cmp::Ordering> {
-        (&self.created_at, &self.account, &self.message).partial_cmp(&(
+impl Ord for TimelineItem {
+    fn cmp(&self, other: &TimelineItem) -> std::cmp::Ordering {
+        (&self.created_at, &self.account, &self.message).cmp(&(
             &other.created_at,
             &other.account,
             &other.message,
@@ -27,6 +28,12 @@ impl PartialOrd<TimelineItem> for TimelineItem {
     }
 }
 
+impl PartialOrd<TimelineItem> for TimelineItem {
+    fn partial_cmp(&self, other: &TimelineItem) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::fmt::Display for TimelineItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -40,7 +47,7 @@ impl std::fmt::Display for TimelineItem {
 #[derive(Debug, Clone)]
 struct Timeline {
     // meta
-    start: SystemTime,
+    clock: VirtualClock,
     follow: HashMap<String, Vec<String>>,
     use_flush: bool,
 
@@ -52,42 +59,39 @@ struct Timeline {
 impl Timeline {
     fn new() -> Self {
         Timeline {
-            start: SystemTime::now(),
-            follow: HashMap::from_iter(
-                vec![
-                    (
-                        "Azio".to_string(),
-                        vec![
-                            "こんにちは".to_string(),
-                            "你好".to_string(),
-                            "안녕하세요".to_string(),
-                            "Xin chào".to_string(),
-                            "नमस्ते".to_string(),
-                        ],
-                    ),
-                    (
+            clock: VirtualClock::from_unix_epoch(Duration::from_millis(250)),
+            follow: HashMap::from_iter(vec![
+                (
+                    "Azio".to_string(),
+                    vec![
+                        "こんにちは".to_string(),
+                        "你好".to_string(),
+                        "안녕하세요".to_string(),
+                        "Xin chào".to_string(),
+                        "नमस्ते".to_string(),
+                    ],
+                ),
+                (
+                    "Mezoriento".to_string(),
+                    vec![
                         "Mezoriento".to_string(),
-                        vec![
-                            "Mezoriento".to_string(),
-                            "Merhaba".to_string(),
-                            "გამარჯობა".to_string(),
-                            "سلام علیکم".to_string(),
-                            "ԲարեՎ".to_string(),
-                        ],
-                    ),
-                    (
-                        "Eŭropo".to_string(),
-                        vec![
-                            "Bonjour".to_string(),
-                            "Guten tag".to_string(),
-                            "Buon giorno".to_string(),
-                            "Buon giorno".to_string(),
-                            "Olá".to_string(),
-                        ],
-                    ),
-                ]
-                .into_iter(),
-            ),
+                        "Merhaba".to_string(),
+                        "გამარჯობა".to_string(),
+                        "سلام علیکم".to_string(),
+                        "ԲարեՎ".to_string(),
+                    ],
+                ),
+                (
+                    "Eŭropo".to_string(),
+                    vec![
+                        "Bonjour".to_string(),
+                        "Guten tag".to_string(),
+                        "Buon giorno".to_string(),
+                        "Buon giorno".to_string(),
+                        "Olá".to_string(),
+                    ],
+                ),
+            ]),
             use_flush: false,
             items: vec![],
             before_flush: vec![],
@@ -98,7 +102,7 @@ impl Timeline {
         self.before_flush.push(TimelineItem {
             account,
             message,
-            created_at: SystemTime::now().duration_since(self.start).unwrap(),
+            created_at: self.clock.elapsed(),
         });
     }
 
@@ -114,7 +118,7 @@ impl Timeline {
             .timeout(
                 rng,
                 EventTimer::Uniform(20, 30, true),
-                Priority::MIN + 1,
+                Priority::MIN.raised(1),
                 TimelineEvent::Spawn(account.to_string(), messages.first().unwrap().to_string()),
             )
             .unwrap();
@@ -177,8 +181,7 @@ impl Model<Recorder> for Timeline {
     }
 
     fn start_frame(&mut self, _recorder: &mut Recorder) {
-        // wait time for apply every 0.25sec. (not need)
-        sleep(Duration::from_millis(250));
+        self.clock.advance();
     }
 
     fn finish_frame(&mut self, recorder: &mut Recorder) {
@@ -214,5 +217,11 @@ fn main() {
     let mut rng = thread_rng();
     let model = Timeline::new();
     let mut simulator = Simulator::create_from(&mut rng, model, Recorder {});
-    simulator.run_n_each_event(&mut rng, COUNT);
+    // run each frame every 0.25sec. (not need)
+    let (report, realtime) = simulator.run_realtime_each_event(
+        &mut rng,
+        COUNT,
+        Pacing::catch_up(Duration::from_millis(250)),
+    );
+    println!("\n{:?}\n{:?}", report, realtime);
 }
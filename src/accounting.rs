@@ -0,0 +1,333 @@
+//! Accounting of the energy, cost and other amounts which are posted by the model
+
+use crate::event::{Event, EventScheduler, Priority, Schedule, ScheduleEventError};
+use crate::FrameIndex;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+/// one posted amount
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerEntry<C> {
+    /// category of the amount
+    pub category: C,
+    /// posted amount. the income is positive and the expense is negative as usual.
+    pub amount: f64,
+    /// index of the frame which the amount is posted at
+    pub frame: FrameIndex,
+}
+
+/// ledger which keep posted amounts and aggregate them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ledger<C: Ord + Clone> {
+    entries: Vec<LedgerEntry<C>>,
+    /// running total of each category which is updated in posting
+    totals: BTreeMap<C, f64>,
+}
+
+impl<C: Ord + Clone> Default for Ledger<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Ord + Clone> Ledger<C> {
+    /// initializer
+    pub fn new() -> Self {
+        Ledger {
+            entries: vec![],
+            totals: BTreeMap::new(),
+        }
+    }
+
+    /// post the amount for the category at the frame
+    pub fn post(&mut self, frame: FrameIndex, category: C, amount: f64) {
+        *self.totals.entry(category.clone()).or_insert(0.0) += amount;
+        self.entries.push(LedgerEntry {
+            category,
+            amount,
+            frame,
+        });
+    }
+
+    /// getter for posted entries in posted order
+    pub fn entries(&self) -> &[LedgerEntry<C>] {
+        &self.entries
+    }
+
+    /// judge nothing is posted
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// count of posted entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.totals.clear();
+    }
+
+    /// sum of all amounts
+    pub fn total(&self) -> f64 {
+        self.entries.iter().map(|entry| entry.amount).sum()
+    }
+
+    /// sum of amounts in the category
+    pub fn total_of(&self, category: &C) -> f64 {
+        self.totals.get(category).copied().unwrap_or(0.0)
+    }
+
+    /// sum of amounts for each category
+    pub fn totals(&self) -> BTreeMap<C, f64> {
+        self.totals.clone()
+    }
+
+    /// sum of amounts for each frame which has entries
+    pub fn totals_by_frame(&self) -> BTreeMap<FrameIndex, f64> {
+        let mut totals = BTreeMap::new();
+        for entry in self.entries.iter() {
+            *totals.entry(entry.frame).or_insert(0.0) += entry.amount;
+        }
+        totals
+    }
+
+    /// balance at the end of each frame which has entries in ascending order of the frame
+    pub fn running_balance(&self) -> Vec<(FrameIndex, f64)> {
+        let mut balance = 0.0;
+        self.totals_by_frame()
+            .into_iter()
+            .map(|(frame, amount)| {
+                balance += amount;
+                (frame, balance)
+            })
+            .collect()
+    }
+
+    /// balance at the end of the frame
+    pub fn balance_at(&self, frame: FrameIndex) -> f64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.frame <= frame)
+            .map(|entry| entry.amount)
+            .sum()
+    }
+
+    /// net present value at frame 0 with the discount rate per frame
+    pub fn npv(&self, rate: f64) -> f64 {
+        self.discounted_sum(rate, |_| true)
+    }
+
+    /// net present value of the category at frame 0 with the discount rate per frame
+    pub fn npv_of(&self, category: &C, rate: f64) -> f64 {
+        self.discounted_sum(rate, |entry| &entry.category == category)
+    }
+
+    /// sum of the discounted amounts which are filtered
+    fn discounted_sum<F>(&self, rate: f64, filter: F) -> f64
+    where
+        F: Fn(&LedgerEntry<C>) -> bool,
+    {
+        self.entries
+            .iter()
+            .filter(|entry| filter(entry))
+            .map(|entry| entry.amount / (1.0 + rate).powf(entry.frame as f64))
+            .sum()
+    }
+}
+
+/// limit of the category with the event which is scheduled when exceeded
+#[derive(Debug, Clone)]
+struct Budget<C, E: Event> {
+    category: C,
+    limit: f64,
+    schedule: Schedule,
+    priority: Priority,
+    event: E,
+    exhausted: bool,
+}
+
+/// budget limits for categories of the ledger.
+///
+/// the budget is exceeded when the absolute value of the category's total is greater than the limit,
+/// so it work for both the expense posted as positive and as negative.
+/// the event is scheduled only once until the budget is rearmed.
+#[derive(Debug, Clone)]
+pub struct Budgets<C: Ord + Clone, E: Event> {
+    budgets: Vec<Budget<C, E>>,
+}
+
+impl<C: Ord + Clone, E: Event + Clone> Default for Budgets<C, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Ord + Clone, E: Event + Clone> Budgets<C, E> {
+    /// initializer
+    pub fn new() -> Self {
+        Budgets { budgets: vec![] }
+    }
+
+    /// set the limit with the event which fire at immediate timing when exceeded.
+    /// the old limit for the category is replaced.
+    pub fn set_limit(&mut self, category: C, limit: f64, priority: Priority, event: E) {
+        self.set_limit_with_schedule(category, limit, Schedule::Immediate, priority, event)
+    }
+
+    /// set the limit with the event which is scheduled when exceeded.
+    /// the old limit for the category is replaced.
+    pub fn set_limit_with_schedule(
+        &mut self,
+        category: C,
+        limit: f64,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) {
+        self.remove_limit(&category);
+        self.budgets.push(Budget {
+            category,
+            limit,
+            schedule,
+            priority,
+            event,
+            exhausted: false,
+        });
+    }
+
+    /// remove the limit for the category
+    pub fn remove_limit(&mut self, category: &C) {
+        self.budgets.retain(|budget| &budget.category != category);
+    }
+
+    /// getter for the limit of the category
+    pub fn limit(&self, category: &C) -> Option<f64> {
+        self.find(category).map(|budget| budget.limit)
+    }
+
+    /// judge the budget of the category is already exceeded
+    pub fn is_exhausted(&self, category: &C) -> bool {
+        self.find(category).map_or(false, |budget| budget.exhausted)
+    }
+
+    /// enable to schedule the event again for the category
+    pub fn rearm(&mut self, category: &C) {
+        for budget in self.budgets.iter_mut() {
+            if &budget.category == category {
+                budget.exhausted = false;
+            }
+        }
+    }
+
+    /// find the budget of the category
+    fn find(&self, category: &C) -> Option<&Budget<C, E>> {
+        self.budgets
+            .iter()
+            .find(|budget| &budget.category == category)
+    }
+
+    /// check all budgets with the ledger and schedule the events for newly exceeded budgets.
+    /// return the newly exceeded categories.
+    pub fn check<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        ledger: &Ledger<C>,
+        scheduler: &mut EventScheduler<E>,
+    ) -> Result<Vec<C>, ScheduleEventError> {
+        let mut exceeded = vec![];
+        for budget in self.budgets.iter_mut() {
+            if budget.exhausted {
+                continue;
+            }
+            if ledger.total_of(&budget.category).abs() > budget.limit {
+                scheduler.schedule(
+                    rng,
+                    budget.schedule.clone(),
+                    budget.priority,
+                    budget.event.clone(),
+                )?;
+                budget.exhausted = true;
+                exceeded.push(budget.category.clone());
+            }
+        }
+        Ok(exceeded)
+    }
+
+    /// post the amount to the ledger and check the budget of the category.
+    /// return true when the budget is newly exceeded.
+    pub fn post<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        ledger: &mut Ledger<C>,
+        scheduler: &mut EventScheduler<E>,
+        frame: FrameIndex,
+        category: C,
+        amount: f64,
+    ) -> Result<bool, ScheduleEventError> {
+        ledger.post(frame, category.clone(), amount);
+        let budget = match self
+            .budgets
+            .iter_mut()
+            .find(|budget| budget.category == category)
+        {
+            Some(budget) if !budget.exhausted => budget,
+            _ => return Ok(false),
+        };
+        if ledger.total_of(&category).abs() <= budget.limit {
+            return Ok(false);
+        }
+        scheduler.schedule(
+            rng,
+            budget.schedule.clone(),
+            budget.priority,
+            budget.event.clone(),
+        )?;
+        budget.exhausted = true;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct OverBudget;
+
+    impl Event for OverBudget {}
+
+    #[test]
+    fn budgets_check_running_totals_of_ledger() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        let mut ledger = Ledger::new();
+        let mut budgets = Budgets::new();
+        budgets.set_limit("energy", 10.0, Priority::NORMAL, OverBudget);
+
+        ledger.post(1, "cost", -3.0);
+        assert!(!budgets
+            .post(&mut rng, &mut ledger, &mut scheduler, 1, "energy", -6.0)
+            .unwrap());
+        assert!(budgets
+            .post(&mut rng, &mut ledger, &mut scheduler, 2, "energy", -5.0)
+            .unwrap());
+        assert_eq!(ledger.total_of(&"energy"), -11.0);
+        assert_eq!(ledger.total_of(&"cost"), -3.0);
+        assert_eq!(ledger.total(), -14.0);
+        assert_eq!(scheduler.count(), 1);
+
+        ledger.clear();
+        assert_eq!(ledger.total_of(&"energy"), 0.0);
+        budgets.rearm(&"energy");
+        ledger.post(3, "energy", 12.0);
+        assert_eq!(
+            budgets.check(&mut rng, &ledger, &mut scheduler).unwrap(),
+            vec!["energy"]
+        );
+    }
+}
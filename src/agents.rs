@@ -0,0 +1,487 @@
+//! Simulator for the population of agents which send events to each other
+
+use crate::event::{
+    Event, EventId, EventScheduler, FrameActions, Priority, Schedule, ScheduleEventError, Tag,
+};
+use crate::{FrameCounter, FrameIndex};
+use rand::{Rng, RngCore};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::mem;
+
+/// identifier of the agent
+pub type AgentId = u64;
+
+/// destination of the event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// send to the agent
+    Agent(AgentId),
+    /// send to all agents which exist at the fired time
+    Broadcast,
+}
+
+/// event with the destination and the sender
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<E: Event> {
+    /// destination of the event
+    pub to: Address,
+    /// sender of the event. if sent from outside of agents, then None.
+    pub from: Option<AgentId>,
+    /// sent event
+    pub event: E,
+}
+
+impl<E: Event> Event for Envelope<E> {}
+
+/// agent which is managed by AgentSimulator
+pub trait Agent<E: Event, Rec> {
+    #[allow(unused_variables)]
+    /// action when the agent is spawned
+    fn on_spawn(&mut self, ctx: &mut AgentContext<'_, E, Rec>) {
+        // usually not use
+    }
+
+    /// action for the event in the agent's mailbox
+    fn handle(
+        &mut self,
+        ctx: &mut AgentContext<'_, E, Rec>,
+        priority: Priority,
+        from: Option<AgentId>,
+        event: E,
+    );
+
+    #[allow(unused_variables)]
+    /// action after handled all events in the mailbox for each frame
+    fn on_frame(&mut self, ctx: &mut AgentContext<'_, E, Rec>) {
+        // usually not use
+    }
+
+    #[allow(unused_variables)]
+    /// action when the agent is despawned
+    fn on_despawn(&mut self, ctx: &mut AgentContext<'_, E, Rec>) {
+        // usually not use
+    }
+}
+
+/// request from the agent which is applied after the agent's action
+enum Command<E: Event, Rec> {
+    Spawn(AgentId, Box<dyn Agent<E, Rec>>),
+    Despawn(AgentId),
+}
+
+/// context for the agent's action
+pub struct AgentContext<'a, E: Event, Rec> {
+    id: AgentId,
+    frame: FrameIndex,
+    rng: &'a mut dyn RngCore,
+    recorder: &'a mut Rec,
+    scheduler: &'a mut EventScheduler<Envelope<E>>,
+    next_id: &'a mut AgentId,
+    commands: &'a mut Vec<Command<E, Rec>>,
+}
+
+impl<'a, E: Event + Clone, Rec> AgentContext<'a, E, Rec> {
+    /// identifier of the acting agent
+    pub fn id(&self) -> AgentId {
+        self.id
+    }
+
+    /// index of the current frame
+    pub fn frame(&self) -> FrameIndex {
+        self.frame
+    }
+
+    /// getter for random number generator
+    pub fn rng(&mut self) -> &mut dyn RngCore {
+        self.rng
+    }
+
+    /// getter for recorder
+    pub fn recorder(&mut self) -> &mut Rec {
+        self.recorder
+    }
+
+    /// getter for scheduler
+    pub fn scheduler(&mut self) -> &mut EventScheduler<Envelope<E>> {
+        self.scheduler
+    }
+
+    /// send event to the address with scheduling
+    pub fn send(
+        &mut self,
+        schedule: Schedule,
+        priority: Priority,
+        to: Address,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        let envelope = Envelope {
+            to,
+            from: Some(self.id),
+            event,
+        };
+        self.scheduler
+            .schedule(&mut *self.rng, schedule, priority, envelope)
+    }
+
+    /// send event to the agent with scheduling
+    pub fn send_to(
+        &mut self,
+        schedule: Schedule,
+        priority: Priority,
+        to: AgentId,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.send(schedule, priority, Address::Agent(to), event)
+    }
+
+    /// send event to all agents with scheduling
+    pub fn broadcast(
+        &mut self,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.send(schedule, priority, Address::Broadcast, event)
+    }
+
+    /// spawn the agent after this action. the identifier is fixed now.
+    pub fn spawn(&mut self, agent: Box<dyn Agent<E, Rec>>) -> AgentId {
+        let id = *self.next_id;
+        *self.next_id += 1;
+        self.commands.push(Command::Spawn(id, agent));
+        id
+    }
+
+    /// despawn the agent after this action
+    pub fn despawn(&mut self, id: AgentId) {
+        self.commands.push(Command::Despawn(id));
+    }
+}
+
+/// agents with their mailboxes and the states which are lent to the agents' actions
+struct Population<E: Event, Rec> {
+    agents: BTreeMap<AgentId, Box<dyn Agent<E, Rec>>>,
+    mailboxes: BTreeMap<AgentId, Vec<(Priority, Option<AgentId>, E)>>,
+    recorder: Rec,
+    next_id: AgentId,
+    frame: FrameIndex,
+    undelivered: usize,
+}
+
+impl<E: Event + Clone, Rec> Population<E, Rec> {
+    /// run the agent's action with context
+    fn act<R, F>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<Envelope<E>>,
+        id: AgentId,
+        commands: &mut Vec<Command<E, Rec>>,
+        action: F,
+    ) where
+        R: Rng,
+        F: FnOnce(&mut dyn Agent<E, Rec>, &mut AgentContext<'_, E, Rec>),
+    {
+        // take out the agent to lend the other states to the context
+        let mut agent = match self.agents.remove(&id) {
+            Some(agent) => agent,
+            None => return,
+        };
+        let mut ctx = AgentContext {
+            id,
+            frame: self.frame,
+            rng,
+            recorder: &mut self.recorder,
+            scheduler,
+            next_id: &mut self.next_id,
+            commands,
+        };
+        action(agent.as_mut(), &mut ctx);
+        self.agents.insert(id, agent);
+    }
+
+    /// apply spawn and despawn requests until no request is left
+    fn apply_commands<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<Envelope<E>>,
+        commands: &mut Vec<Command<E, Rec>>,
+    ) {
+        while !commands.is_empty() {
+            let mut next_commands = vec![];
+            for command in commands.drain(..) {
+                match command {
+                    Command::Spawn(id, agent) => {
+                        self.agents.insert(id, agent);
+                        self.act(rng, scheduler, id, &mut next_commands, |agent, ctx| {
+                            agent.on_spawn(ctx)
+                        });
+                    }
+                    Command::Despawn(id) => {
+                        self.act(rng, scheduler, id, &mut next_commands, |agent, ctx| {
+                            agent.on_despawn(ctx)
+                        });
+                        self.agents.remove(&id);
+                        self.mailboxes.remove(&id);
+                        scheduler
+                            .remove_when(|(_, _, _, envelope)| envelope.to == Address::Agent(id));
+                    }
+                }
+            }
+            *commands = next_commands;
+        }
+    }
+
+    /// deliver fired events to the mailboxes
+    fn deliver(&mut self, fired_events: &mut Vec<(Priority, Envelope<E>)>) {
+        for (priority, envelope) in fired_events.drain(..) {
+            match envelope.to {
+                Address::Agent(id) => {
+                    if self.agents.contains_key(&id) {
+                        self.mailboxes.entry(id).or_default().push((
+                            priority,
+                            envelope.from,
+                            envelope.event,
+                        ));
+                    } else {
+                        self.undelivered += 1;
+                    }
+                }
+                Address::Broadcast => {
+                    for id in self.agents.keys() {
+                        self.mailboxes.entry(*id).or_default().push((
+                            priority,
+                            envelope.from,
+                            envelope.event.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// simulator which manage agents and deliver events to the agents' mailboxes
+pub struct AgentSimulator<E: Event, Rec> {
+    population: Population<E, Rec>,
+    scheduler: EventScheduler<Envelope<E>>,
+}
+
+impl<E: Event + Clone, Rec> AgentSimulator<E, Rec> {
+    /// create simulator without agents
+    pub fn new(recorder: Rec) -> Self {
+        AgentSimulator {
+            population: Population {
+                agents: BTreeMap::new(),
+                mailboxes: BTreeMap::new(),
+                recorder,
+                next_id: 0,
+                frame: 0,
+                undelivered: 0,
+            },
+            scheduler: EventScheduler::new(),
+        }
+    }
+
+    /// getter for recorder
+    pub fn get_recorder(&self) -> &Rec {
+        &self.population.recorder
+    }
+
+    /// getter for recorder
+    pub fn get_recorder_as_mut(&mut self) -> &mut Rec {
+        &mut self.population.recorder
+    }
+
+    /// getter for scheduler
+    pub fn get_scheduler(&self) -> &EventScheduler<Envelope<E>> {
+        &self.scheduler
+    }
+
+    /// index of the last simulated frame. if not simulated yet, then 0.
+    pub fn frame_index(&self) -> FrameIndex {
+        self.population.frame
+    }
+
+    /// count of living agents
+    pub fn agent_count(&self) -> usize {
+        self.population.agents.len()
+    }
+
+    /// judge the agent is living
+    pub fn contains(&self, id: AgentId) -> bool {
+        self.population.agents.contains_key(&id)
+    }
+
+    /// identifiers of living agents in ascending order
+    pub fn agent_ids(&self) -> Vec<AgentId> {
+        self.population.agents.keys().copied().collect()
+    }
+
+    /// count of fired events which are sent to the agent already despawned
+    pub fn undelivered(&self) -> usize {
+        self.population.undelivered
+    }
+
+    /// spawn the agent from outside of agents
+    pub fn spawn<R: Rng>(&mut self, rng: &mut R, agent: Box<dyn Agent<E, Rec>>) -> AgentId {
+        let id = self.population.next_id;
+        self.population.next_id += 1;
+        let mut commands = vec![Command::Spawn(id, agent)];
+        self.population
+            .apply_commands(rng, &mut self.scheduler, &mut commands);
+        id
+    }
+
+    /// despawn the agent from outside of agents
+    pub fn despawn<R: Rng>(&mut self, rng: &mut R, id: AgentId) {
+        let mut commands = vec![Command::Despawn(id)];
+        self.population
+            .apply_commands(rng, &mut self.scheduler, &mut commands);
+    }
+
+    /// send event from outside of agents with scheduling
+    pub fn send<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        to: Address,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        let envelope = Envelope {
+            to,
+            from: None,
+            event,
+        };
+        self.scheduler.schedule(rng, schedule, priority, envelope)
+    }
+
+    //
+    // run simulation
+    //
+
+    /// run simulate for one frame.
+    /// each agent handle the events in its mailbox in ascending order of the identifier.
+    /// the events which cascade in the frame are handled by the agents which receive them without on_frame.
+    pub fn run_step<R: Rng>(&mut self, rng: &mut R) {
+        self.population.frame += 1;
+        let mut fired_events = vec![];
+        let mut actions = AgentActions {
+            population: &mut self.population,
+            batched: vec![],
+            first: true,
+        };
+        let _ = self
+            .scheduler
+            .fire_frame(rng, &mut fired_events, &mut actions);
+    }
+
+    /// run simulate for frames
+    pub fn run_n<R: Rng, FC: FrameCounter>(&mut self, rng: &mut R, counter: FC) {
+        let mut index = FC::start_index();
+        loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break;
+            }
+
+            self.run_step(rng);
+        }
+    }
+
+    /// run simulation until condition is true
+    pub fn run_until<R: Rng, F>(&mut self, rng: &mut R, mut can_continue: F)
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        loop {
+            if !can_continue(self) {
+                break;
+            }
+
+            self.run_step(rng);
+        }
+    }
+
+    /// take all agents with identifiers
+    pub fn into_agents(mut self) -> Vec<(AgentId, Box<dyn Agent<E, Rec>>)> {
+        mem::take(&mut self.population.agents).into_iter().collect()
+    }
+}
+
+/// actions of the agents for the frame which is fired by the scheduler
+struct AgentActions<'a, E: Event, Rec> {
+    population: &'a mut Population<E, Rec>,
+    /// batched events which are delivered after the fired events
+    batched: Vec<(Priority, Envelope<E>)>,
+    /// the first handle in the frame, not the cascade
+    first: bool,
+}
+
+impl<'a, E, Rec, R> FrameActions<Envelope<E>, R> for AgentActions<'a, E, Rec>
+where
+    E: Event + Clone,
+    R: Rng,
+{
+    type Error = Infallible;
+
+    // the agents have no hook for the expired events
+
+    fn batch(
+        &mut self,
+        _rng: &mut R,
+        _scheduler: &mut EventScheduler<Envelope<E>>,
+        _tag: Tag,
+        mut events: Vec<(Priority, Envelope<E>)>,
+    ) {
+        // the agents have no hook for the batches, so the batched events are delivered at the end of the window
+        self.batched.append(&mut events);
+    }
+
+    fn handle(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<Envelope<E>>,
+        fired_events: &mut Vec<(Priority, Envelope<E>)>,
+    ) -> Result<(), Infallible> {
+        let population = &mut *self.population;
+        population.deliver(fired_events);
+        population.deliver(&mut self.batched);
+
+        let first = mem::replace(&mut self.first, false);
+        let ids: Vec<AgentId> = if first {
+            population.agents.keys().copied().collect()
+        } else {
+            population.mailboxes.keys().copied().collect()
+        };
+        for id in ids {
+            let mailbox = population.mailboxes.remove(&id).unwrap_or_default();
+            let mut commands = vec![];
+            population.act(rng, scheduler, id, &mut commands, |agent, ctx| {
+                for (priority, from, event) in mailbox.into_iter() {
+                    agent.handle(ctx, priority, from, event);
+                }
+                if first {
+                    agent.on_frame(ctx);
+                }
+            });
+            population.apply_commands(rng, scheduler, &mut commands);
+        }
+        Ok(())
+    }
+}
+
+impl<E: Event + Clone, Rec: fmt::Debug> fmt::Debug for AgentSimulator<E, Rec>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AgentSimulator")
+            .field("agents", &self.agent_ids())
+            .field("recorder", &self.population.recorder)
+            .field("scheduler", &self.scheduler)
+            .field("frame", &self.population.frame)
+            .finish()
+    }
+}
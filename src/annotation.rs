@@ -0,0 +1,12 @@
+//! Free-form annotations which mark what happened at the frame
+
+use crate::FrameIndex;
+
+/// annotation which is attached to the frame
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    /// index of the frame which the annotation is attached to. the annotation in initializing is attached to 0.
+    pub frame: FrameIndex,
+    /// text of the annotation
+    pub text: String,
+}
@@ -0,0 +1,104 @@
+//! Strategies of proptest which generate the valid schedules, timers and priorities for the property tests
+
+use crate::event::{EventTimer, LocalEventTime, Priority, Schedule, TimeSampler};
+use proptest::prelude::*;
+
+/// max time of the timers which are generated by Arbitrary
+pub const DEFAULT_MAX_TIME: LocalEventTime = 100;
+
+/// priority including the lowest and the highest
+pub fn priority() -> BoxedStrategy<Priority> {
+    prop_oneof![
+        Just(Priority::MIN),
+        Just(Priority::MAX),
+        any::<u8>().prop_map(Priority::new)
+    ]
+    .boxed()
+}
+
+/// timer which fire in [1, max_time] frames except the jitter. the jittered timer fire in [1, 2 * max_time] frames.
+///
+/// the edge cases such as the single value uniform ranges and the single item weights are included.
+pub fn timer(max_time: LocalEventTime) -> BoxedStrategy<EventTimer> {
+    let base = base_timer(max_time);
+    prop_oneof![
+        4 => base.clone(),
+        1 => (base, 0..=max_time.clamp(1, LocalEventTime::MAX - 1))
+            .prop_map(|(base, spread)| base.with_jitter(spread)),
+    ]
+    .boxed()
+}
+
+/// schedule whose timer is generated by timer(max_time). the repeat count and the period are never 0.
+pub fn schedule(max_time: LocalEventTime) -> BoxedStrategy<Schedule> {
+    let timer = timer(max_time);
+    prop_oneof![
+        Just(Schedule::Immediate),
+        Just(Schedule::Everytime),
+        timer.clone().prop_map(Schedule::Timeout),
+        timer.clone().prop_map(Schedule::EveryInterval),
+        (prop_oneof![Just(1), Just(u8::MAX), 1..=u8::MAX], timer)
+            .prop_map(|(count, timer)| Schedule::Repeat(count, timer)),
+        (1..=max_time.max(1), 0..=max_time)
+            .prop_map(|(period, offset)| Schedule::Periodic { period, offset }),
+    ]
+    .boxed()
+}
+
+/// timer without the jitter
+fn base_timer(max_time: LocalEventTime) -> BoxedStrategy<EventTimer> {
+    // keep the room for the exclusive upper bound of the uniform range
+    let max_time = max_time.clamp(1, LocalEventTime::MAX - 1);
+    let time = prop_oneof![Just(1), Just(max_time), 1..=max_time];
+    let fixed = time.clone().prop_map(EventTimer::Time);
+    // single value ranges
+    let single_inclusive = time
+        .clone()
+        .prop_map(|time| EventTimer::Uniform(time, time, true));
+    let single_exclusive = time
+        .clone()
+        .prop_map(|time| EventTimer::Uniform(time, time + 1, false));
+    let inclusive = (time.clone(), time.clone())
+        .prop_map(|(a, b)| EventTimer::Uniform(a.min(b), a.max(b), true));
+    let exclusive = (1..=max_time)
+        .prop_flat_map(move |low| (Just(low), low + 1..=max_time + 1))
+        .prop_map(|(low, high)| EventTimer::Uniform(low, high, false));
+    let weighted =
+        prop::collection::vec((time.clone(), 0..=100_u32), 1..=4).prop_map(|mut items| {
+            // at least one weight must be positive
+            if items.iter().all(|(_, weight)| *weight == 0) {
+                items[0].1 = 1;
+            }
+            EventTimer::WeightedIndex(items)
+        });
+    let sampled =
+        time.prop_map(|time| EventTimer::Sampled(TimeSampler::new(move |_| f64::from(time))));
+    prop_oneof![
+        fixed,
+        single_inclusive,
+        single_exclusive,
+        inclusive,
+        exclusive,
+        weighted,
+        sampled,
+    ]
+    .boxed()
+}
+
+impl Arbitrary for EventTimer {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<EventTimer>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        timer(DEFAULT_MAX_TIME)
+    }
+}
+
+impl Arbitrary for Schedule {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Schedule>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        schedule(DEFAULT_MAX_TIME)
+    }
+}
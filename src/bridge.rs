@@ -0,0 +1,309 @@
+//! Co-simulation of two simulators which exchange the events on the shared frame clock
+
+use crate::event::{Event, EventScheduler, Priority, Schedule};
+use crate::frame::FrameOutcome;
+use crate::model::{BulkEvents, Model, StepEachEvent};
+use crate::{owned_events, FrameCounter, FrameIndex, Simulator};
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+
+/// mapping from the fired event of one simulator to the event of the other simulator. None is not transferred.
+pub type EventMap<From, To> = Arc<dyn Fn(Priority, &From) -> Option<(Priority, To)> + Send + Sync>;
+
+/// result of the frame which is simulated by both simulators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BridgeOutcome {
+    /// outcome of the simulator a
+    pub a: FrameOutcome,
+    /// outcome of the simulator b
+    pub b: FrameOutcome,
+    /// count of the events which are transferred from a to b at the end of the frame
+    pub to_b: usize,
+    /// count of the events which are transferred from b to a at the end of the frame
+    pub to_a: usize,
+}
+
+/// two simulators which are stepped in lockstep.
+///
+/// the fired events of each simulator are translated by the mapping and injected into the other scheduler
+/// as the immediate events at the end of the frame, so the transferred events fire in the next frame of the other simulator.
+/// both simulators should be at the same frame when bridged.
+#[derive(Clone)]
+pub struct Bridge<MA, EA, RecA, MB, EB, RecB>
+where
+    MA: Model<RecA, ModelEvent = EA>,
+    EA: Event,
+    MB: Model<RecB, ModelEvent = EB>,
+    EB: Event,
+{
+    a: Simulator<MA, EA, RecA>,
+    b: Simulator<MB, EB, RecB>,
+    a_to_b: EventMap<EA, EB>,
+    b_to_a: EventMap<EB, EA>,
+    transferred_to_a: u64,
+    transferred_to_b: u64,
+}
+
+impl<MA, EA, RecA, MB, EB, RecB> fmt::Debug for Bridge<MA, EA, RecA, MB, EB, RecB>
+where
+    MA: Model<RecA, ModelEvent = EA> + fmt::Debug,
+    EA: Event + fmt::Debug,
+    RecA: fmt::Debug,
+    MB: Model<RecB, ModelEvent = EB> + fmt::Debug,
+    EB: Event + fmt::Debug,
+    RecB: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bridge")
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .field("transferred_to_a", &self.transferred_to_a)
+            .field("transferred_to_b", &self.transferred_to_b)
+            .finish()
+    }
+}
+
+impl<MA, EA, RecA, MB, EB, RecB> Bridge<MA, EA, RecA, MB, EB, RecB>
+where
+    MA: Model<RecA, ModelEvent = EA>,
+    EA: Event,
+    MB: Model<RecB, ModelEvent = EB>,
+    EB: Event,
+{
+    /// initializer with the simulators and the mappings of the events in each direction
+    pub fn new<FA, FB>(
+        a: Simulator<MA, EA, RecA>,
+        b: Simulator<MB, EB, RecB>,
+        a_to_b: FA,
+        b_to_a: FB,
+    ) -> Self
+    where
+        FA: Fn(Priority, &EA) -> Option<(Priority, EB)> + Send + Sync + 'static,
+        FB: Fn(Priority, &EB) -> Option<(Priority, EA)> + Send + Sync + 'static,
+    {
+        Bridge {
+            a,
+            b,
+            a_to_b: Arc::new(a_to_b),
+            b_to_a: Arc::new(b_to_a),
+            transferred_to_a: 0,
+            transferred_to_b: 0,
+        }
+    }
+
+    /// getter for the simulator a
+    pub fn a(&self) -> &Simulator<MA, EA, RecA> {
+        &self.a
+    }
+
+    /// getter for the simulator a as mutable
+    pub fn a_as_mut(&mut self) -> &mut Simulator<MA, EA, RecA> {
+        &mut self.a
+    }
+
+    /// getter for the simulator b
+    pub fn b(&self) -> &Simulator<MB, EB, RecB> {
+        &self.b
+    }
+
+    /// getter for the simulator b as mutable
+    pub fn b_as_mut(&mut self) -> &mut Simulator<MB, EB, RecB> {
+        &mut self.b
+    }
+
+    /// index of the frame of the simulator a
+    pub fn frame_index(&self) -> FrameIndex {
+        self.a.frame_index()
+    }
+
+    /// count of the events which are transferred from b to a
+    pub fn transferred_to_a(&self) -> u64 {
+        self.transferred_to_a
+    }
+
+    /// count of the events which are transferred from a to b
+    pub fn transferred_to_b(&self) -> u64 {
+        self.transferred_to_b
+    }
+
+    /// take the simulators
+    pub fn into_parts(self) -> (Simulator<MA, EA, RecA>, Simulator<MB, EB, RecB>) {
+        (self.a, self.b)
+    }
+
+    /// simulate one frame of both simulators with the handlers and exchange the fired events
+    pub fn step<R: Rng + ?Sized, HA, HB>(
+        &mut self,
+        rng: &mut R,
+        handler_a: HA,
+        handler_b: HB,
+    ) -> BridgeOutcome
+    where
+        HA: FnMut(&mut R, &mut MA, &mut RecA, &mut EventScheduler<EA>, Vec<(Priority, EA)>),
+        HB: FnMut(&mut R, &mut MB, &mut RecB, &mut EventScheduler<EB>, Vec<(Priority, EB)>),
+    {
+        self.step_buffered(rng, owned_events(handler_a), owned_events(handler_b))
+    }
+
+    /// same as step, but the handlers take the buffer of the fired events
+    pub fn step_buffered<R: Rng + ?Sized, HA, HB>(
+        &mut self,
+        rng: &mut R,
+        mut handler_a: HA,
+        mut handler_b: HB,
+    ) -> BridgeOutcome
+    where
+        HA: FnMut(&mut R, &mut MA, &mut RecA, &mut EventScheduler<EA>, &mut Vec<(Priority, EA)>),
+        HB: FnMut(&mut R, &mut MB, &mut RecB, &mut EventScheduler<EB>, &mut Vec<(Priority, EB)>),
+    {
+        let mut to_b: Vec<(Priority, EB)> = vec![];
+        let a_to_b = &self.a_to_b;
+        let a = self
+            .a
+            .run_step_buffered(rng, |rng, model, recorder, scheduler, events| {
+                // the handler is called again for each cascade in the frame
+                to_b.extend(
+                    events
+                        .iter()
+                        .filter_map(|(priority, event)| a_to_b(*priority, event)),
+                );
+                handler_a(rng, model, recorder, scheduler, events)
+            });
+        let mut to_a: Vec<(Priority, EA)> = vec![];
+        let b_to_a = &self.b_to_a;
+        let b = self
+            .b
+            .run_step_buffered(rng, |rng, model, recorder, scheduler, events| {
+                to_a.extend(
+                    events
+                        .iter()
+                        .filter_map(|(priority, event)| b_to_a(*priority, event)),
+                );
+                handler_b(rng, model, recorder, scheduler, events)
+            });
+
+        let outcome = BridgeOutcome {
+            a,
+            b,
+            to_b: to_b.len(),
+            to_a: to_a.len(),
+        };
+        self.transferred_to_b += to_b.len() as u64;
+        self.transferred_to_a += to_a.len() as u64;
+        let scheduler = self.b.get_scheduler_as_mut();
+        for (priority, event) in to_b {
+            // the immediate schedule is always valid
+            let _ = scheduler.schedule_once(rng, Schedule::Immediate, priority, event);
+        }
+        let scheduler = self.a.get_scheduler_as_mut();
+        for (priority, event) in to_a {
+            let _ = scheduler.schedule_once(rng, Schedule::Immediate, priority, event);
+        }
+        outcome
+    }
+
+    /// simulate frames of both simulators with the handlers and exchange the fired events at the end of each frame
+    pub fn run_n<R: Rng + ?Sized, FC: FrameCounter, HA, HB>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        handler_a: HA,
+        handler_b: HB,
+    ) where
+        HA: FnMut(&mut R, &mut MA, &mut RecA, &mut EventScheduler<EA>, Vec<(Priority, EA)>),
+        HB: FnMut(&mut R, &mut MB, &mut RecB, &mut EventScheduler<EB>, Vec<(Priority, EB)>),
+    {
+        self.run_n_buffered(
+            rng,
+            counter,
+            owned_events(handler_a),
+            owned_events(handler_b),
+        )
+    }
+
+    /// same as run_n, but the handlers take the buffer of the fired events
+    pub fn run_n_buffered<R: Rng + ?Sized, FC: FrameCounter, HA, HB>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        mut handler_a: HA,
+        mut handler_b: HB,
+    ) where
+        HA: FnMut(&mut R, &mut MA, &mut RecA, &mut EventScheduler<EA>, &mut Vec<(Priority, EA)>),
+        HB: FnMut(&mut R, &mut MB, &mut RecB, &mut EventScheduler<EB>, &mut Vec<(Priority, EB)>),
+    {
+        let mut index = FC::start_index();
+        loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break;
+            }
+
+            self.step_buffered(rng, &mut handler_a, &mut handler_b);
+        }
+    }
+}
+
+/// both models step for each fired event
+impl<MA, EA, RecA, MB, EB, RecB> Bridge<MA, EA, RecA, MB, EB, RecB>
+where
+    MA: StepEachEvent<RecA, EA>,
+    EA: Event,
+    MB: StepEachEvent<RecB, EB>,
+    EB: Event,
+{
+    /// simulate one frame of both simulators and exchange the fired events
+    pub fn step_each_event<R: Rng + ?Sized>(&mut self, rng: &mut R) -> BridgeOutcome {
+        self.step_buffered(
+            rng,
+            Simulator::<MA, EA, RecA>::handler_each_event,
+            Simulator::<MB, EB, RecB>::handler_each_event,
+        )
+    }
+
+    /// simulate frames of both simulators and exchange the fired events at the end of each frame
+    pub fn run_n_each_event<R: Rng + ?Sized, FC: FrameCounter>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+    ) {
+        self.run_n_buffered(
+            rng,
+            counter,
+            Simulator::<MA, EA, RecA>::handler_each_event,
+            Simulator::<MB, EB, RecB>::handler_each_event,
+        )
+    }
+}
+
+/// both models step for the fired events in bulk
+impl<MA, EA, RecA, MB, EB, RecB> Bridge<MA, EA, RecA, MB, EB, RecB>
+where
+    MA: BulkEvents<RecA, EA>,
+    EA: Event,
+    MB: BulkEvents<RecB, EB>,
+    EB: Event,
+{
+    /// simulate one frame of both simulators and exchange the fired events
+    pub fn step_in_bulk_event<R: Rng + ?Sized>(&mut self, rng: &mut R) -> BridgeOutcome {
+        self.step_buffered(
+            rng,
+            Simulator::<MA, EA, RecA>::handler_in_bulk_event,
+            Simulator::<MB, EB, RecB>::handler_in_bulk_event,
+        )
+    }
+
+    /// simulate frames of both simulators and exchange the fired events at the end of each frame
+    pub fn run_n_in_bulk_event<R: Rng + ?Sized, FC: FrameCounter>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+    ) {
+        self.run_n_buffered(
+            rng,
+            counter,
+            Simulator::<MA, EA, RecA>::handler_in_bulk_event,
+            Simulator::<MB, EB, RecB>::handler_in_bulk_event,
+        )
+    }
+}
@@ -0,0 +1,264 @@
+//! Generator of the characterization test which pin the recorded run of the model
+
+use crate::trace::TraceRecord;
+use crate::FrameIndex;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// builder of the test source file which re-run the model with the seed
+/// and compare the fired events with the recorded trace and the final digest.
+///
+/// the generated test use `rand::rngs::StdRng` seeded by the seed, so the recorded run must use the same generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacterizationTest {
+    name: String,
+    seed: u64,
+    frames: FrameIndex,
+    imports: Vec<String>,
+    setup: String,
+    frames_method: String,
+    labeler: String,
+    records: Vec<TraceRecord>,
+    digest: Option<(String, String)>,
+}
+
+impl CharacterizationTest {
+    /// create builder of the test function with the name from the trace of the run with the seed.
+    /// the count of frames is the last frame of the trace.
+    pub fn from_trace(name: &str, seed: u64, records: &[TraceRecord]) -> Self {
+        CharacterizationTest {
+            name: name.to_string(),
+            seed,
+            frames: records.iter().map(|record| record.frame).max().unwrap_or(0),
+            imports: vec![],
+            setup: "Simulator::new(&mut rng)".to_string(),
+            frames_method: "frames_each_event".to_string(),
+            labeler: "|event: &_| format!(\"{:?}\", event)".to_string(),
+            records: records.to_vec(),
+            digest: None,
+        }
+    }
+
+    /// count of frames to re-run. use when the last frames fire no event.
+    pub fn frames(mut self, frames: FrameIndex) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// add the path of use declaration, e.g. `my_crate::Timeline`
+    pub fn import(mut self, path: &str) -> Self {
+        self.imports.push(path.to_string());
+        self
+    }
+
+    /// expression which create the simulator with `rng`, e.g. `Simulator::create_from(&mut rng, Timeline::new(), Recorder::default())`
+    pub fn setup(mut self, expression: &str) -> Self {
+        self.setup = expression.to_string();
+        self
+    }
+
+    /// name of the frames method of the simulator, e.g. `frames_in_bulk_event`
+    pub fn frames_method(mut self, method: &str) -> Self {
+        self.frames_method = method.to_string();
+        self
+    }
+
+    /// expression of the closure which label the event as same as the recorded trace.
+    /// the argument of the closure should be annotated as the reference, e.g. `|event: &Event| event.name()`.
+    pub fn labeler(mut self, expression: &str) -> Self {
+        self.labeler = expression.to_string();
+        self
+    }
+
+    /// compare the digest of the expression with `simulator` after the run, e.g. `simulator.get_recorder()`.
+    /// the digest is the value of testing::debug_digest of the expression in the recorded run.
+    pub fn digest(mut self, expression: &str, digest: &str) -> Self {
+        self.digest = Some((expression.to_string(), digest.to_string()));
+        self
+    }
+
+    /// source of the test file
+    pub fn source(&self) -> String {
+        let mut out = String::new();
+        out.push_str("// characterization test which is generated from the recorded run.\n");
+        out.push_str("// regenerate this test when the change of the behavior is intended.\n\n");
+        out.push_str("use rand::SeedableRng;\n");
+        out.push_str("use sim_by_fired_event::Simulator;\n");
+        for import in self.imports.iter() {
+            let _ = writeln!(out, "use {};", import);
+        }
+        let _ = writeln!(out, "\n#[test]\nfn {}() {{", self.name);
+        let _ = writeln!(
+            out,
+            "    let mut rng = rand::rngs::StdRng::seed_from_u64({});",
+            self.seed
+        );
+        let _ = writeln!(out, "    let mut simulator = {};", self.setup);
+        let _ = writeln!(
+            out,
+            "    #[allow(unused_mut)]\n    let mut labeler = {};",
+            self.labeler
+        );
+        out.push_str("    let mut fired: Vec<(u64, u8, String)> = Vec::new();\n");
+        let _ = writeln!(
+            out,
+            "    for report in simulator.{}(&mut rng).take({}) {{",
+            self.frames_method, self.frames
+        );
+        out.push_str("        for (priority, event) in report.fired_events.iter() {\n");
+        out.push_str(
+            "            fired.push((report.index, u8::from(*priority), labeler(event)));\n",
+        );
+        out.push_str("        }\n    }\n\n");
+        out.push_str("    let expected: &[(u64, u8, &str)] = &[\n");
+        for record in self.records.iter() {
+            let _ = writeln!(
+                out,
+                "        ({}, {}, {:?}),",
+                record.frame,
+                u8::from(record.priority),
+                record.label
+            );
+        }
+        out.push_str("    ];\n");
+        out.push_str(
+            "    assert_eq!(fired.len(), expected.len(), \"count of fired events is different\");\n",
+        );
+        out.push_str("    for (index, (actual, expected)) in fired.iter().zip(expected.iter()).enumerate() {\n");
+        out.push_str("        assert_eq!(\n            (actual.0, actual.1, actual.2.as_str()),\n            *expected,\n            \"fired event at {} is different\",\n            index\n        );\n    }\n");
+        if let Some((expression, digest)) = &self.digest {
+            let _ = writeln!(
+                out,
+                "    assert_eq!(\n        sim_by_fired_event::testing::debug_digest({}),\n        {:?},\n        \"final digest is different\"\n    );",
+                expression, digest
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// write the source of the test file, e.g. into `tests/` directory
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.source())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Event, EventScheduler, EventTimer, Priority};
+    use crate::model::{Model, StepEachEvent};
+    use crate::testing::debug_digest;
+    use crate::Simulator;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Ping(u32);
+
+    impl Event for Ping {}
+
+    /// model which ping three times at the random interval
+    #[derive(Debug, Default)]
+    struct Pinger;
+
+    impl Model<Vec<u32>> for Pinger {
+        type ModelEvent = Ping;
+
+        fn initialize<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            _: &mut Vec<u32>,
+            scheduler: &mut EventScheduler<Ping>,
+        ) {
+            scheduler
+                .repeat(
+                    rng,
+                    3,
+                    EventTimer::Uniform(1, 3, true),
+                    Priority::NORMAL,
+                    Ping(0),
+                )
+                .unwrap();
+        }
+    }
+
+    impl StepEachEvent<Vec<u32>, Ping> for Pinger {
+        fn step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<u32>,
+            _: &mut EventScheduler<Ping>,
+            _: Priority,
+            fired_event: Ping,
+        ) {
+            recorder.push(fired_event.0);
+        }
+    }
+
+    /// recorded run with the seed
+    fn recorded_run(seed: u64) -> (Vec<TraceRecord>, FrameIndex, String) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut simulator = Simulator::create_from(&mut rng, Pinger, vec![]);
+        simulator.keep_fired_history(|event: &Ping| format!("{:?}", event));
+        simulator.run_n_each_event(&mut rng, 10_u64);
+        (
+            simulator.fired_history().to_vec(),
+            simulator.frame_index(),
+            debug_digest(simulator.get_recorder()),
+        )
+    }
+
+    #[test]
+    fn source_pin_recorded_run() {
+        let (records, frames, digest) = recorded_run(3);
+        assert_eq!(records.len(), 3);
+        let source = CharacterizationTest::from_trace("pinger_is_stable", 3, &records)
+            .frames(frames)
+            .import("my_crate::Pinger")
+            .setup("Simulator::create_from(&mut rng, Pinger, vec![])")
+            .digest("simulator.get_recorder()", &digest)
+            .source();
+
+        assert!(source.contains("use my_crate::Pinger;\n"));
+        assert!(source.contains("#[test]\nfn pinger_is_stable() {\n"));
+        assert!(source.contains("rand::rngs::StdRng::seed_from_u64(3);"));
+        assert!(source
+            .contains("let mut simulator = Simulator::create_from(&mut rng, Pinger, vec![]);"));
+        assert!(source.contains("simulator.frames_each_event(&mut rng).take(10)"));
+        for record in records.iter() {
+            let expected = format!("        ({}, 128, \"Ping(0)\"),\n", record.frame);
+            assert!(source.contains(&expected), "{} is not pinned", expected);
+        }
+        assert!(source.contains(&format!(
+            "{:?},\n        \"final digest is different\"",
+            digest
+        )));
+    }
+
+    #[test]
+    fn from_trace_run_until_last_fired_frame() {
+        let (records, _, _) = recorded_run(5);
+        let last = records.last().unwrap().frame;
+        let source = CharacterizationTest::from_trace("pinger", 5, &records).source();
+        assert!(source.contains(&format!(".take({})", last)));
+        assert!(!source.contains("final digest"));
+    }
+
+    #[test]
+    fn write_to_create_directory() {
+        let (records, _, _) = recorded_run(0);
+        let test = CharacterizationTest::from_trace("pinger", 0, &records);
+        let dir = std::env::temp_dir().join(format!("characterize-{}", std::process::id()));
+        let path = dir.join("tests").join("pinger.rs");
+        test.write_to(&path).unwrap();
+        let written = fs::read_to_string(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(written.unwrap(), test.source());
+    }
+}
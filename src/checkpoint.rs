@@ -0,0 +1,288 @@
+//! Checkpoint of the simulation and verification of the determinism after restore
+
+use crate::closure::CallbackEvent;
+use crate::event::{Event, EventClass, EventScheduler, Priority};
+#[cfg(feature = "rayon")]
+use crate::model::ParStepEachEvent;
+use crate::model::{BulkEvents, MixedStep, Model, StepByPhase, StepByPriorityGroup, StepEachEvent};
+use crate::trace::{diff_records, TraceDiff, TraceRecord};
+use crate::{owned_events, FrameIndex, Simulator};
+use rand::Rng;
+
+/// state of the simulator and the random number generator between frames
+#[derive(Debug, Clone)]
+pub struct Checkpoint<M, E, Rec, R>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    simulator: Simulator<M, E, Rec>,
+    rng: R,
+}
+
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: Model<Rec, ModelEvent = E> + Clone,
+    E: Event + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// take the checkpoint of the current state.
+    /// the injection is not taken, so the checkpoint never drain the events which are injected into the simulator.
+    pub fn capture(simulator: &Simulator<M, E, Rec>, rng: &R) -> Self {
+        let mut simulator = simulator.clone();
+        simulator.injection = None;
+        Checkpoint {
+            simulator,
+            rng: rng.clone(),
+        }
+    }
+
+    /// index of the last finished frame at the checkpoint
+    pub fn frame_index(&self) -> FrameIndex {
+        self.simulator.frame_index()
+    }
+
+    /// getter for simulator at the checkpoint
+    pub fn get_simulator(&self) -> &Simulator<M, E, Rec> {
+        &self.simulator
+    }
+
+    /// restore the simulator and the random number generator. the checkpoint can be restored again.
+    pub fn restore(&self) -> (Simulator<M, E, Rec>, R) {
+        (self.simulator.clone(), self.rng.clone())
+    }
+
+    /// take the simulator and the random number generator
+    pub fn into_parts(self) -> (Simulator<M, E, Rec>, R) {
+        (self.simulator, self.rng)
+    }
+
+    /// re-run from the checkpoint and compare the fired events with the original trace after the checkpoint.
+    ///
+    /// the records of the original trace at or before the checkpoint are ignored,
+    /// and the restored simulation run until the last frame of the original trace.
+    pub fn verify<L, H>(&self, original: &[TraceRecord], labeler: L, handler: H) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.verify_buffered(original, labeler, owned_events(handler))
+    }
+
+    /// same as verify, but the handler take the buffer of the fired events
+    pub fn verify_buffered<L, H>(
+        &self,
+        original: &[TraceRecord],
+        mut labeler: L,
+        mut handler: H,
+    ) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let start = self.frame_index();
+        let tail: Vec<TraceRecord> = original
+            .iter()
+            .filter(|record| record.frame > start)
+            .cloned()
+            .collect();
+        let end = tail
+            .iter()
+            .map(|record| record.frame)
+            .max()
+            .unwrap_or(start);
+
+        let (mut simulator, mut rng) = self.restore();
+        let mut replayed = vec![];
+        for report in simulator
+            .frames_buffered(&mut rng, &mut handler)
+            .take((end - start) as usize)
+        {
+            for (priority, event) in report.fired_events.iter() {
+                replayed.push(TraceRecord {
+                    frame: report.index,
+                    priority: *priority,
+                    label: labeler(event),
+                });
+            }
+        }
+        diff_records(&tail, &replayed)
+    }
+}
+
+/// verify for fired event with calculate in bulk
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: BulkEvents<Rec, E> + Clone,
+    E: Event + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_in_bulk_event<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_in_bulk_event)
+    }
+}
+
+/// verify for fired event with calculate each event
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: StepEachEvent<Rec, E> + Clone,
+    E: Event + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_each_event<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_each_event)
+    }
+}
+
+/// verify for fired event with calculate each group of the same priority
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: StepByPriorityGroup<Rec, E> + Clone,
+    E: Event + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_by_priority<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_by_priority)
+    }
+}
+
+/// verify for fired event with calculate phase by phase
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: StepByPhase<Rec, E> + Clone,
+    E: Event + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_phased<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_phased)
+    }
+}
+
+/// verify for fired event with calculate each event or in bulk by the class of the event
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: MixedStep<Rec, E> + Clone,
+    E: EventClass + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_mixed<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_mixed)
+    }
+}
+
+/// verify for the closure events by calling the callbacks
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: Model<Rec, ModelEvent = E> + Clone,
+    E: CallbackEvent<M, Rec> + Clone,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_closure<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_closure)
+    }
+}
+
+/// verify for the disjoint parts of the model in parallel
+#[cfg(feature = "rayon")]
+impl<M, E, Rec, R> Checkpoint<M, E, Rec, R>
+where
+    M: ParStepEachEvent<Rec, E> + Clone,
+    E: Event + Clone + Send,
+    Rec: Clone,
+    R: Rng + Clone,
+{
+    /// re-run from the checkpoint and compare with the original trace
+    pub fn verify_par_each_event<L>(&self, original: &[TraceRecord], labeler: L) -> TraceDiff
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.verify_buffered(original, labeler, Simulator::handler_par_each_event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Ping;
+
+    impl Event for Ping {}
+
+    #[derive(Debug, Clone)]
+    struct Listener;
+
+    impl Model<Vec<Ping>> for Listener {
+        type ModelEvent = Ping;
+
+        fn initialize<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            _: &mut Vec<Ping>,
+            _: &mut EventScheduler<Ping>,
+        ) {
+        }
+    }
+
+    impl StepEachEvent<Vec<Ping>, Ping> for Listener {
+        fn step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<Ping>,
+            _: &mut EventScheduler<Ping>,
+            _: Priority,
+            fired_event: Ping,
+        ) {
+            recorder.push(fired_event);
+        }
+    }
+
+    #[test]
+    fn restored_simulator_not_drain_injections() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = Simulator::create_from(&mut rng, Listener, vec![]);
+        let injector = simulator.injector();
+        let checkpoint = Checkpoint::capture(&simulator, &rng);
+
+        injector.immediate(Priority::NORMAL, Ping).unwrap();
+        let (mut restored, mut restored_rng) = checkpoint.restore();
+        restored.run_n_each_event(&mut restored_rng, 2_u64);
+        assert!(restored.get_recorder().is_empty());
+
+        simulator.run_n_each_event(&mut rng, 2_u64);
+        assert_eq!(simulator.get_recorder(), &vec![Ping]);
+    }
+}
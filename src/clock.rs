@@ -0,0 +1,100 @@
+//! Virtual clock which is derived from the frame index
+
+use crate::event::{EventTimer, LocalEventTime};
+use crate::FrameIndex;
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// clock for the model which use the time instead of SystemTime::now().
+///
+/// the time is calculated from the frame index and the duration of one frame,
+/// so the time is reproducible and independent of the speed of the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualClock {
+    epoch: SystemTime,
+    tick: Duration,
+    frame: FrameIndex,
+}
+
+impl VirtualClock {
+    /// create clock which start at epoch with the duration of one frame
+    pub fn new(epoch: SystemTime, tick: Duration) -> Self {
+        VirtualClock {
+            epoch,
+            tick,
+            frame: 0,
+        }
+    }
+
+    /// create clock which start at the unix epoch with the duration of one frame
+    pub fn from_unix_epoch(tick: Duration) -> Self {
+        Self::new(UNIX_EPOCH, tick)
+    }
+
+    /// getter for the time at frame 0
+    pub fn epoch(&self) -> SystemTime {
+        self.epoch
+    }
+
+    /// getter for the duration of one frame
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// getter for the current frame
+    pub fn frame(&self) -> FrameIndex {
+        self.frame
+    }
+
+    /// move the clock to next frame
+    pub fn advance(&mut self) {
+        self.frame += 1;
+    }
+
+    /// move the clock to the frame
+    pub fn set_frame(&mut self, frame: FrameIndex) {
+        self.frame = frame;
+    }
+
+    /// current time
+    pub fn now(&self) -> SystemTime {
+        self.at(self.frame)
+    }
+
+    /// time at the frame
+    pub fn at(&self, frame: FrameIndex) -> SystemTime {
+        self.epoch + self.elapsed_at(frame)
+    }
+
+    /// elapsed time from the epoch to now
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_at(self.frame)
+    }
+
+    /// elapsed time from the epoch to the frame
+    pub fn elapsed_at(&self, frame: FrameIndex) -> Duration {
+        self.tick.mul_f64(frame as f64)
+    }
+
+    /// count of frames which cover the duration. the partial frame is counted as one frame.
+    /// if the tick is zero, the duration over zero is LocalEventTime::MAX.
+    pub fn frames_for(&self, duration: Duration) -> LocalEventTime {
+        let tick = self.tick.as_nanos();
+        if tick == 0 {
+            return if duration.is_zero() {
+                0
+            } else {
+                LocalEventTime::MAX
+            };
+        }
+        let nanos = duration.as_nanos();
+        // round up to cover the duration
+        let ticks = nanos / tick + u128::from(nanos % tick != 0);
+        LocalEventTime::try_from(ticks).unwrap_or(LocalEventTime::MAX)
+    }
+
+    /// timer which fire after the duration
+    pub fn timer_for(&self, duration: Duration) -> EventTimer {
+        EventTimer::Time(self.frames_for(duration))
+    }
+}
@@ -0,0 +1,137 @@
+//! Event which carry the callback, so the quick script need not define the event type and the step
+
+use crate::event::{Event, EventId, EventScheduler, Priority, Schedule, ScheduleEventError};
+use rand::{Rng, RngCore};
+use std::any;
+use std::fmt;
+use std::sync::Arc;
+
+/// callback which is called when the event fire
+type Callback<M, Rec> = Arc<
+    dyn Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<ClosureEvent<M, Rec>>)
+        + Send
+        + Sync,
+>;
+
+/// event which call the callback with the model, the recorder and the scheduler when fired.
+///
+/// the callback is shared by the clones, so the event can be scheduled as the recurring event.
+/// the simulator of this event run by run_XXX_closure methods.
+pub struct ClosureEvent<M, Rec> {
+    label: &'static str,
+    callback: Callback<M, Rec>,
+}
+
+impl<M, Rec> ClosureEvent<M, Rec> {
+    /// initializer with the label for the debug and the callback
+    pub fn new<F>(label: &'static str, callback: F) -> Self
+    where
+        F: Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<ClosureEvent<M, Rec>>)
+            + Send
+            + Sync
+            + 'static,
+    {
+        ClosureEvent {
+            label,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// label for the debug
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+}
+
+/// event which call the callback with the model, the recorder and the scheduler of the event when fired
+pub trait CallbackEvent<M, Rec>: Event + Sized {
+    /// call the callback
+    fn call(
+        &self,
+        rng: &mut dyn RngCore,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self>,
+    );
+}
+
+impl<M, Rec> CallbackEvent<M, Rec> for ClosureEvent<M, Rec> {
+    fn call(
+        &self,
+        rng: &mut dyn RngCore,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self>,
+    ) {
+        (self.callback)(rng, model, recorder, scheduler)
+    }
+}
+
+impl<M, Rec> Clone for ClosureEvent<M, Rec> {
+    fn clone(&self) -> Self {
+        ClosureEvent {
+            label: self.label,
+            callback: Arc::clone(&self.callback),
+        }
+    }
+}
+
+impl<M, Rec> fmt::Debug for ClosureEvent<M, Rec> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ClosureEvent").field(&self.label).finish()
+    }
+}
+
+impl<M, Rec> Event for ClosureEvent<M, Rec> {}
+
+impl<M, Rec> EventScheduler<ClosureEvent<M, Rec>> {
+    /// store the callback with scheduling. the label is the type name of the callback.
+    pub fn schedule_callback<R, F>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        callback: F,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        R: Rng + ?Sized,
+        F: Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<ClosureEvent<M, Rec>>)
+            + Send
+            + Sync
+            + 'static,
+    {
+        let event = ClosureEvent::new(any::type_name::<F>(), callback);
+        self.schedule(rng, schedule, priority, event)
+    }
+
+    /// store the callback which is called at immediate timing
+    pub fn immediate_callback<R, F>(
+        &mut self,
+        rng: &mut R,
+        priority: Priority,
+        callback: F,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        R: Rng + ?Sized,
+        F: Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<ClosureEvent<M, Rec>>)
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.schedule_callback(rng, Schedule::Immediate, priority, callback)
+    }
+}
+
+/// create the closure event from the callback which take the rng, the model, the recorder and the scheduler.
+///
+/// the label is the location of the macro call if not specified, e.g. `callback!("spawn", |rng, model: &mut MyModel, recorder, scheduler| ...)`.
+/// the type of the model parameter is needed when the closure cannot infer it.
+#[macro_export]
+macro_rules! callback {
+    ($label:literal, $callback:expr $(,)?) => {
+        $crate::closure::ClosureEvent::new($label, $callback)
+    };
+    ($callback:expr $(,)?) => {
+        $crate::closure::ClosureEvent::new(concat!(file!(), ":", line!()), $callback)
+    };
+}
@@ -0,0 +1,410 @@
+//! Continuous time simulator which advance directly from the event to the next event without frames
+
+use crate::event::{Event, Priority, ScheduleEventError};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::mem;
+
+/// time in the continuous time simulation
+pub type SimTime = f64;
+
+/// scheduled event in the heap
+#[derive(Debug, Clone)]
+struct TimedEntry<E: Event> {
+    time: SimTime,
+    priority: Priority,
+    /// scheduled order for the events which fire at same time with same priority
+    sequence: u64,
+    event: E,
+}
+
+impl<E: Event> PartialEq for TimedEntry<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<E: Event> Eq for TimedEntry<E> {}
+
+impl<E: Event> PartialOrd for TimedEntry<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E: Event> Ord for TimedEntry<E> {
+    /// the entry which fire first is the greatest because the heap is max-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the time is never NaN because it is checked when scheduled
+        other
+            .time
+            .partial_cmp(&self.time)
+            .unwrap_or(Ordering::Equal)
+            .then(self.priority.cmp(&other.priority))
+            .then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// event scheduler which keep the events with the time of fire
+#[derive(Debug, Clone)]
+pub struct ContinuousScheduler<E: Event> {
+    now: SimTime,
+    heap: BinaryHeap<TimedEntry<E>>,
+    next_sequence: u64,
+}
+
+impl<E: Event> ContinuousScheduler<E> {
+    /// initializer
+    pub(crate) fn new() -> Self {
+        ContinuousScheduler {
+            now: 0.0,
+            heap: BinaryHeap::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// remove all events and go back to time 0 with keeping the allocated capacity
+    pub(crate) fn reset(&mut self) {
+        self.now = 0.0;
+        self.heap.clear();
+        self.next_sequence = 0;
+    }
+
+    /// take the next event with advancing the time to the time of the event
+    fn pop(&mut self) -> Option<(SimTime, Priority, E)> {
+        self.heap.pop().map(|entry| {
+            self.now = entry.time;
+            (entry.time, entry.priority, entry.event)
+        })
+    }
+
+    /// getter for the current time
+    pub fn now(&self) -> SimTime {
+        self.now
+    }
+
+    /// time of the event which fire next. if no event, then None.
+    pub fn next_time(&self) -> Option<SimTime> {
+        self.heap.peek().map(|entry| entry.time)
+    }
+
+    /// judge exist scheduled event
+    pub fn have_event(&self) -> bool {
+        !self.heap.is_empty()
+    }
+
+    /// get length of scheduled events
+    pub fn count(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// clear all scheduled events
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    /// retains only the scheduled events specified by the predicate.
+    pub fn retain<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(SimTime, Priority, &E) -> bool,
+    {
+        let mut entries = mem::take(&mut self.heap).into_vec();
+        entries.retain(|entry| predicate(entry.time, entry.priority, &entry.event));
+        self.heap = BinaryHeap::from(entries);
+    }
+
+    /// store event which fire at the time.
+    /// the events at same time fire in order of the priority from the highest and then in scheduled order.
+    /// if the time is NaN or past, then error.
+    pub fn schedule_at(
+        &mut self,
+        time: SimTime,
+        priority: Priority,
+        event: E,
+    ) -> Result<(), ScheduleEventError> {
+        if time.is_nan() || time < self.now {
+            return Err(ScheduleEventError::CannotFireEvent);
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.heap.push(TimedEntry {
+            time,
+            priority,
+            sequence,
+            event,
+        });
+        Ok(())
+    }
+
+    /// store event which fire after the delay from now. if the delay is NaN or negative, then error.
+    pub fn schedule_in(
+        &mut self,
+        delay: SimTime,
+        priority: Priority,
+        event: E,
+    ) -> Result<(), ScheduleEventError> {
+        if delay.is_nan() || delay < 0.0 {
+            return Err(ScheduleEventError::CannotFireEvent);
+        }
+        self.schedule_at(self.now + delay, priority, event)
+    }
+
+    /// store event which fire at immediate timing. the event fire at now after the events which is already scheduled at now.
+    pub fn immediate(&mut self, priority: Priority, event: E) -> Result<(), ScheduleEventError> {
+        self.schedule_at(self.now, priority, event)
+    }
+
+    /// store event which fire after the delay in uniform distribution of [low, high).
+    /// if the range is empty or not finite, then error.
+    pub fn uniform<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        low: SimTime,
+        high: SimTime,
+        priority: Priority,
+        event: E,
+    ) -> Result<(), ScheduleEventError> {
+        if !(low.is_finite() && high.is_finite() && low < high) {
+            return Err(ScheduleEventError::CannotFireEvent);
+        }
+        let delay = rng.gen_range(low..high);
+        self.schedule_in(delay, priority, event)
+    }
+
+    /// store event which fire after the delay in exponential distribution with the rate, e.g. the inter-arrival time of Poisson process.
+    /// if the rate is not positive and finite, then error.
+    pub fn exponential<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        rate: f64,
+        priority: Priority,
+        event: E,
+    ) -> Result<(), ScheduleEventError> {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(ScheduleEventError::CannotFireEvent);
+        }
+        let uniform: f64 = rng.gen();
+        let delay = -(1.0 - uniform).ln() / rate;
+        self.schedule_in(delay, priority, event)
+    }
+}
+
+/// can store model as ContinuousTimeSimulator's model.
+///
+/// the model is same as the model of the frame simulator, but the model is called for each fired event
+/// and the time of the event is the current time of the scheduler.
+pub trait ContinuousModel<Rec> {
+    /// usable event's type
+    type ModelEvent: Event;
+
+    /// initialize model and schedule when create simulator
+    fn initialize<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut ContinuousScheduler<Self::ModelEvent>,
+    );
+
+    /// action for each fired event. the time of the event is `scheduler.now()`
+    fn step_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut ContinuousScheduler<Self::ModelEvent>,
+        priority: Priority,
+        event: Self::ModelEvent,
+    );
+
+    #[allow(unused_variables)]
+    /// action when the time is advanced to the time without event, e.g. the end time of run_to
+    fn advance_to(&mut self, recorder: &mut Rec, time: SimTime) {
+        // usually not use
+    }
+}
+
+/// simulator which advance the time directly to the next event.
+/// there is no frame, so the time of the event is not rounded to the frame grid.
+#[derive(Clone)]
+pub struct ContinuousTimeSimulator<M, E, Rec>
+where
+    M: ContinuousModel<Rec, ModelEvent = E>,
+    E: Event,
+{
+    model: M,
+    recorder: Rec,
+    scheduler: ContinuousScheduler<E>,
+    fired: u64,
+}
+
+impl<M, E, Rec> fmt::Debug for ContinuousTimeSimulator<M, E, Rec>
+where
+    M: ContinuousModel<Rec, ModelEvent = E> + fmt::Debug,
+    E: Event + fmt::Debug,
+    Rec: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContinuousTimeSimulator")
+            .field("model", &self.model)
+            .field("recorder", &self.recorder)
+            .field("scheduler", &self.scheduler)
+            .field("fired", &self.fired)
+            .finish()
+    }
+}
+
+impl<M, E, Rec> ContinuousTimeSimulator<M, E, Rec>
+where
+    M: ContinuousModel<Rec, ModelEvent = E>,
+    E: Event,
+{
+    /// create as default
+    pub fn new<R: Rng + ?Sized>(rng: &mut R) -> Self
+    where
+        Rec: Default,
+        M: Default,
+    {
+        Self::create_from(rng, Default::default(), Default::default())
+    }
+
+    /// create simulator from model
+    pub fn create_from<R: Rng + ?Sized>(rng: &mut R, model: M, recorder: Rec) -> Self {
+        let mut sim = ContinuousTimeSimulator {
+            model,
+            recorder,
+            scheduler: ContinuousScheduler::new(),
+            fired: 0,
+        };
+        sim.model
+            .initialize(rng, &mut sim.recorder, &mut sim.scheduler);
+        sim
+    }
+
+    /// clear the scheduler, go back to time 0 and reinitialize the model in place for the next run
+    pub fn reset<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.scheduler.reset();
+        self.fired = 0;
+        self.model
+            .initialize(rng, &mut self.recorder, &mut self.scheduler);
+    }
+
+    /// getter for model
+    pub fn get_model(&self) -> &M {
+        &self.model
+    }
+
+    /// getter for model as mutable
+    pub fn get_model_as_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+
+    /// getter for scheduler
+    pub fn get_scheduler(&self) -> &ContinuousScheduler<E> {
+        &self.scheduler
+    }
+
+    /// getter for scheduler as mutable
+    pub fn get_scheduler_as_mut(&mut self) -> &mut ContinuousScheduler<E> {
+        &mut self.scheduler
+    }
+
+    /// getter for recorder
+    pub fn get_recorder(&self) -> &Rec {
+        &self.recorder
+    }
+
+    /// getter for recorder as mutable
+    pub fn get_recorder_as_mut(&mut self) -> &mut Rec {
+        &mut self.recorder
+    }
+
+    /// getter for the current time
+    pub fn now(&self) -> SimTime {
+        self.scheduler.now()
+    }
+
+    /// count of the fired events
+    pub fn fired_events(&self) -> u64 {
+        self.fired
+    }
+
+    /// decompose simulator into model, recorder and scheduler
+    pub fn into_parts(self) -> (M, Rec, ContinuousScheduler<E>) {
+        (self.model, self.recorder, self.scheduler)
+    }
+
+    /// fire the next event. return the time of the event. if no event, then None.
+    pub fn run_step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<SimTime> {
+        let (time, priority, event) = self.scheduler.pop()?;
+        self.fired += 1;
+        self.model.step_event(
+            rng,
+            &mut self.recorder,
+            &mut self.scheduler,
+            priority,
+            event,
+        );
+        Some(time)
+    }
+
+    /// fire the events up to the count. return the count of the fired events.
+    pub fn run_events<R: Rng + ?Sized>(&mut self, rng: &mut R, count: u64) -> u64 {
+        let mut fired = 0;
+        while fired < count && self.run_step(rng).is_some() {
+            fired += 1;
+        }
+        fired
+    }
+
+    /// fire the events at or before the end time and advance the time to the end time
+    pub fn run_to<R: Rng + ?Sized>(&mut self, rng: &mut R, end: SimTime) {
+        self.fired += advance(
+            rng,
+            &mut self.model,
+            &mut self.recorder,
+            &mut self.scheduler,
+            end,
+        );
+    }
+
+    /// fire the events while condition is true and the event exists
+    pub fn run_until<R: Rng + ?Sized, F>(&mut self, rng: &mut R, mut can_continue: F)
+    where
+        F: FnMut(&M, &Rec, &ContinuousScheduler<E>) -> bool,
+    {
+        while can_continue(&self.model, &self.recorder, &self.scheduler) {
+            if self.run_step(rng).is_none() {
+                break;
+            }
+        }
+    }
+}
+
+/// fire the events at or before the end time and advance the time to the end time.
+/// return the count of the fired events.
+pub(crate) fn advance<M, E, Rec, R>(
+    rng: &mut R,
+    model: &mut M,
+    recorder: &mut Rec,
+    scheduler: &mut ContinuousScheduler<E>,
+    end: SimTime,
+) -> u64
+where
+    M: ContinuousModel<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng + ?Sized,
+{
+    let mut fired = 0;
+    while scheduler.next_time().map_or(false, |time| time <= end) {
+        // the next event exists
+        let (_, priority, event) = scheduler.pop().unwrap();
+        model.step_event(rng, recorder, scheduler, priority, event);
+        fired += 1;
+    }
+    if end > scheduler.now {
+        scheduler.now = end;
+        model.advance_to(recorder, end);
+    }
+    fired
+}
@@ -0,0 +1,140 @@
+//! Control of the running simulation from another thread or by the deadline
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// reason why the run stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// all frames are simulated
+    Finished,
+    /// the condition of the run stop the run, e.g. the condition of run_until or run_while
+    ConditionMet,
+    /// the run is aborted by the controller before the frame
+    Aborted,
+    /// the deadline is passed before the frame
+    DeadlineExceeded,
+    /// the step of the model fail and the policy abort the run
+    StepFailed,
+    /// the start frame of the run is not the next frame of the simulator, so no frame is simulated
+    StartMismatch,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Finished => write!(f, "finished"),
+            StopReason::ConditionMet => write!(f, "condition met"),
+            StopReason::Aborted => write!(f, "aborted"),
+            StopReason::DeadlineExceeded => write!(f, "deadline exceeded"),
+            StopReason::StepFailed => write!(f, "step failed"),
+            StopReason::StartMismatch => write!(f, "start mismatch"),
+        }
+    }
+}
+
+/// state which is shared between the controller and the token
+#[derive(Debug, Default)]
+struct ControlState {
+    paused: bool,
+    steps: u64,
+    aborted: bool,
+}
+
+/// controller which pause, resume, single-step and abort the controlled run.
+///
+/// the run check the token before each frame, so the frame in simulating is always finished.
+/// the controller can be cloned and sent to another thread.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationController {
+    shared: Arc<(Mutex<ControlState>, Condvar)>,
+}
+
+impl SimulationController {
+    /// initializer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// token which is checked by the controlled run
+    pub fn token(&self) -> ControlToken {
+        ControlToken {
+            shared: self.shared.clone(),
+        }
+    }
+
+    fn update<F: FnOnce(&mut ControlState)>(&self, f: F) {
+        let (state, condvar) = &*self.shared;
+        f(&mut lock(state));
+        condvar.notify_all();
+    }
+
+    /// pause the run before the next frame
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    /// resume the paused run
+    pub fn resume(&self) {
+        self.update(|state| {
+            state.paused = false;
+            state.steps = 0;
+        });
+    }
+
+    /// simulate one more frame while the run is paused
+    pub fn step(&self) {
+        self.update(|state| state.steps += 1);
+    }
+
+    /// stop the run before the next frame. the paused run is also stopped.
+    pub fn abort(&self) {
+        self.update(|state| state.aborted = true);
+    }
+
+    /// judge the run is paused
+    pub fn is_paused(&self) -> bool {
+        lock(&self.shared.0).paused
+    }
+
+    /// judge the run is aborted
+    pub fn is_aborted(&self) -> bool {
+        lock(&self.shared.0).aborted
+    }
+}
+
+/// token which the controlled run check before each frame
+#[derive(Debug, Clone)]
+pub struct ControlToken {
+    shared: Arc<(Mutex<ControlState>, Condvar)>,
+}
+
+impl ControlToken {
+    /// wait while the run is paused. return false when the run is aborted.
+    pub fn wait(&self) -> bool {
+        let (state, condvar) = &*self.shared;
+        let mut state = lock(state);
+        loop {
+            if state.aborted {
+                return false;
+            }
+            if !state.paused {
+                return true;
+            }
+            if state.steps > 0 {
+                state.steps -= 1;
+                return true;
+            }
+            state = condvar
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+}
+
+/// lock the state even if the other thread is panicked while locking
+fn lock(state: &Mutex<ControlState>) -> MutexGuard<'_, ControlState> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
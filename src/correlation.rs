@@ -0,0 +1,207 @@
+//! Correlation of request events and reply events
+
+use crate::event::{Event, EventScheduler, Priority, Schedule, ScheduleEventError};
+use crate::FrameIndex;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+/// identifier which is carried by the request event and the reply event
+pub type CorrelationId = u64;
+
+/// request which is replied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedRequest<T> {
+    /// identifier of the request
+    pub id: CorrelationId,
+    /// index of the frame which the request is sent at
+    pub requested_at: FrameIndex,
+    /// index of the frame which the reply is received at
+    pub replied_at: FrameIndex,
+    /// data which is attached to the request
+    pub data: T,
+}
+
+impl<T> CompletedRequest<T> {
+    /// count of frames from the request to the reply
+    pub fn latency(&self) -> FrameIndex {
+        self.replied_at.saturating_sub(self.requested_at)
+    }
+}
+
+/// tracker of the outstanding requests which match the replies by the correlation id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correlator<T = ()> {
+    next_id: CorrelationId,
+    outstanding: BTreeMap<CorrelationId, (FrameIndex, T)>,
+    latencies: Vec<FrameIndex>,
+    unmatched_replies: usize,
+    expired: usize,
+}
+
+impl<T> Default for Correlator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Correlator<T> {
+    /// initializer
+    pub fn new() -> Self {
+        Correlator {
+            next_id: 0,
+            outstanding: BTreeMap::new(),
+            latencies: vec![],
+            unmatched_replies: 0,
+            expired: 0,
+        }
+    }
+
+    /// register the request with the attached data and return the new correlation id
+    pub fn request_with(&mut self, frame: FrameIndex, data: T) -> CorrelationId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.outstanding.insert(id, (frame, data));
+        id
+    }
+
+    /// register the request and schedule the request event which carry the correlation id
+    #[allow(clippy::too_many_arguments)]
+    pub fn schedule_request_with<E, R, F>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        frame: FrameIndex,
+        schedule: Schedule,
+        priority: Priority,
+        data: T,
+        make_event: F,
+    ) -> Result<CorrelationId, ScheduleEventError>
+    where
+        E: Event + Clone,
+        R: Rng + ?Sized,
+        F: FnOnce(CorrelationId) -> E,
+    {
+        let id = self.next_id;
+        scheduler.schedule(rng, schedule, priority, make_event(id))?;
+        Ok(self.request_with(frame, data))
+    }
+
+    /// match the reply with the outstanding request.
+    /// if the request is not outstanding, then the reply is counted as unmatched and return None.
+    pub fn reply(&mut self, id: CorrelationId, frame: FrameIndex) -> Option<CompletedRequest<T>> {
+        match self.outstanding.remove(&id) {
+            Some((requested_at, data)) => {
+                let completed = CompletedRequest {
+                    id,
+                    requested_at,
+                    replied_at: frame,
+                    data,
+                };
+                self.latencies.push(completed.latency());
+                Some(completed)
+            }
+            None => {
+                self.unmatched_replies += 1;
+                None
+            }
+        }
+    }
+
+    /// forget the outstanding request without reply
+    pub fn cancel(&mut self, id: CorrelationId) -> Option<T> {
+        self.outstanding.remove(&id).map(|(_, data)| data)
+    }
+
+    /// forget the outstanding requests which wait more than max_age frames at the frame
+    pub fn expire(&mut self, frame: FrameIndex, max_age: FrameIndex) -> Vec<(CorrelationId, T)> {
+        let expired_ids: Vec<CorrelationId> = self
+            .outstanding
+            .iter()
+            .filter(|(_, (requested_at, _))| frame.saturating_sub(*requested_at) > max_age)
+            .map(|(id, _)| *id)
+            .collect();
+        self.expired += expired_ids.len();
+        expired_ids
+            .into_iter()
+            .filter_map(|id| self.outstanding.remove(&id).map(|(_, data)| (id, data)))
+            .collect()
+    }
+
+    /// judge the request is waiting for the reply
+    pub fn is_outstanding(&self, id: CorrelationId) -> bool {
+        self.outstanding.contains_key(&id)
+    }
+
+    /// count of the requests which wait for the reply
+    pub fn outstanding_count(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// identifiers of the requests which wait for the reply in ascending order
+    pub fn outstanding_ids(&self) -> Vec<CorrelationId> {
+        self.outstanding.keys().copied().collect()
+    }
+
+    /// index of the frame which the outstanding request is sent at
+    pub fn requested_at(&self, id: CorrelationId) -> Option<FrameIndex> {
+        self.outstanding.get(&id).map(|(frame, _)| *frame)
+    }
+
+    /// count of the replied requests
+    pub fn completed_count(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// count of the replies which do not match any outstanding request
+    pub fn unmatched_replies(&self) -> usize {
+        self.unmatched_replies
+    }
+
+    /// count of the expired requests
+    pub fn expired_count(&self) -> usize {
+        self.expired
+    }
+
+    /// latency of each replied request in replied order
+    pub fn latencies(&self) -> &[FrameIndex] {
+        &self.latencies
+    }
+
+    /// mean of the latencies. if no request is replied, then None.
+    pub fn mean_latency(&self) -> Option<f64> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        Some(self.latencies.iter().sum::<FrameIndex>() as f64 / self.latencies.len() as f64)
+    }
+
+    /// max of the latencies. if no request is replied, then None.
+    pub fn max_latency(&self) -> Option<FrameIndex> {
+        self.latencies.iter().copied().max()
+    }
+}
+
+impl Correlator<()> {
+    /// register the request and return the new correlation id
+    pub fn request(&mut self, frame: FrameIndex) -> CorrelationId {
+        self.request_with(frame, ())
+    }
+
+    /// register the request and schedule the request event which carry the correlation id
+    pub fn schedule_request<E, R, F>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        frame: FrameIndex,
+        schedule: Schedule,
+        priority: Priority,
+        make_event: F,
+    ) -> Result<CorrelationId, ScheduleEventError>
+    where
+        E: Event + Clone,
+        R: Rng + ?Sized,
+        F: FnOnce(CorrelationId) -> E,
+    {
+        self.schedule_request_with(rng, scheduler, frame, schedule, priority, (), make_event)
+    }
+}
@@ -0,0 +1,353 @@
+//! Coupling of the frame based model and the continuous time model on the frame boundaries
+
+use crate::continuous::{self, ContinuousModel, ContinuousScheduler, SimTime};
+use crate::event::{Event, EventClass, EventScheduler, Priority, Tag};
+use crate::model::{BulkEvents, MixedStep, Model, StepByPriorityGroup, StepEachEvent};
+use crate::FrameIndex;
+use rand::Rng;
+use std::fmt;
+
+/// exchange between the frame based model and the continuous time model at the frame boundary.
+/// implement for the frame based model.
+pub trait Coupling<Rec, C: ContinuousModel<Rec>>: Model<Rec> {
+    /// action at the end of each frame after the continuous time model is advanced to the frame boundary.
+    /// e.g. read the state of one model and schedule the event into the other scheduler.
+    fn synchronize<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        continuous: &mut C,
+        continuous_scheduler: &mut ContinuousScheduler<C::ModelEvent>,
+    );
+}
+
+/// Error for coupling the models
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CouplingError {
+    /// the continuous time per one frame is not positive and finite
+    InvalidFrameLength(SimTime),
+}
+
+impl std::error::Error for CouplingError {}
+
+impl fmt::Display for CouplingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CouplingError::InvalidFrameLength(frame_length) => write!(
+                f,
+                "The frame length {} is not positive and finite",
+                frame_length
+            ),
+        }
+    }
+}
+
+/// frame based model which drive the continuous time model as the sub-model.
+///
+/// the frame n cover the continuous time up to n * frame_length. at the end of the frame,
+/// the continuous time model fire the events up to the boundary and then the models are synchronized.
+/// the events which are scheduled in the synchronization fire in the next frame at earliest.
+/// the handler of the frame based model is used as it is, so the models can be migrated gradually between the two engines.
+#[derive(Debug, Clone)]
+pub struct Coupled<F, C, CE: Event> {
+    frame_model: F,
+    continuous: C,
+    continuous_scheduler: ContinuousScheduler<CE>,
+    frame_length: SimTime,
+    frame: FrameIndex,
+    continuous_fired: u64,
+}
+
+impl<F, C, CE: Event> Coupled<F, C, CE> {
+    /// initializer. the frame_length is the continuous time per one frame.
+    /// if the frame_length is not positive and finite, then error.
+    pub fn new(
+        frame_model: F,
+        continuous: C,
+        frame_length: SimTime,
+    ) -> Result<Self, CouplingError> {
+        if !frame_length.is_finite() || frame_length <= 0.0 {
+            return Err(CouplingError::InvalidFrameLength(frame_length));
+        }
+        Ok(Coupled {
+            frame_model,
+            continuous,
+            continuous_scheduler: ContinuousScheduler::new(),
+            frame_length,
+            frame: 0,
+            continuous_fired: 0,
+        })
+    }
+
+    /// getter for the frame based model
+    pub fn frame_model(&self) -> &F {
+        &self.frame_model
+    }
+
+    /// getter for the frame based model as mutable
+    pub fn frame_model_as_mut(&mut self) -> &mut F {
+        &mut self.frame_model
+    }
+
+    /// getter for the continuous time model
+    pub fn continuous(&self) -> &C {
+        &self.continuous
+    }
+
+    /// getter for the continuous time model as mutable
+    pub fn continuous_as_mut(&mut self) -> &mut C {
+        &mut self.continuous
+    }
+
+    /// getter for the scheduler of the continuous time model
+    pub fn continuous_scheduler(&self) -> &ContinuousScheduler<CE> {
+        &self.continuous_scheduler
+    }
+
+    /// getter for the continuous time per one frame
+    pub fn frame_length(&self) -> SimTime {
+        self.frame_length
+    }
+
+    /// getter for the current time of the continuous time model
+    pub fn now(&self) -> SimTime {
+        self.continuous_scheduler.now()
+    }
+
+    /// count of the fired events of the continuous time model
+    pub fn continuous_fired_events(&self) -> u64 {
+        self.continuous_fired
+    }
+
+    /// decompose into the frame based model and the continuous time model
+    pub fn into_parts(self) -> (F, C) {
+        (self.frame_model, self.continuous)
+    }
+}
+
+impl<Rec, F, C, CE> Model<Rec> for Coupled<F, C, CE>
+where
+    F: Coupling<Rec, C>,
+    C: ContinuousModel<Rec, ModelEvent = CE>,
+    CE: Event,
+{
+    type ModelEvent = F::ModelEvent;
+
+    fn initialize<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+    ) {
+        self.continuous_scheduler.reset();
+        self.frame = 0;
+        self.continuous_fired = 0;
+        self.frame_model.initialize(rng, recorder, scheduler);
+        self.continuous
+            .initialize(rng, recorder, &mut self.continuous_scheduler);
+    }
+
+    fn start_frame(&mut self, recorder: &mut Rec) {
+        self.frame += 1;
+        self.frame_model.start_frame(recorder);
+    }
+
+    fn start_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.frame += 1;
+        self.frame_model.start_frame_at(recorder, frame);
+    }
+
+    fn before_first_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+    ) {
+        self.frame_model
+            .before_first_event(rng, recorder, scheduler);
+    }
+
+    fn after_last_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+    ) {
+        self.frame_model.after_last_event(rng, recorder, scheduler);
+        let boundary = self.frame as SimTime * self.frame_length;
+        self.continuous_fired += continuous::advance(
+            rng,
+            &mut self.continuous,
+            recorder,
+            &mut self.continuous_scheduler,
+            boundary,
+        );
+        self.frame_model.synchronize(
+            rng,
+            recorder,
+            scheduler,
+            &mut self.continuous,
+            &mut self.continuous_scheduler,
+        );
+    }
+
+    fn on_expired<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        event: Self::ModelEvent,
+    ) {
+        self.frame_model
+            .on_expired(rng, recorder, scheduler, priority, event);
+    }
+
+    fn on_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        tag: Tag,
+        events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        self.frame_model
+            .on_batch(rng, recorder, scheduler, tag, events);
+    }
+
+    fn finish_frame(&mut self, recorder: &mut Rec) {
+        self.frame_model.finish_frame(recorder);
+    }
+
+    fn finish_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.frame_model.finish_frame_at(recorder, frame);
+    }
+
+    fn on_warmup_complete(&mut self, recorder: &mut Rec) {
+        self.frame_model.on_warmup_complete(recorder);
+    }
+}
+
+impl<Rec, E, F, C, CE> BulkEvents<Rec, E> for Coupled<F, C, CE>
+where
+    E: Event,
+    F: Coupling<Rec, C> + BulkEvents<Rec, E>,
+    C: ContinuousModel<Rec, ModelEvent = CE>,
+    CE: Event,
+{
+    fn step_in_bulk<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        self.frame_model
+            .step_in_bulk(rng, recorder, scheduler, fired_events);
+    }
+
+    fn step_in_bulk_buffer<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: &mut Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        self.frame_model
+            .step_in_bulk_buffer(rng, recorder, scheduler, fired_events);
+    }
+}
+
+impl<Rec, E, F, C, CE> StepEachEvent<Rec, E> for Coupled<F, C, CE>
+where
+    E: Event,
+    F: Coupling<Rec, C> + StepEachEvent<Rec, E>,
+    C: ContinuousModel<Rec, ModelEvent = CE>,
+    CE: Event,
+{
+    fn step_each_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_event: Self::ModelEvent,
+    ) {
+        self.frame_model
+            .step_each_event(rng, recorder, scheduler, priority, fired_event);
+    }
+}
+
+impl<Rec, E, F, C, CE> StepByPriorityGroup<Rec, E> for Coupled<F, C, CE>
+where
+    E: Event,
+    F: Coupling<Rec, C> + StepByPriorityGroup<Rec, E>,
+    C: ContinuousModel<Rec, ModelEvent = CE>,
+    CE: Event,
+{
+    fn step_priority_group<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_events: Vec<Self::ModelEvent>,
+    ) {
+        self.frame_model
+            .step_priority_group(rng, recorder, scheduler, priority, fired_events);
+    }
+}
+
+impl<Rec, E, F, C, CE> MixedStep<Rec, E> for Coupled<F, C, CE>
+where
+    E: EventClass,
+    F: Coupling<Rec, C> + MixedStep<Rec, E>,
+    C: ContinuousModel<Rec, ModelEvent = CE>,
+    CE: Event,
+{
+    fn step_individual<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_event: Self::ModelEvent,
+    ) {
+        self.frame_model
+            .step_individual(rng, recorder, scheduler, priority, fired_event);
+    }
+
+    fn step_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        self.frame_model
+            .step_batch(rng, recorder, scheduler, fired_events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct Tick;
+
+    impl Event for Tick {}
+
+    #[test]
+    fn coupled_reject_invalid_frame_length() {
+        for frame_length in [0.0, -1.0, SimTime::NAN, SimTime::INFINITY].iter() {
+            assert!(matches!(
+                Coupled::<(), (), Tick>::new((), (), *frame_length),
+                Err(CouplingError::InvalidFrameLength(_))
+            ));
+        }
+        let coupled = Coupled::<(), (), Tick>::new((), (), 0.5).unwrap();
+        assert_eq!(coupled.frame_length(), 0.5);
+    }
+}
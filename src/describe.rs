@@ -0,0 +1,127 @@
+//! Introspection of the event's schema for tooling
+
+use crate::event::Event;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// information of the event variant's field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldInfo {
+    /// name of the field. the field of tuple variant is empty name and known by the position.
+    pub name: &'static str,
+    /// type of the field as written in the source
+    pub type_name: &'static str,
+}
+
+/// information of the event variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VariantInfo {
+    /// name of the variant
+    pub name: &'static str,
+    /// fields of the variant
+    pub fields: &'static [FieldInfo],
+}
+
+/// event which can tell its variants and the fields at runtime.
+///
+/// usually implemented by describe_events macro.
+pub trait DescribeEvents: Event {
+    /// all variants of the event
+    fn variants() -> &'static [VariantInfo];
+
+    /// name of the variant of this event
+    fn variant_name(&self) -> &'static str;
+
+    /// information of the variant of this event
+    fn variant_info(&self) -> Option<&'static VariantInfo> {
+        let name = self.variant_name();
+        Self::variants().iter().find(|info| info.name == name)
+    }
+}
+
+impl<E: DescribeEvents> DescribeEvents for Arc<E> {
+    fn variants() -> &'static [VariantInfo] {
+        E::variants()
+    }
+
+    fn variant_name(&self) -> &'static str {
+        self.as_ref().variant_name()
+    }
+}
+
+impl<E: DescribeEvents> DescribeEvents for Rc<E> {
+    fn variants() -> &'static [VariantInfo] {
+        E::variants()
+    }
+
+    fn variant_name(&self) -> &'static str {
+        self.as_ref().variant_name()
+    }
+}
+
+/// implement DescribeEvents for the event enum.
+///
+/// write the variants as same as the enum's definition without attributes, e.g.
+/// `describe_events!(TimelineEvent { Flush, Spawn(String, String), Move { x: i32, y: i32 } });`
+#[macro_export]
+macro_rules! describe_events {
+    ($t:ty { $($body:tt)* }) => {
+        $crate::describe_events!(@munch $t; [] []; $($body)*);
+    };
+
+    // struct variant
+    (@munch $t:ty; [$($info:tt)*] [$($arm:tt)*];
+        $v:ident { $($f:ident : $fty:ty),* $(,)? } $(, $($rest:tt)*)?) => {
+        $crate::describe_events!(@munch $t;
+            [$($info)* $crate::describe::VariantInfo {
+                name: stringify!($v),
+                fields: &[$($crate::describe::FieldInfo {
+                    name: stringify!($f),
+                    type_name: stringify!($fty),
+                }),*],
+            },]
+            [$($arm)* Self::$v { .. } => stringify!($v),];
+            $($($rest)*)?);
+    };
+
+    // tuple variant
+    (@munch $t:ty; [$($info:tt)*] [$($arm:tt)*];
+        $v:ident ( $($fty:ty),* $(,)? ) $(, $($rest:tt)*)?) => {
+        $crate::describe_events!(@munch $t;
+            [$($info)* $crate::describe::VariantInfo {
+                name: stringify!($v),
+                fields: &[$($crate::describe::FieldInfo {
+                    name: "",
+                    type_name: stringify!($fty),
+                }),*],
+            },]
+            [$($arm)* Self::$v(..) => stringify!($v),];
+            $($($rest)*)?);
+    };
+
+    // unit variant
+    (@munch $t:ty; [$($info:tt)*] [$($arm:tt)*];
+        $v:ident $(, $($rest:tt)*)?) => {
+        $crate::describe_events!(@munch $t;
+            [$($info)* $crate::describe::VariantInfo {
+                name: stringify!($v),
+                fields: &[],
+            },]
+            [$($arm)* Self::$v => stringify!($v),];
+            $($($rest)*)?);
+    };
+
+    (@munch $t:ty; [$($info:tt)*] [$($arm:tt)*];) => {
+        impl $crate::describe::DescribeEvents for $t {
+            fn variants() -> &'static [$crate::describe::VariantInfo] {
+                &[$($info)*]
+            }
+
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    $($arm)*
+                }
+            }
+        }
+    };
+}
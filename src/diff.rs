@@ -0,0 +1,362 @@
+//! Lockstep comparison of two simulations to find the first frame which diverge
+
+use crate::event::{Event, EventScheduler, Priority};
+use crate::frame::Frames;
+use crate::model::Model;
+use crate::trace::{frame_divergence, FrameDivergence, TraceRecord};
+use crate::FrameIndex;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// fired events and the digest of the model at the frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameSnapshot {
+    /// index of the frame
+    pub frame: FrameIndex,
+    /// labeled fired events in fired order
+    pub fired_events: Vec<(Priority, String)>,
+    /// digest of the model after the frame, e.g. by testing::debug_digest. None is not compared.
+    pub digest: Option<String>,
+}
+
+/// one side of the lockstep comparison
+pub trait ReplaySide {
+    /// simulate or read the next frame. if no more frame, then None.
+    fn next_frame(&mut self) -> Option<FrameSnapshot>;
+}
+
+/// running simulator as the side which is simulated frame by frame
+pub struct SimulatorSide<'a, M, E, Rec, R, H, L>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event + Clone,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    L: FnMut(&E) -> String,
+{
+    frames: Frames<'a, M, E, Rec, R, H>,
+    labeler: L,
+    digest: Option<fn(&M) -> String>,
+}
+
+impl<'a, M, E, Rec, R, H, L> SimulatorSide<'a, M, E, Rec, R, H, L>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event + Clone,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    L: FnMut(&E) -> String,
+{
+    /// initializer with the frames of the simulator, e.g. Simulator::frames_each_event, and the labeler of the event
+    pub fn new(frames: Frames<'a, M, E, Rec, R, H>, labeler: L) -> Self {
+        SimulatorSide {
+            frames,
+            labeler,
+            digest: None,
+        }
+    }
+
+    /// compare the digest of the model after each frame
+    pub fn with_digest(mut self, digest: fn(&M) -> String) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+}
+
+impl<'a, M, E, Rec, R, H, L> ReplaySide for SimulatorSide<'a, M, E, Rec, R, H, L>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event + Clone,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    L: FnMut(&E) -> String,
+{
+    fn next_frame(&mut self) -> Option<FrameSnapshot> {
+        let report = self.frames.next()?;
+        let labeler = &mut self.labeler;
+        let fired_events = report
+            .fired_events
+            .iter()
+            .map(|(priority, event)| (*priority, labeler(event)))
+            .collect();
+        Some(FrameSnapshot {
+            frame: report.index,
+            fired_events,
+            digest: self
+                .digest
+                .map(|digest| digest(self.frames.get_simulator().get_model())),
+        })
+    }
+}
+
+/// recorded trace as the side. the digest is not compared.
+#[derive(Debug, Clone)]
+pub struct TraceSide {
+    frames: BTreeMap<FrameIndex, Vec<(Priority, String)>>,
+    next: FrameIndex,
+    last: FrameIndex,
+}
+
+impl TraceSide {
+    /// initializer with the records. the frames from 1 to the last frame of the records are read.
+    pub fn new(records: &[TraceRecord]) -> Self {
+        let mut frames: BTreeMap<FrameIndex, Vec<(Priority, String)>> = BTreeMap::new();
+        for record in records.iter() {
+            frames
+                .entry(record.frame)
+                .or_default()
+                .push((record.priority, record.label.clone()));
+        }
+        let last = frames.keys().next_back().copied().unwrap_or(0);
+        TraceSide {
+            frames,
+            next: 1,
+            last,
+        }
+    }
+
+    /// start to read from the frame, e.g. the next frame of the checkpoint
+    pub fn starting_at(mut self, frame: FrameIndex) -> Self {
+        self.next = frame;
+        self
+    }
+}
+
+impl ReplaySide for TraceSide {
+    fn next_frame(&mut self) -> Option<FrameSnapshot> {
+        if self.next > self.last {
+            return None;
+        }
+        let frame = self.next;
+        self.next += 1;
+        Some(FrameSnapshot {
+            frame,
+            fired_events: self.frames.remove(&frame).unwrap_or_default(),
+            digest: None,
+        })
+    }
+}
+
+/// difference which is found first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// the fired events or the priorities are different
+    FiredEvents(FrameDivergence),
+    /// the fired events are same, but the digests of the models are different
+    Digest {
+        /// index of the frame
+        frame: FrameIndex,
+        /// digest of the side a
+        a: String,
+        /// digest of the side b
+        b: String,
+    },
+    /// one side has no more frame while the other side still fire events
+    Ended {
+        /// index of the frame which the other side fire events at
+        frame: FrameIndex,
+        /// the side which is ended
+        ended: Side,
+        /// labeled fired events of the other side at the frame
+        fired_events: Vec<(Priority, String)>,
+    },
+    /// the sides are at the different frames, e.g. the trace side start at the other frame
+    FrameMismatch {
+        /// index of the frame of the side a
+        a: FrameIndex,
+        /// index of the frame of the side b
+        b: FrameIndex,
+    },
+}
+
+/// side of the lockstep comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// the first side
+    A,
+    /// the second side
+    B,
+}
+
+impl Divergence {
+    /// index of the frame which diverge. for the frame mismatch, the frame of the side a.
+    pub fn frame(&self) -> FrameIndex {
+        match self {
+            Divergence::FiredEvents(divergence) => divergence.frame,
+            Divergence::Digest { frame, .. } => *frame,
+            Divergence::Ended { frame, .. } => *frame,
+            Divergence::FrameMismatch { a, .. } => *a,
+        }
+    }
+}
+
+/// result of the lockstep comparison
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DivergenceReport {
+    /// count of the frames which are compared until the divergence or the end
+    pub frames_compared: usize,
+    /// the first difference
+    pub first_divergence: Option<Divergence>,
+}
+
+impl DivergenceReport {
+    /// judge both sides are same
+    pub fn is_identical(&self) -> bool {
+        self.first_divergence.is_none()
+    }
+}
+
+impl fmt::Display for DivergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.first_divergence {
+            None => write!(f, "no divergence in {} frames", self.frames_compared),
+            Some(Divergence::FiredEvents(divergence)) => {
+                writeln!(f, "fired events diverge at frame {}", divergence.frame)?;
+                if divergence.order_only {
+                    writeln!(f, "  same events are fired in different order")?;
+                }
+                for (priority, label) in divergence.only_in_a.iter() {
+                    writeln!(f, "  - P={} {}", priority, label)?;
+                }
+                for (priority, label) in divergence.only_in_b.iter() {
+                    writeln!(f, "  + P={} {}", priority, label)?;
+                }
+                write!(f, "after {} frames", self.frames_compared)
+            }
+            Some(Divergence::Digest { frame, a, b }) => write!(
+                f,
+                "model digest diverge at frame {} ({} != {}) after {} frames",
+                frame, a, b, self.frames_compared
+            ),
+            Some(Divergence::Ended {
+                frame,
+                ended,
+                fired_events,
+            }) => {
+                writeln!(
+                    f,
+                    "side {:?} is ended, but the other side fire events at frame {}",
+                    ended, frame
+                )?;
+                let mark = if *ended == Side::A { '+' } else { '-' };
+                for (priority, label) in fired_events.iter() {
+                    writeln!(f, "  {} P={} {}", mark, priority, label)?;
+                }
+                write!(f, "after {} frames", self.frames_compared)
+            }
+            Some(Divergence::FrameMismatch { a, b }) => write!(
+                f,
+                "frames diverge (frame {} != frame {}) after {} frames",
+                a, b, self.frames_compared
+            ),
+        }
+    }
+}
+
+/// run both sides frame by frame up to the max frames and report the first divergence.
+/// the comparison stop when both sides have no more frame.
+/// after one side is ended, the other side must fire no event.
+pub fn lockstep<A, B>(a: &mut A, b: &mut B, max_frames: FrameIndex) -> DivergenceReport
+where
+    A: ReplaySide + ?Sized,
+    B: ReplaySide + ?Sized,
+{
+    let mut report = DivergenceReport::default();
+    for _ in 0..max_frames {
+        let divergence = match (a.next_frame(), b.next_frame()) {
+            (Some(snapshot_a), Some(snapshot_b)) => compare(snapshot_a, snapshot_b),
+            (None, Some(snapshot)) => after_end(Side::A, snapshot),
+            (Some(snapshot), None) => after_end(Side::B, snapshot),
+            (None, None) => break,
+        };
+        report.frames_compared += 1;
+        if divergence.is_some() {
+            report.first_divergence = divergence;
+            break;
+        }
+    }
+    report
+}
+
+/// first difference of the frames of both sides
+fn compare(a: FrameSnapshot, b: FrameSnapshot) -> Option<Divergence> {
+    if a.frame != b.frame {
+        return Some(Divergence::FrameMismatch {
+            a: a.frame,
+            b: b.frame,
+        });
+    }
+    if let Some(divergence) = frame_divergence(a.frame, &a.fired_events, &b.fired_events) {
+        return Some(Divergence::FiredEvents(divergence));
+    }
+    match (a.digest, b.digest) {
+        (Some(digest_a), Some(digest_b)) if digest_a != digest_b => Some(Divergence::Digest {
+            frame: a.frame,
+            a: digest_a,
+            b: digest_b,
+        }),
+        _ => None,
+    }
+}
+
+/// difference of the frame of the other side after the side is ended
+fn after_end(ended: Side, snapshot: FrameSnapshot) -> Option<Divergence> {
+    if snapshot.fired_events.is_empty() {
+        None
+    } else {
+        Some(Divergence::Ended {
+            frame: snapshot.frame,
+            ended,
+            fired_events: snapshot.fired_events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(frames: &[FrameIndex]) -> Vec<TraceRecord> {
+        frames
+            .iter()
+            .map(|frame| TraceRecord {
+                frame: *frame,
+                priority: Priority::NORMAL,
+                label: "Tick".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lockstep_report_side_which_end_before_other_fire() {
+        let mut a = TraceSide::new(&records(&[1, 2]));
+        let mut b = TraceSide::new(&records(&[1, 2, 4]));
+        let report = lockstep(&mut a, &mut b, 10);
+        assert_eq!(report.frames_compared, 4);
+        assert_eq!(
+            report.first_divergence,
+            Some(Divergence::Ended {
+                frame: 4,
+                ended: Side::A,
+                fired_events: vec![(Priority::NORMAL, "Tick".to_string())],
+            })
+        );
+
+        let mut a = TraceSide::new(&records(&[1, 2]));
+        let mut b = TraceSide::new(&records(&[1, 2]));
+        assert!(lockstep(&mut a, &mut b, 10).is_identical());
+    }
+
+    #[test]
+    fn lockstep_report_frame_mismatch() {
+        let mut a = TraceSide::new(&records(&[1, 2, 3]));
+        let mut b = TraceSide::new(&records(&[1, 2, 3])).starting_at(2);
+        let report = lockstep(&mut a, &mut b, 10);
+        assert_eq!(report.frames_compared, 1);
+        assert_eq!(
+            report.first_divergence,
+            Some(Divergence::FrameMismatch { a: 1, b: 2 })
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Type-erased event for composing the plugins whose event types are not known to each other
+
+use crate::event::{Event, EventId, EventScheduler, Priority, Schedule, ScheduleEventError};
+use rand::{Rng, RngCore};
+use std::any::{self, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// object safe part of the type-erased event
+trait AnyEvent: Any + Send {
+    fn clone_box(&self) -> Box<dyn AnyEvent>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    fn type_name(&self) -> &'static str;
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl<T: Any + Clone + fmt::Debug + Send> AnyEvent for T {
+    fn clone_box(&self) -> Box<dyn AnyEvent> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// event of any type. the scheduler of this event can hold the events of the different types together.
+pub struct DynEvent(Box<dyn AnyEvent>);
+
+impl DynEvent {
+    /// wrap the event
+    pub fn new<T: Any + Clone + fmt::Debug + Send>(event: T) -> Self {
+        DynEvent(Box::new(event))
+    }
+
+    /// identifier of the type of the wrapped event
+    pub fn type_id(&self) -> TypeId {
+        self.0.as_any().type_id()
+    }
+
+    /// name of the type of the wrapped event
+    pub fn type_name(&self) -> &'static str {
+        self.0.type_name()
+    }
+
+    /// judge the wrapped event is the type
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.as_any().is::<T>()
+    }
+
+    /// getter for the wrapped event if the event is the type
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    /// take the wrapped event. if the event is not the type, then return self as error.
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        if self.is::<T>() {
+            Ok(*self.0.into_any().downcast().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Clone for DynEvent {
+    fn clone(&self) -> Self {
+        DynEvent(self.0.clone_box())
+    }
+}
+
+impl fmt::Debug for DynEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_debug(f)
+    }
+}
+
+impl Event for DynEvent {}
+
+impl EventScheduler<DynEvent> {
+    /// store event of any type with scheduling
+    pub fn schedule_dyn<R: Rng + ?Sized, T: Any + Clone + fmt::Debug + Send>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: T,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule(rng, schedule, priority, DynEvent::new(event))
+    }
+
+    /// store event of any type which fire at immediate timing
+    pub fn immediate_dyn<R: Rng + ?Sized, T: Any + Clone + fmt::Debug + Send>(
+        &mut self,
+        rng: &mut R,
+        priority: Priority,
+        event: T,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.immediate(rng, priority, DynEvent::new(event))
+    }
+
+    /// remove scheduled events of the type.
+    /// the events which wait the removed event are also removed.
+    pub fn remove_of_type<T: Any>(&mut self) {
+        self.remove_when(|(_, _, _, event)| event.is::<T>())
+    }
+}
+
+/// handler for the fired event of the registered type
+type DynHandler<M, Rec> = Arc<
+    dyn Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<DynEvent>, Priority, DynEvent)
+        + Send
+        + Sync,
+>;
+
+/// dispatcher which route the fired type-erased events to the handlers registered by the type
+pub struct Dispatcher<M, Rec> {
+    handlers: Arc<HashMap<TypeId, DynHandler<M, Rec>>>,
+    unhandled: usize,
+}
+
+impl<M, Rec> Default for Dispatcher<M, Rec> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M, Rec> Clone for Dispatcher<M, Rec> {
+    fn clone(&self) -> Self {
+        Dispatcher {
+            handlers: Arc::clone(&self.handlers),
+            unhandled: self.unhandled,
+        }
+    }
+}
+
+impl<M, Rec> fmt::Debug for Dispatcher<M, Rec> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("handlers", &self.handlers.len())
+            .field("unhandled", &self.unhandled)
+            .finish()
+    }
+}
+
+impl<M, Rec> Dispatcher<M, Rec> {
+    /// initializer
+    pub fn new() -> Self {
+        Dispatcher {
+            handlers: Arc::new(HashMap::new()),
+            unhandled: 0,
+        }
+    }
+
+    /// register the handler for the event of the type. the handler registered before for the type is replaced.
+    pub fn register<T, F>(&mut self, handler: F)
+    where
+        T: Any,
+        F: Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<DynEvent>, Priority, T)
+            + Send
+            + Sync
+            + 'static,
+    {
+        let handler: DynHandler<M, Rec> =
+            Arc::new(move |rng, model, recorder, scheduler, priority, event| {
+                // the handler is registered by the type of the event
+                let event = event.downcast::<T>().unwrap();
+                handler(rng, model, recorder, scheduler, priority, event)
+            });
+        Arc::make_mut(&mut self.handlers).insert(TypeId::of::<T>(), handler);
+    }
+
+    /// judge the handler for the type is registered
+    pub fn is_registered<T: Any>(&self) -> bool {
+        self.handlers.contains_key(&TypeId::of::<T>())
+    }
+
+    /// count of the fired events which are dropped because the handler for the type is not registered
+    pub fn unhandled(&self) -> usize {
+        self.unhandled
+    }
+
+    /// hand the fired events to the handlers in fired order
+    pub(crate) fn dispatch<R: Rng + ?Sized>(
+        &mut self,
+        mut rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<DynEvent>,
+        fired_events: &mut Vec<(Priority, DynEvent)>,
+    ) {
+        for (priority, event) in fired_events.drain(..) {
+            match self.handlers.get(&event.type_id()) {
+                Some(handler) => handler(&mut rng, model, recorder, scheduler, priority, event),
+                None => self.unhandled += 1,
+            }
+        }
+    }
+}
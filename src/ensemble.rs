@@ -0,0 +1,292 @@
+//! Runner for independent replications of the simulation
+
+use crate::event::Event;
+use crate::model::Model;
+use crate::recorder::{merge_all, summarize, FrameSummary, MergeRecorder};
+use crate::{FrameIndex, Simulator};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// generator of the initial model state which is sampled for each replication
+pub trait InitialConditions<M> {
+    /// sample the initial model state
+    fn sample(&self, rng: &mut dyn RngCore) -> M;
+}
+
+impl<M, F> InitialConditions<M> for F
+where
+    F: Fn(&mut dyn RngCore) -> M,
+{
+    fn sample(&self, rng: &mut dyn RngCore) -> M {
+        self(rng)
+    }
+}
+
+/// seed for sampling initial conditions which is independent of the seed for running
+fn initial_conditions_seed(seed: u64) -> u64 {
+    StdRng::seed_from_u64(seed ^ 0x9E37_79B9_7F4A_7C15).gen()
+}
+
+/// Error for giving the seeds of the replications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedError {
+    /// fixed seeds are fewer than the replications
+    NotEnoughSeeds {
+        /// count of the replications
+        required: usize,
+        /// count of the fixed seeds
+        specified: usize,
+    },
+}
+
+impl std::error::Error for SeedError {}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SeedError::NotEnoughSeeds {
+                required,
+                specified,
+            } => write!(
+                f,
+                "The seeds are specified only for {} of {} replications",
+                specified, required
+            ),
+        }
+    }
+}
+
+/// strategy to give the seed for each replication
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedStrategy {
+    /// the seed of the replication i is base + i
+    Sequential(u64),
+    /// the seeds are drawn from the random number generator seeded by the master seed
+    Derived(u64),
+    /// the seed of the replication i is the i-th seed. the seeds must be specified for all replications.
+    Fixed(Vec<u64>),
+}
+
+impl SeedStrategy {
+    /// seeds for the replications. if the fixed seeds are fewer than n, then error.
+    pub fn seeds(&self, n: usize) -> Result<Vec<u64>, SeedError> {
+        match self {
+            SeedStrategy::Sequential(base) => {
+                Ok((0..n as u64).map(|i| base.wrapping_add(i)).collect())
+            }
+            SeedStrategy::Derived(master) => {
+                let mut rng = StdRng::seed_from_u64(*master);
+                Ok((0..n).map(|_| rng.gen()).collect())
+            }
+            SeedStrategy::Fixed(seeds) if seeds.len() < n => Err(SeedError::NotEnoughSeeds {
+                required: n,
+                specified: seeds.len(),
+            }),
+            SeedStrategy::Fixed(seeds) => Ok(seeds[..n].to_vec()),
+        }
+    }
+}
+
+/// final state of one replication
+#[derive(Debug, Clone)]
+pub struct Replication<M, Rec> {
+    /// index of the replication
+    pub index: usize,
+    /// seed of the random number generator for the replication
+    pub seed: u64,
+    /// model at the end of the replication
+    pub model: M,
+    /// recorder at the end of the replication
+    pub recorder: Rec,
+    /// seed of the random number generator for sampling the initial model state if sampled
+    pub initial_conditions_seed: Option<u64>,
+}
+
+impl<M, Rec> Replication<M, Rec> {
+    /// information to reproduce the replication. this can be used as the manifest of the trace.
+    pub fn manifest(&self) -> BTreeMap<String, String> {
+        let mut manifest = BTreeMap::new();
+        manifest.insert("replication".to_string(), self.index.to_string());
+        manifest.insert("seed".to_string(), self.seed.to_string());
+        if let Some(seed) = self.initial_conditions_seed {
+            manifest.insert("initial_conditions_seed".to_string(), seed.to_string());
+        }
+        manifest
+    }
+}
+
+/// run one replication
+fn run_replication<M, E, Rec, MF, RF, F>(
+    index: usize,
+    seed: u64,
+    model_factory: &MF,
+    recorder_factory: &RF,
+    run: &F,
+) -> Replication<M, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    MF: Fn(usize, u64) -> (M, Option<u64>),
+    RF: Fn(usize) -> Rec,
+    F: Fn(&mut Simulator<M, E, Rec>, &mut StdRng),
+{
+    let (model, initial_conditions_seed) = model_factory(index, seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut simulator = Simulator::create_from(&mut rng, model, recorder_factory(index));
+    run(&mut simulator, &mut rng);
+    Replication {
+        index,
+        seed,
+        model: simulator.model,
+        recorder: simulator.recorder,
+        initial_conditions_seed,
+    }
+}
+
+/// run all replications with the model which is created from the index and the seed.
+/// the seeds are checked before any replication runs.
+fn run_all<M, E, Rec, MF, RF, F>(
+    n: usize,
+    seed_strategy: SeedStrategy,
+    model_factory: MF,
+    recorder_factory: RF,
+    run: F,
+) -> Result<Vec<Replication<M, Rec>>, SeedError>
+where
+    M: Model<Rec, ModelEvent = E> + Send,
+    E: Event,
+    Rec: Send,
+    MF: Fn(usize, u64) -> (M, Option<u64>) + Sync,
+    RF: Fn(usize) -> Rec + Sync,
+    F: Fn(&mut Simulator<M, E, Rec>, &mut StdRng) + Sync,
+{
+    let seeds = seed_strategy.seeds(n)?;
+
+    #[cfg(feature = "rayon")]
+    let replications = seeds
+        .into_par_iter()
+        .enumerate()
+        .map(|(index, seed)| run_replication(index, seed, &model_factory, &recorder_factory, &run))
+        .collect();
+    #[cfg(not(feature = "rayon"))]
+    let replications = seeds
+        .into_iter()
+        .enumerate()
+        .map(|(index, seed)| run_replication(index, seed, &model_factory, &recorder_factory, &run))
+        .collect();
+
+    Ok(replications)
+}
+
+/// run n replications and return the final states in order of the index.
+///
+/// each replication use the random number generator seeded by the seed strategy,
+/// so the result is same whether the replications run in parallel or not.
+/// with the rayon feature, replications run in parallel.
+/// if the seed strategy cannot give n seeds, then error without running.
+pub fn run_replications<M, E, Rec, MF, RF, F>(
+    n: usize,
+    seed_strategy: SeedStrategy,
+    model_factory: MF,
+    recorder_factory: RF,
+    run: F,
+) -> Result<Vec<Replication<M, Rec>>, SeedError>
+where
+    M: Model<Rec, ModelEvent = E> + Send,
+    E: Event,
+    Rec: Send,
+    MF: Fn(usize) -> M + Sync,
+    RF: Fn(usize) -> Rec + Sync,
+    F: Fn(&mut Simulator<M, E, Rec>, &mut StdRng) + Sync,
+{
+    run_all(
+        n,
+        seed_strategy,
+        |index, _| (model_factory(index), None),
+        recorder_factory,
+        run,
+    )
+}
+
+/// run n replications with the initial model state which is sampled for each replication.
+///
+/// the initial conditions are sampled by the random number generator
+/// which is independent of the one for running, and the seed is recorded in the replication.
+pub fn run_sampled_replications<M, E, Rec, IC, RF, F>(
+    n: usize,
+    seed_strategy: SeedStrategy,
+    initial_conditions: IC,
+    recorder_factory: RF,
+    run: F,
+) -> Result<Vec<Replication<M, Rec>>, SeedError>
+where
+    M: Model<Rec, ModelEvent = E> + Send,
+    E: Event,
+    Rec: Send,
+    IC: InitialConditions<M> + Sync,
+    RF: Fn(usize) -> Rec + Sync,
+    F: Fn(&mut Simulator<M, E, Rec>, &mut StdRng) + Sync,
+{
+    run_all(
+        n,
+        seed_strategy,
+        |_, seed| {
+            let initial_seed = initial_conditions_seed(seed);
+            let mut rng = StdRng::seed_from_u64(initial_seed);
+            (initial_conditions.sample(&mut rng), Some(initial_seed))
+        },
+        recorder_factory,
+        run,
+    )
+}
+
+/// run n replications and merge the recorders in order of the index. if n is 0, then None.
+pub fn run_merged_replications<M, E, Rec, MF, RF, F>(
+    n: usize,
+    seed_strategy: SeedStrategy,
+    model_factory: MF,
+    recorder_factory: RF,
+    run: F,
+) -> Result<Option<Rec>, SeedError>
+where
+    M: Model<Rec, ModelEvent = E> + Send,
+    E: Event,
+    Rec: MergeRecorder + Send,
+    MF: Fn(usize) -> M + Sync,
+    RF: Fn(usize) -> Rec + Sync,
+    F: Fn(&mut Simulator<M, E, Rec>, &mut StdRng) + Sync,
+{
+    run_replications(n, seed_strategy, model_factory, recorder_factory, run).map(merge_replications)
+}
+
+/// merge the recorders of the replications in order. if no replication, then None.
+pub fn merge_replications<M, Rec: MergeRecorder>(
+    replications: Vec<Replication<M, Rec>>,
+) -> Option<Rec> {
+    merge_all(
+        replications
+            .into_iter()
+            .map(|replication| replication.recorder),
+    )
+}
+
+/// summarize the per-frame metric of the recorders across the replications.
+/// the confidence interval use the quantile of normal distribution z, e.g. 1.96 for 95%.
+pub fn summarize_replications<M, Rec, F>(
+    replications: &[Replication<M, Rec>],
+    z: f64,
+    metric: F,
+) -> Vec<FrameSummary>
+where
+    F: Fn(&Rec) -> Vec<(FrameIndex, f64)>,
+{
+    summarize(
+        replications.iter().map(|replication| &replication.recorder),
+        z,
+        metric,
+    )
+}
@@ -1,13 +1,81 @@
 //! Simulator's event and the event manager
 
+use crate::model::Phase;
+use crate::trace::{self, TraceRecord};
+use crate::FrameIndex;
 use rand::distributions::{Distribution, Uniform, WeightedError, WeightedIndex};
-use rand::Rng;
+use rand::{Rng, RngCore};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
 
 /// Timer for local
 pub type LocalEventTime = u32;
 
-/// can store event as Simulator's event
-pub trait Event: Clone {}
+/// can store event as Simulator's event.
+///
+/// the one-shot event (Immediate, Timeout) is moved to the model when fired, so the event need not be `Clone`
+/// and can own the resource such as the file handle or the channel. schedule such event by schedule_once.
+/// the recurring event is cloned for each fire, so it need `Clone`.
+/// if the event has the large payload, schedule it by EventScheduler::schedule_shared to share the payload without deep clone.
+pub trait Event {}
+
+impl<T: ?Sized> Event for Arc<T> {}
+
+impl<T: ?Sized> Event for Rc<T> {}
+
+/// event which is one of two event types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<L, R> {
+    /// first event type
+    Left(L),
+    /// second event type
+    Right(R),
+}
+
+impl<L: Event, R: Event> Event for Either<L, R> {}
+
+/// way to deliver the fired event to the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Delivery {
+    /// deliver one by one in fired order
+    Each,
+    /// deliver together with other bulk events
+    Bulk,
+}
+
+/// event which is classified into the way to deliver
+pub trait EventClass: Event {
+    /// way to deliver this event
+    fn delivery(&self) -> Delivery;
+}
+
+impl<L: EventClass, R: EventClass> EventClass for Either<L, R> {
+    fn delivery(&self) -> Delivery {
+        match self {
+            Either::Left(left) => left.delivery(),
+            Either::Right(right) => right.delivery(),
+        }
+    }
+}
+
+/// event which is grouped by the kind for the statistics of the scheduler
+pub trait EventKey: Event {
+    /// kind of this event. usually the name of the variant, e.g. DescribeEvents::variant_name.
+    fn key(&self) -> &'static str;
+}
+
+impl<L: EventKey, R: EventKey> EventKey for Either<L, R> {
+    fn key(&self) -> &'static str {
+        match self {
+            Either::Left(left) => left.key(),
+            Either::Right(right) => right.key(),
+        }
+    }
+}
 
 /// Error for scheduled event
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +85,8 @@ pub enum ScheduleEventError {
     /// for example, occurred when user schedule repeat count 0 repeat schedule.
     CannotFireEvent,
     WeightedError(WeightedError),
+    /// the timer of the schedule cannot calculate the time
+    InvalidSchedule(ScheduleValidationError),
 }
 
 impl std::error::Error for ScheduleEventError {}
@@ -26,6 +96,7 @@ impl std::fmt::Display for ScheduleEventError {
         match *self {
             ScheduleEventError::CannotFireEvent => write!(f, "Cannot fire the event"),
             ScheduleEventError::WeightedError(we) => write!(f, "{}", we),
+            ScheduleEventError::InvalidSchedule(error) => write!(f, "{}", error),
         }
     }
 }
@@ -36,6 +107,114 @@ impl From<WeightedError> for ScheduleEventError {
     }
 }
 
+/// the errors which are returned before this validation are kept
+impl From<ScheduleValidationError> for ScheduleEventError {
+    fn from(error: ScheduleValidationError) -> Self {
+        match error {
+            ScheduleValidationError::ZeroRepeat => ScheduleEventError::CannotFireEvent,
+            ScheduleValidationError::NoWeightedItem => {
+                ScheduleEventError::WeightedError(WeightedError::NoItem)
+            }
+            ScheduleValidationError::ZeroWeights => {
+                ScheduleEventError::WeightedError(WeightedError::AllWeightsZero)
+            }
+            ScheduleValidationError::WeightOverflow => {
+                ScheduleEventError::WeightedError(WeightedError::InvalidWeight)
+            }
+            error => ScheduleEventError::InvalidSchedule(error),
+        }
+    }
+}
+
+/// problem of the schedule which is found by the validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleValidationError {
+    /// the uniform range has no value
+    EmptyRange {
+        /// low value of the range
+        low: LocalEventTime,
+        /// max value of the range
+        max: LocalEventTime,
+        /// the max value is contained or not
+        inclusive: bool,
+    },
+    /// the weighted timer has no item
+    NoWeightedItem,
+    /// all weights of the weighted timer are 0
+    ZeroWeights,
+    /// the total of the weights is over u32
+    WeightOverflow,
+    /// the repeat count is 0, so the event never fire
+    ZeroRepeat,
+    /// the period of the periodic schedule is 0
+    ZeroPeriod,
+}
+
+impl std::error::Error for ScheduleValidationError {}
+
+impl std::fmt::Display for ScheduleValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ScheduleValidationError::EmptyRange {
+                low,
+                max,
+                inclusive,
+            } => {
+                let end = if inclusive { "]" } else { ")" };
+                write!(f, "The uniform range [{}, {}{} is empty", low, max, end)
+            }
+            ScheduleValidationError::NoWeightedItem => write!(f, "The weighted timer has no item"),
+            ScheduleValidationError::ZeroWeights => write!(f, "All weights are zero"),
+            ScheduleValidationError::WeightOverflow => {
+                write!(f, "The total of the weights is over u32")
+            }
+            ScheduleValidationError::ZeroRepeat => write!(f, "The repeat count is zero"),
+            ScheduleValidationError::ZeroPeriod => write!(f, "The period is zero"),
+        }
+    }
+}
+
+/// broken invariant of the scheduler which is found by EventScheduler::check_invariants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// the scheduled event at the index is ordered before the previous event.
+    /// the events must be ordered by the time asc and then by the priority desc.
+    OutOfOrder(usize),
+    /// the event belong to the domain which is not created
+    UnknownDomain(EventId, DomainId),
+    /// the identifier is used by two or more events
+    DuplicatedId(EventId),
+    /// the identifier is not issued by the scheduler yet
+    UnissuedId(EventId),
+    /// the recurring event cannot calculate the next timer
+    InvalidSchedule(EventId),
+    /// the event wait the event which is neither scheduled nor waiting
+    OrphanedEvent(EventId),
+}
+
+impl std::error::Error for InvariantViolation {}
+
+impl std::fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            InvariantViolation::OutOfOrder(index) => {
+                write!(f, "The event at {} is out of order", index)
+            }
+            InvariantViolation::UnknownDomain(id, domain) => {
+                write!(f, "The event {:?} belong to unknown domain {}", id, domain)
+            }
+            InvariantViolation::DuplicatedId(id) => write!(f, "The id {:?} is duplicated", id),
+            InvariantViolation::UnissuedId(id) => write!(f, "The id {:?} is not issued", id),
+            InvariantViolation::InvalidSchedule(id) => {
+                write!(f, "The event {:?} has invalid schedule", id)
+            }
+            InvariantViolation::OrphanedEvent(id) => {
+                write!(f, "The event {:?} wait the unknown event", id)
+            }
+        }
+    }
+}
+
 /// timer for schedule
 #[derive(Debug, Clone)]
 pub enum EventTimer {
@@ -47,10 +226,136 @@ pub enum EventTimer {
     /// it inclusive is true then low <= max, if false then low < max.
     Uniform(LocalEventTime, LocalEventTime, bool),
     /// fire after choice value with these weight as random.
-    WeightedIndex(Vec<(LocalEventTime, u8)>),
+    /// the total of the weights must be in u32. use WeightedTimer to validate the weights when build the timer.
+    WeightedIndex(Vec<(LocalEventTime, u32)>),
+    /// fire after the base timer perturbed by the random offset in [-spread, spread].
+    ///
+    /// args is pair of base timer and spread.
+    /// the recurring event keep the base schedule, so the offsets are not accumulated.
+    Jittered(Box<EventTimer>, LocalEventTime),
+    /// fire after random value sampled from the user distribution
+    Sampled(TimeSampler),
+}
+
+/// sampler of the time from the user distribution.
+/// the sampled value is rounded and clamped in [1, LocalEventTime::MAX], and NaN is treated as 1.
+#[derive(Clone)]
+pub struct TimeSampler(SampleFn);
+
+/// function which sample the time
+type SampleFn = Arc<dyn Fn(&mut dyn RngCore) -> f64 + Send + Sync>;
+
+impl TimeSampler {
+    /// initializer with the function which sample the time
+    pub fn new<F>(sampler: F) -> Self
+    where
+        F: Fn(&mut dyn RngCore) -> f64 + Send + Sync + 'static,
+    {
+        TimeSampler(Arc::new(sampler))
+    }
+
+    /// sample the time as local time
+    pub fn sample<R: RngCore + ?Sized>(&self, mut rng: &mut R) -> LocalEventTime {
+        let time = (self.0)(&mut rng).round();
+        if time >= f64::from(LocalEventTime::MAX) {
+            LocalEventTime::MAX
+        } else if time >= 1.0 {
+            time as LocalEventTime
+        } else {
+            1
+        }
+    }
+}
+
+impl std::fmt::Debug for TimeSampler {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("TimeSampler")
+    }
 }
 
 impl EventTimer {
+    /// perturb this timer by the random offset in [-spread, spread]
+    pub fn with_jitter(self, spread: LocalEventTime) -> Self {
+        EventTimer::Jittered(Box::new(self), spread)
+    }
+
+    /// all problems of the timer. if empty, the timer can calculate the time.
+    pub fn validate(&self) -> Vec<ScheduleValidationError> {
+        let mut problems = vec![];
+        self.collect_problems(&mut problems);
+        problems
+    }
+
+    /// validate the timer. if invalid, then return the first problem.
+    pub fn checked(self) -> Result<Self, ScheduleValidationError> {
+        match self.validate().into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(self),
+        }
+    }
+
+    /// push the problems of the timer
+    fn collect_problems(&self, problems: &mut Vec<ScheduleValidationError>) {
+        match self {
+            EventTimer::Time(_) | EventTimer::Sampled(_) => {}
+            EventTimer::Uniform(low, max, inclusive) => {
+                if (*inclusive && low > max) || (!*inclusive && low >= max) {
+                    problems.push(ScheduleValidationError::EmptyRange {
+                        low: *low,
+                        max: *max,
+                        inclusive: *inclusive,
+                    });
+                }
+            }
+            EventTimer::WeightedIndex(items) => {
+                if items.is_empty() {
+                    problems.push(ScheduleValidationError::NoWeightedItem);
+                } else if items
+                    .iter()
+                    .try_fold(0_u32, |total, item| total.checked_add(item.1))
+                    .is_none()
+                {
+                    problems.push(ScheduleValidationError::WeightOverflow);
+                } else if items.iter().all(|item| item.1 == 0) {
+                    problems.push(ScheduleValidationError::ZeroWeights);
+                }
+            }
+            EventTimer::Jittered(base, _) => base.collect_problems(problems),
+        }
+    }
+
+    /// judge the timer can calculate the time
+    fn is_valid(&self) -> bool {
+        self.validate().is_empty()
+    }
+
+    /// calculate time for event timer as local time with the offset of the jitter.
+    /// the offset of the previous fire is canceled to keep the base schedule.
+    fn to_local_time_with_jitter<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        previous_offset: i64,
+    ) -> Result<(LocalEventTime, i64), ScheduleEventError> {
+        let (base, offset) = match &self {
+            EventTimer::Jittered(base, spread) => {
+                let spread = i64::from(*spread);
+                (base.to_local_time(rng)?, rng.gen_range(-spread..=spread))
+            }
+            timer => (timer.to_local_time(rng)?, 0),
+        };
+        if offset == 0 && previous_offset == 0 {
+            return Ok((base, 0));
+        }
+        // the event fire at the next frame at earliest
+        let time =
+            (i64::from(base) + offset - previous_offset).clamp(1, i64::from(LocalEventTime::MAX));
+        // the actually applied offset is kept for the next fire
+        Ok((
+            LocalEventTime::try_from(time).unwrap(),
+            time - i64::from(base) + previous_offset,
+        ))
+    }
+
     /// calculate time for event timer as local time
     fn to_local_time<R: Rng + ?Sized>(
         &self,
@@ -64,17 +369,85 @@ impl EventTimer {
                 Uniform::from(*low..*max).sample(rng)
             }),
             EventTimer::WeightedIndex(items) => {
-                let dist = WeightedIndex::new(items.iter().map(|item| item.1))?;
+                let dist = weighted_index(items)?;
                 Ok(items
                     // always success because sampler is constructed from list of the (LocalEventTimer, weight)s.
                     .get(dist.sample(rng))
                     .unwrap()
                     .0)
             }
+            EventTimer::Jittered(_, _) => {
+                self.to_local_time_with_jitter(rng, 0).map(|(time, _)| time)
+            }
+            EventTimer::Sampled(sampler) => Ok(sampler.sample(rng)),
         }
     }
 }
 
+/// sampler of the weighted items. the total of the weights over u32 is invalid weight.
+fn weighted_index(items: &[(LocalEventTime, u32)]) -> Result<WeightedIndex<u32>, WeightedError> {
+    items
+        .iter()
+        .try_fold(0_u32, |total, item| total.checked_add(item.1))
+        .ok_or(WeightedError::InvalidWeight)?;
+    WeightedIndex::new(items.iter().map(|item| item.1))
+}
+
+/// validated items of EventTimer::WeightedIndex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedTimer {
+    items: Vec<(LocalEventTime, u32)>,
+}
+
+impl WeightedTimer {
+    /// initializer with the pairs of the timer and the weight.
+    /// if no item, all weights are 0 or the total of the weights is over u32, then error.
+    pub fn new<W, I>(items: I) -> Result<Self, WeightedError>
+    where
+        W: Into<u32>,
+        I: IntoIterator<Item = (LocalEventTime, W)>,
+    {
+        let items: Vec<(LocalEventTime, u32)> = items
+            .into_iter()
+            .map(|(timer, weight)| (timer, weight.into()))
+            .collect();
+        weighted_index(&items)?;
+        Ok(WeightedTimer { items })
+    }
+
+    /// getter for the pairs of the timer and the weight
+    pub fn items(&self) -> &[(LocalEventTime, u32)] {
+        &self.items
+    }
+}
+
+impl From<WeightedTimer> for EventTimer {
+    fn from(timer: WeightedTimer) -> Self {
+        EventTimer::WeightedIndex(timer.items)
+    }
+}
+
+/// conversion from the u8 weights which are used before the weights become u32
+impl From<Vec<(LocalEventTime, u8)>> for EventTimer {
+    fn from(items: Vec<(LocalEventTime, u8)>) -> Self {
+        EventTimer::WeightedIndex(
+            items
+                .into_iter()
+                .map(|(timer, weight)| (timer, u32::from(weight)))
+                .collect(),
+        )
+    }
+}
+
+/// conversion from the u8 weights which are used before the weights become u32
+impl TryFrom<Vec<(LocalEventTime, u8)>> for WeightedTimer {
+    type Error = WeightedError;
+
+    fn try_from(items: Vec<(LocalEventTime, u8)>) -> Result<Self, Self::Error> {
+        WeightedTimer::new(items)
+    }
+}
+
 /// event schedule
 #[derive(Debug, Clone)]
 pub enum Schedule {
@@ -88,26 +461,96 @@ pub enum Schedule {
     EveryInterval(EventTimer),
     /// fire every specify time only specify count
     Repeat(u8, EventTimer),
+    /// fire on the frames whose index is the offset modulo the period, e.g. the frames 3, 13, 23 for the period 10 and the offset 3.
+    /// the frames are counted by the clock of the scheduler, so the fires are aligned regardless of the scheduled frame.
+    Periodic {
+        /// frames between the fires
+        period: LocalEventTime,
+        /// phase of the fires in the period
+        offset: LocalEventTime,
+    },
 }
 
 impl Schedule {
-    /// calculate time for fire timing
+    /// all problems of the schedule. if empty, the schedule can be scheduled and rescheduled.
+    pub fn validate(&self) -> Vec<ScheduleValidationError> {
+        let mut problems = vec![];
+        match self {
+            Schedule::Immediate | Schedule::Everytime => {}
+            Schedule::Timeout(timer) | Schedule::EveryInterval(timer) => {
+                timer.collect_problems(&mut problems)
+            }
+            Schedule::Repeat(count, timer) => {
+                if *count == 0 {
+                    problems.push(ScheduleValidationError::ZeroRepeat);
+                }
+                timer.collect_problems(&mut problems);
+            }
+            Schedule::Periodic { period, .. } => {
+                if *period == 0 {
+                    problems.push(ScheduleValidationError::ZeroPeriod);
+                }
+            }
+        }
+        problems
+    }
+
+    /// validate the schedule. if invalid, then return the first problem.
+    pub fn checked(self) -> Result<Self, ScheduleValidationError> {
+        match self.validate().into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(self),
+        }
+    }
+
+    /// calculate time for fire timing with the offset of the jitter.
+    /// the previous_offset is the offset of the jitter at the previous fire, and the clock is the frame which is simulated last.
     fn to_local_timer<R: Rng + ?Sized>(
         &self,
         rng: &mut R,
-    ) -> Result<LocalEventTime, ScheduleEventError> {
+        previous_offset: i64,
+        clock: FrameIndex,
+    ) -> Result<(LocalEventTime, i64), ScheduleEventError> {
         match &self {
-            Schedule::Immediate => Ok(1),
-            Schedule::Timeout(timeout) => timeout.to_local_time(rng),
-            Schedule::Everytime => Ok(1),
-            Schedule::EveryInterval(interval) => interval.to_local_time(rng),
+            Schedule::Immediate => Ok((1, 0)),
+            Schedule::Timeout(timeout) => timeout.to_local_time_with_jitter(rng, 0),
+            Schedule::Everytime => Ok((1, 0)),
+            Schedule::EveryInterval(interval) => {
+                interval.to_local_time_with_jitter(rng, previous_offset)
+            }
             Schedule::Repeat(count, interval) => {
                 if *count == 0 {
                     return Err(ScheduleEventError::CannotFireEvent);
                 }
 
-                return interval.to_local_time(rng);
+                interval.to_local_time_with_jitter(rng, previous_offset)
+            }
+            Schedule::Periodic { period, offset } => {
+                if *period == 0 {
+                    return Err(ScheduleValidationError::ZeroPeriod.into());
+                }
+                let period = FrameIndex::from(*period);
+                let phase = FrameIndex::from(*offset) % period;
+                let wait = match (phase + period - clock % period) % period {
+                    0 => period,
+                    wait => wait,
+                };
+                // the wait is not over the period
+                Ok((LocalEventTime::try_from(wait).unwrap(), 0))
+            }
+        }
+    }
+
+    /// judge the next schedule can calculate the timer
+    fn is_valid_recurrence(&self) -> bool {
+        match self.to_next() {
+            None | Some(Schedule::Everytime) => true,
+            Some(Schedule::EveryInterval(interval)) | Some(Schedule::Repeat(_, interval)) => {
+                interval.is_valid()
             }
+            Some(Schedule::Periodic { period, .. }) => period > 0,
+            // to_next never return other schedules
+            Some(_) => false,
         }
     }
 
@@ -124,158 +567,2441 @@ impl Schedule {
             Schedule::Repeat(count, interval) => {
                 Some(Schedule::Repeat(count - 1, interval.clone()))
             }
+            Schedule::Periodic { period, offset } => Some(Schedule::Periodic {
+                period: *period,
+                offset: *offset,
+            }),
         }
     }
 }
 
-/// u8::MIN is the lowest priority, u8::MAX is the highest priority.
-pub type Priority = u8;
+/// remove the events which the split accept and return them with converting the event type
+fn split_events<E, T>(
+    events: &mut Vec<(Priority, E)>,
+    split: fn(E) -> Result<T, E>,
+) -> Vec<(Priority, T)> {
+    let mut accepted = vec![];
+    let mut rejected = Vec::with_capacity(events.len());
+    for (priority, event) in events.drain(..) {
+        match split(event) {
+            Ok(event) => accepted.push((priority, event)),
+            Err(event) => rejected.push((priority, event)),
+        }
+    }
+    *events = rejected;
+    accepted
+}
 
-/// event scheduler
-#[derive(Debug, Clone)]
-pub struct EventScheduler<E: Event> {
-    /// event list with inserted order by LocalEventTime's asc.
-    event_list: Vec<(LocalEventTime, Schedule, Priority, E)>,
+/// remove the items which match the predicate and return them.
+/// both the removed items and the kept items keep their order.
+pub(crate) fn extract_matching<T, F>(items: &mut Vec<T>, mut predicate: F) -> Vec<T>
+where
+    F: FnMut(&T) -> bool,
+{
+    let mut extracted = Vec::new();
+    let mut index = 0;
+    while index < items.len() {
+        if predicate(&items[index]) {
+            extracted.push(items.remove(index));
+        } else {
+            index += 1;
+        }
+    }
+    extracted
 }
 
-impl<E: Event> EventScheduler<E> {
-    /// initializer
-    pub(crate) fn new() -> Self {
-        EventScheduler { event_list: vec![] }
+/// timer of the event which follow the anchor. the time which is already past is the next frame.
+fn to_following_timer(time: i64) -> LocalEventTime {
+    LocalEventTime::try_from(time.max(1)).unwrap_or(LocalEventTime::MAX)
+}
+
+/// priority of the event. the event of the higher priority fire first in the same frame.
+/// Priority::MIN is the lowest priority, Priority::MAX is the highest priority. the default is the lowest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Priority(u8);
+
+impl Priority {
+    /// the lowest priority
+    pub const MIN: Priority = Priority(u8::MIN);
+    /// the highest priority
+    pub const MAX: Priority = Priority(u8::MAX);
+    /// priority for the background events
+    pub const LOW: Priority = Priority(64);
+    /// priority for the usual events
+    pub const NORMAL: Priority = Priority(128);
+    /// priority for the events which should fire before the usual events
+    pub const HIGH: Priority = Priority(192);
+    /// priority for the events which must fire first, e.g. the failure. same as MAX.
+    pub const CRITICAL: Priority = Priority(u8::MAX);
+
+    /// initializer from the level. the larger level is the higher priority.
+    pub const fn new(level: u8) -> Self {
+        Priority(level)
     }
 
-    /// calc next state and fetch fired events
-    pub(crate) fn next_time_and_fire<R: Rng + ?Sized>(
-        &mut self,
-        rng: &mut R,
-    ) -> Vec<(Priority, E)> {
-        let mut removed: usize = 0;
-        for event in self.event_list.iter_mut() {
-            if event.0 > 0 {
-                event.0 -= 1;
-            }
-            if event.0 == 0 {
-                removed += 1;
-            }
-        }
+    /// getter for the level
+    pub const fn level(self) -> u8 {
+        self.0
+    }
 
-        let fired_events: Vec<(Schedule, Priority, E)> = self
-            .event_list
-            .drain(0..removed)
-            .map(|(_, s, pty, e)| (s, pty, e))
-            .collect();
+    /// judge this priority fire before the other priority
+    pub fn higher_than(self, other: Priority) -> bool {
+        self > other
+    }
 
-        // reschedule for calculated next event schedule
-        for (schedule, pty, event) in fired_events.iter() {
-            if let Some(next_schedule) = schedule.to_next() {
-                // scheduled event's schedule is already validated
-                self.schedule(rng, next_schedule, *pty, event.clone())
-                    .unwrap();
-            }
-        }
+    /// judge this priority fire after the other priority
+    pub fn lower_than(self, other: Priority) -> bool {
+        self < other
+    }
 
-        return fired_events.into_iter().map(|(_, p, e)| (p, e)).collect();
+    /// priority which is raised by the levels. saturated at the highest.
+    pub fn raised(self, levels: u8) -> Self {
+        Priority(self.0.saturating_add(levels))
     }
 
-    //
-    // get state of scheduler state
-    //
+    /// priority which is lowered by the levels. saturated at the lowest.
+    pub fn lowered(self, levels: u8) -> Self {
+        Priority(self.0.saturating_sub(levels))
+    }
+}
 
-    /// judge exist scheduled event
-    pub fn have_event(&self) -> bool {
-        !self.event_list.is_empty()
+impl From<u8> for Priority {
+    fn from(level: u8) -> Self {
+        Priority(level)
+    }
+}
+
+impl From<Priority> for u8 {
+    fn from(priority: Priority) -> Self {
+        priority.0
     }
+}
 
-    /// get length of scheduled events
-    pub fn count(&self) -> usize {
-        self.event_list.len()
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
     }
+}
 
-    //
-    // schedule event
-    //
+impl std::str::FromStr for Priority {
+    type Err = std::num::ParseIntError;
 
-    /// clear all scheduled events
-    pub fn clear(&mut self) {
-        self.event_list.clear();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Priority)
     }
+}
 
-    /// remove scheduled events when predicate function is true
-    pub fn remove_when<P>(&mut self, mut predicate: P)
-    where
-        P: FnMut(&(LocalEventTime, Schedule, Priority, E)) -> bool,
-    {
-        self.event_list.retain(|state| !predicate(state))
+/// identifier of the scheduling domain
+pub type DomainId = u32;
+
+/// domain which the event scheduled directly by the scheduler belong to
+pub const ROOT_DOMAIN: DomainId = 0;
+
+/// window of the tag whose fired events are held until the end of the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Batch {
+    /// frames of the window from the first held event
+    window: LocalEventTime,
+    /// frames until the held events are delivered. None is no held event.
+    remaining: Option<LocalEventTime>,
+}
+
+/// control state of the scheduling domain
+#[derive(Debug, Clone, PartialEq)]
+struct DomainState {
+    paused: bool,
+    scale: f64,
+    /// elapsed time which is not applied to the timers yet
+    progress: f64,
+}
+
+impl DomainState {
+    /// judge the time of the domain is same as the parent
+    fn is_default(&self) -> bool {
+        !self.paused && self.scale == 1.0
     }
 
-    /// retains only the scheduled events specified by the predicate.
-    #[allow(unused_mut)]
-    pub fn retain<P>(&mut self, mut predicate: P)
-    where
-        P: FnMut(&(LocalEventTime, Schedule, Priority, E)) -> bool,
-    {
-        self.event_list.retain(predicate)
+    /// elapsed local time in this frame
+    fn advance(&mut self) -> LocalEventTime {
+        if self.paused {
+            return 0;
+        }
+        self.progress += self.scale;
+        let step = self.progress.floor();
+        self.progress -= step;
+        step as LocalEventTime
     }
+}
 
-    /// store event with scheduling
-    pub fn schedule<R: Rng + ?Sized>(
-        &mut self,
-        rng: &mut R,
-        schedule: Schedule,
-        priority: Priority,
-        event: E,
-    ) -> Result<(), ScheduleEventError> {
-        let mut index: usize = 0;
-        let timer: LocalEventTime = schedule.to_local_timer(rng)?;
+/// policy which boost the priority of the event the longer the event wait in the scheduler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aging {
+    every: LocalEventTime,
+    step: u8,
+    max: Priority,
+}
 
-        for (count, _, pty, _) in self.event_list.iter() {
-            if (&timer == count && &priority > pty) || &timer < count {
-                break;
-            }
-            index += 1;
+impl Aging {
+    /// boost the priority by the levels of step every the frames which the event wait. every 0 is treated as 1.
+    pub fn new(every: LocalEventTime, step: u8) -> Self {
+        Aging {
+            every: every.max(1),
+            step,
+            max: Priority::MAX,
         }
-        self.event_list
-            .insert(index, (timer, schedule, priority, event));
-        Ok(())
     }
 
-    /// store event with scheduling when user judge ok from all scheduled events
-    pub fn schedule_when<R: Rng + ?Sized, P>(
+    /// limit of the boosted priority. the priority which is higher than the limit is not changed.
+    pub fn max_priority(mut self, max: Priority) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// effective priority of the event which wait for the frames
+    pub fn boost(&self, priority: Priority, waited: LocalEventTime) -> Priority {
+        if priority >= self.max {
+            return priority;
+        }
+        let steps = u8::try_from(waited / self.every).unwrap_or(u8::MAX);
+        priority
+            .raised(steps.saturating_mul(self.step))
+            .min(self.max)
+    }
+}
+
+/// scheduling requests which are stored into the scheduler later, e.g. by the step of the part in parallel
+#[derive(Debug, Clone)]
+pub struct ScheduleRequests<E> {
+    requests: Vec<(Schedule, Priority, E)>,
+}
+
+impl<E> Default for ScheduleRequests<E> {
+    fn default() -> Self {
+        ScheduleRequests { requests: vec![] }
+    }
+}
+
+impl<E: Event> ScheduleRequests<E> {
+    /// initializer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// request to store event with scheduling. the invalid schedule is error when requested.
+    pub fn schedule(
         &mut self,
-        rng: &mut R,
         schedule: Schedule,
         priority: Priority,
         event: E,
-        predicate: P,
-    ) -> Result<(), ScheduleEventError>
-    where
-        P: FnOnce(&Self) -> bool,
-    {
-        if !predicate(&self) {
-            return Ok(());
+    ) -> Result<(), ScheduleEventError> {
+        if let Some(problem) = schedule.validate().into_iter().next() {
+            return Err(problem.into());
         }
-        self.schedule(rng, schedule, priority, event)
+        self.requests.push((schedule, priority, event));
+        Ok(())
     }
 
-    /// store event which fire at immediate timing
-    pub fn immediate<R: Rng + ?Sized>(
+    /// request to store event which fire at immediate timing
+    pub fn immediate(&mut self, priority: Priority, event: E) {
+        self.requests.push((Schedule::Immediate, priority, event));
+    }
+
+    /// request to store event which fire after timeout
+    pub fn timeout(
         &mut self,
-        rng: &mut R,
+        timeout: EventTimer,
         priority: Priority,
         event: E,
     ) -> Result<(), ScheduleEventError> {
-        self.schedule(rng, Schedule::Immediate, priority, event)
+        self.schedule(Schedule::Timeout(timeout), priority, event)
     }
 
-    /// store event which fire after timeout
-    pub fn timeout<R: Rng + ?Sized>(
-        &mut self,
+    /// count of the requests
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// judge no request
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// store all requested events into the scheduler in requested order with the rng of the scheduler.
+    /// return the identifiers of the stored events.
+    /// the requests are validated when requested, so this is never error for the requests made by the methods of ScheduleRequests.
+    pub fn schedule_into<R: Rng + ?Sized>(
+        self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+    ) -> Result<Vec<EventId>, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.requests
+            .into_iter()
+            .map(|(schedule, priority, event)| scheduler.schedule(rng, schedule, priority, event))
+            .collect()
+    }
+}
+
+/// builder of the scheduling which is created by EventScheduler::build, e.g.
+/// `scheduler.build(event).priority(Priority::HIGH).every(EventTimer::Time(10)).times(5).with_tag("spawn").schedule(rng)?`.
+///
+/// the event fire at immediate timing with the lowest priority if not specified.
+#[derive(Debug)]
+pub struct ScheduleBuilder<'a, E: Event> {
+    scheduler: &'a mut EventScheduler<E>,
+    domain: DomainId,
+    event: E,
+    schedule: Schedule,
+    priority: Priority,
+    times: Option<u8>,
+    conditions: Conditions,
+}
+
+impl<'a, E: Event> ScheduleBuilder<'a, E> {
+    /// initializer for the event in the domain
+    fn new(scheduler: &'a mut EventScheduler<E>, domain: DomainId, event: E) -> Self {
+        ScheduleBuilder {
+            scheduler,
+            domain,
+            event,
+            schedule: Schedule::Immediate,
+            priority: Priority::default(),
+            times: None,
+            conditions: Conditions::default(),
+        }
+    }
+
+    /// priority of the event
+    pub fn priority<P: Into<Priority>>(mut self, priority: P) -> Self {
+        self.priority = priority.into();
+        self
+    }
+
+    /// fire at immediate timing
+    pub fn immediate(mut self) -> Self {
+        self.schedule = Schedule::Immediate;
+        self
+    }
+
+    /// fire once after the timeout
+    pub fn after(mut self, timeout: EventTimer) -> Self {
+        self.schedule = Schedule::Timeout(timeout);
+        self
+    }
+
+    /// fire every frame
+    pub fn every_frame(mut self) -> Self {
+        self.schedule = Schedule::Everytime;
+        self
+    }
+
+    /// fire every interval
+    pub fn every(mut self, interval: EventTimer) -> Self {
+        self.schedule = Schedule::EveryInterval(interval);
+        self
+    }
+
+    /// fire on the frames whose index is the offset modulo the period
+    pub fn periodic(mut self, period: LocalEventTime, offset: LocalEventTime) -> Self {
+        self.schedule = Schedule::Periodic { period, offset };
+        self
+    }
+
+    /// fire by the schedule
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// limit the count of the fires of every or every_frame. every_frame is the interval of 1 frame.
+    /// the one-shot schedule accept only 1, and the periodic schedule cannot be limited.
+    pub fn times(mut self, count: u8) -> Self {
+        self.times = Some(count);
+        self
+    }
+
+    /// guard which is checked when the event fire
+    pub fn with_guard(mut self, guard: Guard) -> Self {
+        self.conditions.guard = Some(guard);
+        self
+    }
+
+    /// frames which the event can wait. if not fired within, the event is expired.
+    pub fn with_ttl(mut self, ttl: LocalEventTime) -> Self {
+        self.conditions.ttl = Some(ttl);
+        self
+    }
+
+    /// tag of the group of the event
+    pub fn with_tag(mut self, tag: Tag) -> Self {
+        self.conditions.tag = Some(tag);
+        self
+    }
+
+    /// entity which own the event
+    pub fn with_owner(mut self, owner: OwnerId) -> Self {
+        self.conditions.owner = Some(owner);
+        self
+    }
+
+    /// schedule with the count of the fires
+    fn resolved_schedule(&self) -> Result<Schedule, ScheduleEventError> {
+        let count = match self.times {
+            Some(count) => count,
+            None => return Ok(self.schedule.clone()),
+        };
+        match &self.schedule {
+            Schedule::EveryInterval(interval) | Schedule::Repeat(_, interval) => {
+                Ok(Schedule::Repeat(count, interval.clone()))
+            }
+            Schedule::Everytime => Ok(Schedule::Repeat(count, EventTimer::Time(1))),
+            Schedule::Immediate | Schedule::Timeout(_) if count == 1 => Ok(self.schedule.clone()),
+            _ => Err(ScheduleEventError::CannotFireEvent),
+        }
+    }
+
+    /// store the event with the built scheduling. return the identifier of the event.
+    pub fn schedule<R: Rng + ?Sized>(self, rng: &mut R) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        let schedule = self.resolved_schedule()?;
+        self.scheduler.schedule_in(
+            rng,
+            self.domain,
+            schedule,
+            self.priority,
+            self.event,
+            self.conditions,
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// store the event which cannot be cloned with the built scheduling. the recurring schedule is error.
+    pub fn schedule_once<R: Rng + ?Sized>(
+        self,
+        rng: &mut R,
+    ) -> Result<EventId, ScheduleEventError> {
+        let schedule = self.resolved_schedule()?;
+        self.scheduler.schedule_in(
+            rng,
+            self.domain,
+            schedule,
+            self.priority,
+            self.event,
+            self.conditions,
+            None,
+        )
+    }
+}
+
+/// key of the guard which the model open and close, e.g. identifier of the agent or the resource
+pub type GuardKey = u64;
+
+/// tag of the group of the events, e.g. the name of the subsystem which own the events
+pub type Tag = &'static str;
+
+/// identifier of the entity which own the events, e.g. identifier of the agent
+pub type OwnerId = u64;
+
+/// action for the guarded event which fire while the guard is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnGuardClosed {
+    /// remove the event without firing. the recurring event is not scheduled again.
+    Skip,
+    /// fire the event after the frames again. the event is checked again at that time.
+    ///
+    /// args is the frames to defer. 0 is treated as 1.
+    Defer(LocalEventTime),
+}
+
+/// guard which is checked when the event fire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guard {
+    key: GuardKey,
+    on_closed: OnGuardClosed,
+}
+
+impl Guard {
+    /// guard which skip the event when the guard is closed
+    pub fn skip(key: GuardKey) -> Self {
+        Guard {
+            key,
+            on_closed: OnGuardClosed::Skip,
+        }
+    }
+
+    /// guard which defer the event for the frames when the guard is closed
+    pub fn defer(key: GuardKey, frames: LocalEventTime) -> Self {
+        Guard {
+            key,
+            on_closed: OnGuardClosed::Defer(frames),
+        }
+    }
+
+    /// getter for key
+    pub fn key(&self) -> GuardKey {
+        self.key
+    }
+
+    /// getter for action when the guard is closed
+    pub fn on_closed(&self) -> OnGuardClosed {
+        self.on_closed
+    }
+}
+
+/// identifier of the scheduled event. the recurring event keep the identifier for each fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventId(u64);
+
+impl EventId {
+    /// getter for the raw value
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// reason why the event is scheduled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cause {
+    /// scheduled outside the handling of the fired events, e.g. in initializing or by the host between the frames
+    External,
+    /// scheduled while handling the fired events. the identifier is the handled event if known, e.g. when the model step for each event.
+    Handler(Option<EventId>),
+    /// rescheduled for the next fire of the recurring schedule
+    Recurring,
+}
+
+/// origin of the scheduled event which is recorded after EventScheduler::track_provenance is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    /// index of the frame which the scheduler fired last when scheduled. 0 is before the first frame, e.g. in initializing.
+    pub scheduled_at: FrameIndex,
+    /// reason why the event is scheduled
+    pub cause: Cause,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.cause {
+            Cause::External => write!(f, "scheduled at frame {}", self.scheduled_at),
+            Cause::Handler(Some(id)) => write!(
+                f,
+                "scheduled at frame {} by handler of #{}",
+                self.scheduled_at, id.0
+            ),
+            Cause::Handler(None) => {
+                write!(f, "scheduled at frame {} by handler", self.scheduled_at)
+            }
+            Cause::Recurring => write!(f, "rescheduled at frame {}", self.scheduled_at),
+        }
+    }
+}
+
+/// maker of the event for the next fire of the recurring event
+enum Recurrence<E> {
+    /// clone the fired event
+    Clone(fn(&E) -> E),
+    /// make the event from the template which is kept in the scheduler and shared by all fires
+    Shared(Arc<dyn Fn() -> E + Send + Sync>),
+}
+
+impl<E> Recurrence<E> {
+    /// event for the next fire
+    fn next_event(&self, fired: &E) -> E {
+        match self {
+            Recurrence::Clone(cloner) => cloner(fired),
+            Recurrence::Shared(template) => template(),
+        }
+    }
+}
+
+impl<E> Clone for Recurrence<E> {
+    fn clone(&self) -> Self {
+        match self {
+            Recurrence::Clone(cloner) => Recurrence::Clone(*cloner),
+            Recurrence::Shared(template) => Recurrence::Shared(Arc::clone(template)),
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for Recurrence<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Clone(_) => write!(f, "Clone"),
+            Recurrence::Shared(_) => write!(f, "Shared"),
+        }
+    }
+}
+
+/// scheduled event with the information for the scheduler
+#[derive(Debug, Clone)]
+struct Entry<E: Event> {
+    id: EventId,
+    state: (LocalEventTime, Schedule, Priority, E),
+    domain: DomainId,
+    /// frames which the event wait from scheduled
+    age: LocalEventTime,
+    guard: Option<Guard>,
+    /// frames which the event can wait. if not fired within, the event is expired.
+    ttl: Option<LocalEventTime>,
+    /// offset of the jitter from the base schedule at this fire
+    jitter: i64,
+    tag: Option<Tag>,
+    owner: Option<OwnerId>,
+    /// make the event for the next fire of the recurring schedule. None is the one-shot event.
+    recurrence: Option<Recurrence<E>>,
+    /// origin of the event. None is not tracked.
+    provenance: Option<Provenance>,
+}
+
+/// conditions of the event which is checked by the scheduler
+#[derive(Debug, Clone, Copy, Default)]
+struct Conditions {
+    guard: Option<Guard>,
+    ttl: Option<LocalEventTime>,
+    tag: Option<Tag>,
+    owner: Option<OwnerId>,
+}
+
+impl<E: Event> Entry<E> {
+    /// judge the event is expired
+    fn is_expired(&self) -> bool {
+        self.ttl.map_or(false, |ttl| self.age >= ttl)
+    }
+
+    /// convert the event type with keeping the other information
+    fn map<T: Event + Clone + 'static>(self, convert: fn(E) -> T) -> Entry<T>
+    where
+        E: 'static,
+    {
+        let (timer, schedule, priority, event) = self.state;
+        Entry {
+            id: self.id,
+            state: (timer, schedule, priority, convert(event)),
+            domain: self.domain,
+            age: self.age,
+            guard: self.guard,
+            ttl: self.ttl,
+            jitter: self.jitter,
+            tag: self.tag,
+            owner: self.owner,
+            recurrence: self.recurrence.map(|recurrence| match recurrence {
+                Recurrence::Clone(_) => Recurrence::Clone(T::clone),
+                Recurrence::Shared(template) => {
+                    Recurrence::Shared(Arc::new(move || convert(template())))
+                }
+            }),
+            provenance: self.provenance,
+        }
+    }
+
+    /// template of the recurring event which is shared by all fires
+    fn template(&self) -> Option<Arc<dyn Fn() -> E + Send + Sync>> {
+        match &self.recurrence {
+            Some(Recurrence::Shared(template)) => Some(Arc::clone(template)),
+            _ => None,
+        }
+    }
+
+    /// convert the event type if the split accept the event, or give back the entry
+    #[allow(clippy::result_large_err)]
+    fn split<T: Event + Clone + 'static>(
+        self,
+        split: fn(E) -> Result<T, E>,
+    ) -> Result<Entry<T>, Entry<E>>
+    where
+        E: 'static,
+    {
+        let (timer, schedule, priority, event) = self.state;
+        let event = match split(event) {
+            Ok(event) => event,
+            Err(event) => {
+                return Err(Entry {
+                    state: (timer, schedule, priority, event),
+                    ..self
+                })
+            }
+        };
+        Ok(Entry {
+            id: self.id,
+            state: (timer, schedule, priority, event),
+            domain: self.domain,
+            age: self.age,
+            guard: self.guard,
+            ttl: self.ttl,
+            jitter: self.jitter,
+            tag: self.tag,
+            owner: self.owner,
+            recurrence: self.recurrence.map(|recurrence| match recurrence {
+                Recurrence::Clone(_) => Recurrence::Clone(T::clone),
+                Recurrence::Shared(template) => {
+                    Recurrence::Shared(Arc::new(move || match split(template()) {
+                        Ok(event) => event,
+                        Err(_) => unreachable!("the template make the event of the other side"),
+                    }))
+                }
+            }),
+            provenance: self.provenance,
+        })
+    }
+}
+
+/// event which will fire in the next frame, which is reported by the peek of the next frame
+#[derive(Debug, Clone)]
+pub struct PeekedEvent<'a, E> {
+    /// identifier of the event
+    pub id: EventId,
+    /// priority at the fire including the boost by the aging
+    pub priority: Priority,
+    /// schedule of this fire
+    pub schedule: &'a Schedule,
+    /// event which will fire
+    pub event: &'a E,
+    /// schedule of the next fire and the frames until the next fire. None is not rescheduled.
+    pub next: Option<(Schedule, LocalEventTime)>,
+    /// origin of the event. None is not tracked.
+    pub provenance: Option<Provenance>,
+}
+
+impl<'a, E> PeekedEvent<'a, E> {
+    /// judge the event will be rescheduled after the fire
+    pub fn is_rescheduled(&self) -> bool {
+        self.next.is_some()
+    }
+}
+
+/// actions of the owner of the scheduler for the frame which is fired by EventScheduler::fire_frame
+pub(crate) trait FrameActions<E: Event, R: ?Sized> {
+    /// error of the handle
+    type Error;
+
+    #[allow(unused_variables)]
+    /// notice of the events which are fired and handed to the actions
+    fn fired(&mut self, fired_events: &[(Priority, E)]) {
+        // usually not use
+    }
+
+    #[allow(unused_variables)]
+    /// action for the expired event. the default drop the event.
+    fn expired(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        priority: Priority,
+        event: E,
+    ) {
+        // usually not use
+    }
+
+    /// action for the held events whose window is closed
+    fn batch(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        tag: Tag,
+        events: Vec<(Priority, E)>,
+    );
+
+    #[allow(unused_variables)]
+    /// action before the fired events are handled
+    fn before_first(&mut self, rng: &mut R, scheduler: &mut EventScheduler<E>) {
+        // usually not use
+    }
+
+    /// handle the fired events of the frame or the cascade
+    fn handle(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) -> Result<(), Self::Error>;
+
+    #[allow(unused_variables)]
+    /// action after all fired events are handled
+    fn after_last(&mut self, rng: &mut R, scheduler: &mut EventScheduler<E>) {
+        // usually not use
+    }
+}
+
+/// event scheduler
+#[derive(Debug, Clone)]
+pub struct EventScheduler<E: Event> {
+    /// event list with inserted order by LocalEventTime's asc.
+    event_list: Vec<Entry<E>>,
+    /// domains which are created. the root domain is not contained.
+    domains: BTreeMap<DomainId, DomainState>,
+    next_domain: DomainId,
+    /// buffer for the fired entries which is reused in each frame
+    fired: Vec<Entry<E>>,
+    aging: Option<Aging>,
+    /// annotations which are not attached to the frame yet
+    annotations: Vec<String>,
+    closed_guards: BTreeSet<GuardKey>,
+    /// tags of the events which timers are stopped
+    suspended_tags: BTreeSet<Tag>,
+    /// count of the guarded events which are skipped
+    skipped: usize,
+    next_id: u64,
+    /// events which wait the fire of the event. the timer is the offset from the fire.
+    pending: Vec<(EventId, Entry<E>)>,
+    /// events which follow the remaining time of the anchor event as (event, anchor, offset)
+    relations: Vec<(EventId, EventId, i64)>,
+    /// limit of the cascades in one frame. None is no cascade.
+    cascade: Option<usize>,
+    /// count of the frames which reach the limit of the cascades
+    truncated_cascades: usize,
+    /// expired events which are not handed to the model yet
+    expired: Vec<(Priority, E)>,
+    /// count of the expired events
+    expired_count: usize,
+    /// kind of the event to count the fired events. None is not counted.
+    key_of: Option<fn(&E) -> &'static str>,
+    /// count of the fired events for each kind
+    fired_by_key: BTreeMap<&'static str, u64>,
+    /// windows of the tags whose fired events are delivered together
+    batches: BTreeMap<Tag, Batch>,
+    /// fired events which are held in the windows
+    held: BTreeMap<Tag, Vec<(Priority, E)>>,
+    /// held events whose window is closed and which are not handed to the model yet
+    released: Vec<(Tag, Vec<(Priority, E)>)>,
+    /// index of the frame which is simulated last. the periodic schedules are aligned to this.
+    clock: FrameIndex,
+    /// cause of the events which are scheduled now. None is not tracked.
+    provenance: Option<Cause>,
+    /// identifiers and origins of the events which are fired in the last pass in fired order
+    fired_provenance: Vec<(EventId, Option<Provenance>)>,
+}
+
+impl<E: Event> EventScheduler<E> {
+    /// initializer
+    pub(crate) fn new() -> Self {
+        EventScheduler {
+            event_list: vec![],
+            domains: BTreeMap::new(),
+            next_domain: ROOT_DOMAIN + 1,
+            fired: vec![],
+            aging: None,
+            annotations: vec![],
+            closed_guards: BTreeSet::new(),
+            suspended_tags: BTreeSet::new(),
+            skipped: 0,
+            next_id: 0,
+            pending: vec![],
+            relations: vec![],
+            cascade: None,
+            truncated_cascades: 0,
+            expired: vec![],
+            expired_count: 0,
+            key_of: None,
+            fired_by_key: BTreeMap::new(),
+            batches: BTreeMap::new(),
+            held: BTreeMap::new(),
+            released: vec![],
+            clock: 0,
+            provenance: None,
+            fired_provenance: vec![],
+        }
+    }
+
+    /// remove all events and domains with keeping the allocated capacity
+    pub(crate) fn reset(&mut self) {
+        self.event_list.clear();
+        self.domains.clear();
+        self.next_domain = ROOT_DOMAIN + 1;
+        self.annotations.clear();
+        self.closed_guards.clear();
+        self.suspended_tags.clear();
+        self.skipped = 0;
+        self.next_id = 0;
+        self.pending.clear();
+        self.relations.clear();
+        self.truncated_cascades = 0;
+        self.expired.clear();
+        self.expired_count = 0;
+        self.fired_by_key.clear();
+        self.batches.clear();
+        self.held.clear();
+        self.released.clear();
+        self.clock = 0;
+        if self.provenance.is_some() {
+            self.provenance = Some(Cause::External);
+        }
+        self.fired_provenance.clear();
+    }
+
+    /// take the expired events which are not handed to the model yet
+    pub(crate) fn take_expired(&mut self) -> Vec<(Priority, E)> {
+        mem::take(&mut self.expired)
+    }
+
+    /// take the held events whose window is closed with the tags
+    pub(crate) fn take_batches(&mut self) -> Vec<(Tag, Vec<(Priority, E)>)> {
+        mem::take(&mut self.released)
+    }
+
+    /// take the annotations which are not attached to the frame yet
+    pub(crate) fn take_annotations(&mut self) -> impl Iterator<Item = String> + '_ {
+        self.annotations.drain(..)
+    }
+
+    /// calc next state and push fired events into the buffer.
+    /// the buffers are reused, so no allocation is occurred after enough capacity.
+    pub(crate) fn next_time_and_fire<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        self.clock = self.clock.saturating_add(1);
+        let mut fired = mem::take(&mut self.fired);
+        if self.suspended_tags.is_empty() && self.domains.values().all(|state| state.is_default()) {
+            let mut removed: usize = 0;
+            for entry in self.event_list.iter_mut() {
+                entry.age = entry.age.saturating_add(1);
+                let event = &mut entry.state;
+                if event.0 > 0 {
+                    event.0 -= 1;
+                }
+                if event.0 == 0 {
+                    removed += 1;
+                }
+            }
+            fired.extend(self.event_list.drain(0..removed));
+        } else {
+            // elapsed time of the domain which is paused or scaled
+            let steps: BTreeMap<DomainId, LocalEventTime> = self
+                .domains
+                .iter_mut()
+                .filter(|(_, state)| !state.is_default())
+                .map(|(id, state)| (*id, state.advance()))
+                .collect();
+            let suspended_tags = &self.suspended_tags;
+            let step_of = |entry: &Entry<E>| {
+                if entry.tag.map_or(false, |tag| suspended_tags.contains(tag)) {
+                    0
+                } else {
+                    steps.get(&entry.domain).copied().unwrap_or(1)
+                }
+            };
+            for entry in self.event_list.iter_mut() {
+                let step = step_of(entry);
+                entry.state.0 = entry.state.0.saturating_sub(step);
+                entry.age = entry.age.saturating_add(1);
+            }
+            // the order is changed by the time of each domain
+            self.event_list
+                .sort_by(|a, b| a.state.0.cmp(&b.state.0).then(b.state.2.cmp(&a.state.2)));
+            fired.extend(extract_matching(&mut self.event_list, |entry| {
+                entry.state.0 == 0 && step_of(entry) != 0
+            }));
+        }
+
+        if self.event_list.iter().any(|entry| entry.is_expired()) {
+            let expired = extract_matching(&mut self.event_list, |entry| entry.is_expired());
+            for entry in expired {
+                self.expired_count += 1;
+                // the dependent events never start
+                self.cancel_pending(entry.id);
+                let (_, _, priority, event) = entry.state;
+                self.expired.push((priority, event));
+            }
+        }
+
+        self.settle(rng, fired, fired_events);
+
+        // the window is counted in the frames which include the frame of the first held event
+        for (tag, batch) in self.batches.iter_mut() {
+            if let Some(remaining) = batch.remaining.as_mut() {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    batch.remaining = None;
+                    let events = self.held.remove(tag).unwrap_or_default();
+                    self.released.push((*tag, events));
+                }
+            }
+        }
+    }
+
+    /// fire the immediate events which are scheduled in this frame into the buffer when the cascade is enabled.
+    /// the pass is the count of the cascades which are already done in this frame.
+    /// return false when no event is fired.
+    pub(crate) fn fire_cascade<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        pass: usize,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) -> bool {
+        let limit = match self.cascade {
+            Some(limit) => limit,
+            None => return false,
+        };
+        let domains = &self.domains;
+        let suspended_tags = &self.suspended_tags;
+        let is_cascading = |entry: &Entry<E>| {
+            entry.age == 0
+                && matches!(entry.state.1, Schedule::Immediate)
+                && domains
+                    .get(&entry.domain)
+                    .map_or(true, |state| !state.paused)
+                && entry.tag.map_or(true, |tag| !suspended_tags.contains(tag))
+        };
+        if pass >= limit {
+            if self.event_list.iter().any(is_cascading) {
+                self.truncated_cascades += 1;
+            }
+            return false;
+        }
+        let mut fired = mem::take(&mut self.fired);
+        fired.extend(extract_matching(&mut self.event_list, is_cascading));
+        if fired.is_empty() {
+            self.fired = fired;
+            return false;
+        }
+        self.settle(rng, fired, fired_events);
+        !fired_events.is_empty()
+    }
+
+    /// fire the events of the next frame and hand them to the actions in the order of the frame:
+    /// the expired events, the batches, before_first, the fired events with the cascades and after_last.
+    /// if the handle fail, the cascades stop and the events which the handle leave in the buffer are kept in it.
+    pub(crate) fn fire_frame<R, A>(
+        &mut self,
+        rng: &mut R,
+        fired_events: &mut Vec<(Priority, E)>,
+        actions: &mut A,
+    ) -> Result<(), A::Error>
+    where
+        R: Rng + ?Sized,
+        A: FrameActions<E, R>,
+    {
+        self.next_time_and_fire(rng, fired_events);
+        actions.fired(fired_events);
+        for (priority, event) in self.take_expired() {
+            actions.expired(rng, self, priority, event);
+        }
+        for (tag, events) in self.take_batches() {
+            actions.fired(&events);
+            actions.batch(rng, self, tag, events);
+        }
+        actions.before_first(rng, self);
+        let mut result = actions.handle(rng, self, fired_events);
+        let mut pass = 0;
+        while result.is_ok() {
+            fired_events.clear();
+            if !self.fire_cascade(rng, pass, fired_events) {
+                break;
+            }
+            pass += 1;
+            actions.fired(fired_events);
+            result = actions.handle(rng, self, fired_events);
+        }
+        actions.after_last(rng, self);
+        self.finish_handling();
+        result
+    }
+
+    /// check the guards, reschedule and start the waiting events for the fired entries, and push the fired events into the buffer
+    fn settle<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        mut fired: Vec<Entry<E>>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        if !self.closed_guards.is_empty() {
+            let closed_guards = &self.closed_guards;
+            let blocked = extract_matching(&mut fired, |entry| {
+                entry
+                    .guard
+                    .map_or(false, |guard| closed_guards.contains(&guard.key))
+            });
+            for mut entry in blocked {
+                // guard is checked in extracting
+                match entry.guard.unwrap().on_closed {
+                    OnGuardClosed::Skip => {
+                        self.skipped += 1;
+                        // the dependent events never start
+                        self.cancel_pending(entry.id);
+                    }
+                    OnGuardClosed::Defer(frames) => {
+                        entry.state.0 = frames.max(1);
+                        if let Schedule::Immediate = entry.state.1 {
+                            // not cascade again in this frame. both schedules fire only once.
+                            entry.state.1 = Schedule::Timeout(EventTimer::Time(entry.state.0));
+                        }
+                        self.insert(entry);
+                    }
+                }
+            }
+        }
+
+        if !self.relations.is_empty() {
+            // the events which follow the deferred anchors wait them again
+            let waiting = extract_matching(&mut fired, |entry| {
+                self.following_time(entry.id).map_or(false, |time| time > 0)
+            });
+            for mut entry in waiting {
+                // following time is checked in extracting
+                entry.state.0 = to_following_timer(self.following_time(entry.id).unwrap());
+                self.insert(entry);
+            }
+            // the fired anchors are no longer followed
+            self.relations.retain(|(id, anchor, _)| {
+                !fired
+                    .iter()
+                    .any(|entry| entry.id == *id || entry.id == *anchor)
+            });
+            self.follow_anchors();
+        }
+
+        // reschedule for calculated next event schedule
+        for entry in fired.iter() {
+            let (_, schedule, pty, event) = &entry.state;
+            // the recurring event always has the recurrence
+            if let (Some(next_schedule), Some(recurrence)) =
+                (schedule.to_next(), entry.recurrence.as_ref())
+            {
+                // scheduled event's schedule is already validated
+                let (timer, jitter) = next_schedule
+                    .to_local_timer(rng, entry.jitter, self.clock)
+                    .unwrap();
+                self.insert(Entry {
+                    id: entry.id,
+                    state: (timer, next_schedule, *pty, recurrence.next_event(event)),
+                    domain: entry.domain,
+                    age: 0,
+                    guard: entry.guard,
+                    ttl: entry.ttl,
+                    jitter,
+                    tag: entry.tag,
+                    owner: entry.owner,
+                    recurrence: Some(recurrence.clone()),
+                    provenance: self.provenance.map(|_| Provenance {
+                        scheduled_at: self.clock,
+                        cause: Cause::Recurring,
+                    }),
+                });
+            }
+        }
+
+        // start the timers of the events which wait the fired events
+        if !self.pending.is_empty() {
+            for entry in fired.iter() {
+                let id = entry.id;
+                let started = extract_matching(&mut self.pending, |(after, _)| *after == id);
+                for (_, mut dependent) in started {
+                    dependent.state.0 = dependent.state.0.max(1);
+                    self.insert(dependent);
+                }
+            }
+        }
+
+        if let Some(aging) = self.aging {
+            for entry in fired.iter_mut() {
+                entry.state.2 = aging.boost(entry.state.2, entry.age);
+            }
+            // keep the scheduled order for the same effective priority
+            fired.sort_by_key(|entry| std::cmp::Reverse(entry.state.2));
+        }
+
+        if let Some(key_of) = self.key_of {
+            for entry in fired.iter() {
+                *self.fired_by_key.entry(key_of(&entry.state.3)).or_insert(0) += 1;
+            }
+        }
+
+        if !self.batches.is_empty() {
+            let batches = &self.batches;
+            let held = extract_matching(&mut fired, |entry| {
+                entry.tag.map_or(false, |tag| batches.contains_key(tag))
+            });
+            for entry in held {
+                // tag is checked in extracting
+                let batch = self.batches.get_mut(entry.tag.unwrap()).unwrap();
+                if batch.remaining.is_none() {
+                    batch.remaining = Some(batch.window);
+                }
+                let (_, _, p, e) = entry.state;
+                self.held
+                    .entry(entry.tag.unwrap())
+                    .or_default()
+                    .push((p, e));
+            }
+        }
+
+        if self.provenance.is_some() {
+            self.fired_provenance.clear();
+            self.fired_provenance
+                .extend(fired.iter().map(|entry| (entry.id, entry.provenance)));
+            if !fired.is_empty() {
+                self.provenance = Some(Cause::Handler(None));
+            }
+        }
+
+        fired_events.extend(fired.drain(..).map(|entry| {
+            let (_, _, p, e) = entry.state;
+            (p, e)
+        }));
+        self.fired = fired;
+    }
+
+    /// start to handle the fired event of the index in the last pass.
+    /// the events which are scheduled until the next call are caused by the handler of the event.
+    pub(crate) fn handle_fired(&mut self, index: usize) {
+        if self.provenance.is_some() {
+            let id = self.fired_provenance.get(index).map(|(id, _)| *id);
+            self.provenance = Some(Cause::Handler(id));
+        }
+    }
+
+    /// finish to handle the fired events in the frame.
+    /// the events which are scheduled after that are scheduled from outside.
+    pub(crate) fn finish_handling(&mut self) {
+        if self.provenance.is_some() {
+            self.provenance = Some(Cause::External);
+        }
+    }
+
+    /// origin of the event which is scheduled now. if not tracked, then None.
+    fn current_provenance(&self) -> Option<Provenance> {
+        self.provenance.map(|cause| Provenance {
+            scheduled_at: self.clock,
+            cause,
+        })
+    }
+
+    /// run the action with the projection of this scheduler, e.g. for the sub-model of Composed.
+    /// the projection has the scheduled, held and expired events which the split accept, the settings and the counters,
+    /// so the action can count, cancel and wait the events which are scheduled before.
+    /// they are given back with the convert after the action.
+    pub(crate) fn with_projection<T, F>(
+        &mut self,
+        split: fn(E) -> Result<T, E>,
+        convert: fn(T) -> E,
+        action: F,
+    ) where
+        E: Clone + 'static,
+        T: Event + Clone + 'static,
+        F: FnOnce(&mut EventScheduler<T>),
+    {
+        // the index in the event list keep the order of the events at the same time and the same priority
+        let mut origins: BTreeMap<EventId, usize> = BTreeMap::new();
+        let mut templates = BTreeMap::new();
+        let mut event_list = vec![];
+        let mut kept = vec![];
+        for (index, entry) in mem::take(&mut self.event_list).into_iter().enumerate() {
+            let template = entry.template();
+            match entry.split(split) {
+                Ok(entry) => {
+                    origins.insert(entry.id, index);
+                    if let Some(template) = template {
+                        templates.insert(entry.id, template);
+                    }
+                    event_list.push(entry);
+                }
+                Err(entry) => kept.push((index, entry)),
+            }
+        }
+        let mut pending = vec![];
+        for (after, entry) in mem::take(&mut self.pending) {
+            let template = entry.template();
+            match entry.split(split) {
+                Ok(entry) => {
+                    if let Some(template) = template {
+                        templates.insert(entry.id, template);
+                    }
+                    pending.push((after, entry));
+                }
+                Err(entry) => self.pending.push((after, entry)),
+            }
+        }
+        let projected: BTreeSet<EventId> = event_list
+            .iter()
+            .map(|entry| entry.id)
+            .chain(pending.iter().map(|(_, entry)| entry.id))
+            .collect();
+        let relations = extract_matching(&mut self.relations, |(event, anchor, _)| {
+            projected.contains(event) && projected.contains(anchor)
+        });
+        let mut held = BTreeMap::new();
+        for (tag, events) in self.held.iter_mut() {
+            let events = split_events(events, split);
+            if !events.is_empty() {
+                held.insert(*tag, events);
+            }
+        }
+        let mut released = vec![];
+        for (tag, events) in self.released.iter_mut() {
+            let events = split_events(events, split);
+            if !events.is_empty() {
+                released.push((*tag, events));
+            }
+        }
+        let expired = split_events(&mut self.expired, split);
+        let remaining: BTreeMap<Tag, Option<LocalEventTime>> = self
+            .batches
+            .iter()
+            .map(|(tag, batch)| (*tag, batch.remaining))
+            .collect();
+
+        let mut projection = EventScheduler {
+            event_list,
+            domains: mem::take(&mut self.domains),
+            next_domain: self.next_domain,
+            fired: vec![],
+            aging: self.aging,
+            annotations: mem::take(&mut self.annotations),
+            closed_guards: mem::take(&mut self.closed_guards),
+            suspended_tags: mem::take(&mut self.suspended_tags),
+            skipped: self.skipped,
+            next_id: self.next_id,
+            pending,
+            relations,
+            cascade: self.cascade,
+            truncated_cascades: self.truncated_cascades,
+            expired,
+            expired_count: self.expired_count,
+            key_of: None,
+            fired_by_key: mem::take(&mut self.fired_by_key),
+            batches: mem::take(&mut self.batches),
+            held,
+            released,
+            clock: self.clock,
+            provenance: self.provenance,
+            fired_provenance: mem::take(&mut self.fired_provenance),
+        };
+        action(&mut projection);
+
+        self.domains = projection.domains;
+        self.next_domain = projection.next_domain;
+        self.aging = projection.aging;
+        self.annotations = projection.annotations;
+        self.closed_guards = projection.closed_guards;
+        self.suspended_tags = projection.suspended_tags;
+        self.skipped = projection.skipped;
+        self.next_id = projection.next_id;
+        self.cascade = projection.cascade;
+        self.truncated_cascades = projection.truncated_cascades;
+        self.expired_count = projection.expired_count;
+        self.fired_by_key = projection.fired_by_key;
+        self.batches = projection.batches;
+        self.clock = projection.clock;
+        self.provenance = projection.provenance;
+        self.fired_provenance = projection.fired_provenance;
+
+        let unproject = |entry: Entry<T>| {
+            let mut entry = entry.map(convert);
+            if let (Some(template), Some(Recurrence::Shared(_))) =
+                (templates.get(&entry.id), &entry.recurrence)
+            {
+                // restore the template not to nest the conversions at each projection
+                entry.recurrence = Some(Recurrence::Shared(Arc::clone(template)));
+            }
+            entry
+        };
+        let mut remained: BTreeSet<EventId> = BTreeSet::new();
+        let mut projected_list = projection.event_list.into_iter().peekable();
+        let mut kept = kept.into_iter().peekable();
+        self.event_list.reserve(projected_list.len() + kept.len());
+        loop {
+            let take_projected = match (projected_list.peek(), kept.peek()) {
+                (None, None) => break,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (Some(entry), Some((index, other))) => {
+                    let (timer, _, priority, _) = &entry.state;
+                    let (other_timer, _, other_priority, _) = &other.state;
+                    let origin = origins.get(&entry.id).copied().unwrap_or(usize::MAX);
+                    (timer, std::cmp::Reverse(priority), origin)
+                        < (other_timer, std::cmp::Reverse(other_priority), *index)
+                }
+            };
+            if take_projected {
+                if let Some(entry) = projected_list.next() {
+                    remained.insert(entry.id);
+                    self.event_list.push(unproject(entry));
+                }
+            } else if let Some((_, entry)) = kept.next() {
+                self.event_list.push(entry);
+            }
+        }
+        for (after, entry) in projection.pending {
+            remained.insert(entry.id);
+            self.pending.push((after, unproject(entry)));
+        }
+        self.relations.extend(projection.relations);
+        self.expired
+            .extend(projection.expired.into_iter().map(|(p, e)| (p, convert(e))));
+        for (tag, events) in projection.held {
+            self.held
+                .entry(tag)
+                .or_default()
+                .extend(events.into_iter().map(|(p, e)| (p, convert(e))));
+        }
+        self.held.retain(|_, events| !events.is_empty());
+        for (tag, events) in projection.released {
+            self.released.push((
+                tag,
+                events.into_iter().map(|(p, e)| (p, convert(e))).collect(),
+            ));
+        }
+        self.released.retain(|(_, events)| !events.is_empty());
+
+        // the events of the other side which wait or follow the removed events are removed together
+        for id in projected.difference(&remained) {
+            self.cancel_pending(*id);
+        }
+        for (tag, batch) in self.batches.iter_mut() {
+            // the window of the held events of the other side is kept even if the projection clear own events
+            if batch.remaining.is_none() && self.held.contains_key(tag) {
+                batch.remaining = remaining.get(tag).copied().flatten();
+            }
+        }
+        // the held events whose window is removed in the projection are delivered
+        if !self.held.is_empty() {
+            let batches = &self.batches;
+            let removed: Vec<Tag> = self
+                .held
+                .keys()
+                .filter(|tag| !batches.contains_key(*tag))
+                .copied()
+                .collect();
+            for tag in removed {
+                if let Some(events) = self.held.remove(tag) {
+                    self.released.push((tag, events));
+                }
+            }
+        }
+    }
+
+    /// store event with scheduling into the domain.
+    /// if the schedule is recurring and the event cannot be cloned, then error.
+    #[allow(clippy::too_many_arguments)]
+    fn schedule_in<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        domain: DomainId,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+        conditions: Conditions,
+        recurrence: Option<Recurrence<E>>,
+    ) -> Result<EventId, ScheduleEventError> {
+        if let Some(problem) = schedule.validate().into_iter().next() {
+            return Err(problem.into());
+        }
+        if recurrence.is_none() && schedule.to_next().is_some() {
+            return Err(ScheduleEventError::CannotFireEvent);
+        }
+        let (timer, jitter) = schedule.to_local_timer(rng, 0, self.clock)?;
+        let id = self.next_event_id();
+        self.insert(Entry {
+            id,
+            state: (timer, schedule, priority, event),
+            domain,
+            age: 0,
+            guard: conditions.guard,
+            ttl: conditions.ttl,
+            jitter,
+            tag: conditions.tag,
+            owner: conditions.owner,
+            recurrence,
+            provenance: self.current_provenance(),
+        });
+        Ok(id)
+    }
+
+    /// count of the identifiers which are issued
+    pub(crate) fn issued_count(&self) -> u64 {
+        self.next_id
+    }
+
+    /// issue the identifier for the new event
+    fn next_event_id(&mut self) -> EventId {
+        let id = EventId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// store event which start the timer when the event of the identifier fire
+    fn schedule_after_in(
+        &mut self,
+        domain: DomainId,
+        event_id: EventId,
+        offset: LocalEventTime,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        if !self.is_scheduled(event_id) {
+            return Err(ScheduleEventError::CannotFireEvent);
+        }
+        let id = self.next_event_id();
+        self.pending.push((
+            event_id,
+            Entry {
+                id,
+                state: (
+                    offset,
+                    Schedule::Timeout(EventTimer::Time(offset)),
+                    priority,
+                    event,
+                ),
+                domain,
+                age: 0,
+                guard: None,
+                ttl: None,
+                jitter: 0,
+                tag: None,
+                owner: None,
+                recurrence: None,
+                provenance: self.current_provenance(),
+            },
+        ));
+        Ok(id)
+    }
+
+    /// remove the entries which match the predicate with the events which wait them
+    fn remove_entries<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(&Entry<E>) -> bool,
+    {
+        let mut removed: Vec<EventId> =
+            extract_matching(&mut self.event_list, |entry| predicate(entry))
+                .into_iter()
+                .map(|entry| entry.id)
+                .collect();
+        removed.extend(
+            extract_matching(&mut self.pending, |(_, entry)| predicate(entry))
+                .into_iter()
+                .map(|(_, entry)| entry.id),
+        );
+        for id in removed {
+            self.cancel_pending(id);
+        }
+    }
+
+    /// remove the events which wait or follow the event with the events which wait them
+    fn cancel_pending(&mut self, id: EventId) {
+        let mut waited = vec![id];
+        while let Some(id) = waited.pop() {
+            waited.extend(
+                extract_matching(&mut self.pending, |(after, _)| *after == id)
+                    .into_iter()
+                    .map(|(_, entry)| entry.id),
+            );
+            if !self.relations.is_empty() {
+                let following: Vec<EventId> =
+                    extract_matching(&mut self.relations, |(event, anchor, _)| {
+                        *event == id || *anchor == id
+                    })
+                    .into_iter()
+                    .filter(|(_, anchor, _)| *anchor == id)
+                    .map(|(event, _, _)| event)
+                    .collect();
+                self.event_list
+                    .retain(|entry| !following.contains(&entry.id));
+                waited.extend(following);
+            }
+        }
+    }
+
+    /// frames until the event which follow the anchor should fire. if the event does not follow the scheduled anchor, then None.
+    /// the time which is already past is 0 or negative.
+    fn following_time(&self, id: EventId) -> Option<i64> {
+        let (_, anchor, offset) = self.relations.iter().find(|(event, _, _)| *event == id)?;
+        self.event_list
+            .iter()
+            .find(|entry| entry.id == *anchor)
+            .map(|entry| i64::from(entry.state.0).saturating_add(*offset))
+    }
+
+    /// move the events which follow the anchors to the remaining time of the anchors.
+    /// the time which is already past is the next frame.
+    fn follow_anchors(&mut self) {
+        for index in 0..self.relations.len() {
+            let id = self.relations[index].0;
+            let time = match self.following_time(id) {
+                Some(time) => to_following_timer(time),
+                None => continue,
+            };
+            if let Some(position) = self
+                .event_list
+                .iter()
+                .position(|entry| entry.id == id && entry.state.0 != time)
+            {
+                let mut entry = self.event_list.remove(position);
+                entry.state.0 = time;
+                self.insert(entry);
+            }
+        }
+    }
+
+    /// insert the entry after the entries which fire earlier or at same time with same or higher priority
+    fn insert(&mut self, entry: Entry<E>) {
+        let (timer, _, priority, _) = &entry.state;
+        let mut index: usize = 0;
+        for Entry {
+            state: (count, _, pty, _),
+            ..
+        } in self.event_list.iter()
+        {
+            if (timer == count && priority > pty) || timer < count {
+                break;
+            }
+            index += 1;
+        }
+        self.event_list.insert(index, entry);
+    }
+
+    /// attach the annotation to the current frame, e.g. "price shock applied".
+    /// the annotation is attached when the frame is finished.
+    pub fn annotate<S: Into<String>>(&mut self, text: S) {
+        self.annotations.push(text.into());
+    }
+
+    /// set the aging policy. None is no aging.
+    /// the fired events are handed with the boosted priority in order of the boosted priority.
+    pub fn set_aging(&mut self, aging: Option<Aging>) {
+        self.aging = aging;
+    }
+
+    /// getter for aging policy
+    pub fn aging(&self) -> Option<Aging> {
+        self.aging
+    }
+
+    /// set the limit of the cascades in one frame. None is no cascade.
+    /// in the cascade, the immediate events which are scheduled while handling the fired events
+    /// are fired and handled again in the same frame before after_last_event, up to the limit times.
+    /// the immediate events which remain at the limit fire in the next frame as usual.
+    pub fn set_cascade(&mut self, limit: Option<usize>) {
+        self.cascade = limit;
+    }
+
+    /// getter for the limit of the cascades
+    pub fn cascade(&self) -> Option<usize> {
+        self.cascade
+    }
+
+    /// count of the frames which reach the limit of the cascades with the immediate events remaining
+    pub fn truncated_cascades(&self) -> usize {
+        self.truncated_cascades
+    }
+
+    //
+    // scheduling domain
+    //
+
+    /// create new domain which has own controls and return the view of the domain
+    pub fn create_domain(&mut self) -> SchedulerDomain<'_, E> {
+        let id = self.next_domain;
+        self.next_domain += 1;
+        self.domains.insert(
+            id,
+            DomainState {
+                paused: false,
+                scale: 1.0,
+                progress: 0.0,
+            },
+        );
+        SchedulerDomain {
+            scheduler: self,
+            id,
+        }
+    }
+
+    /// get the view of the domain. if the domain is not exist, then None.
+    pub fn domain(&mut self, id: DomainId) -> Option<SchedulerDomain<'_, E>> {
+        if self.domains.contains_key(&id) {
+            Some(SchedulerDomain {
+                scheduler: self,
+                id,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// remove the domain with the scheduled events in the domain
+    pub fn remove_domain(&mut self, id: DomainId) {
+        if self.domains.remove(&id).is_some() {
+            self.remove_entries(|entry| entry.domain == id);
+        }
+    }
+
+    /// identifiers of created domains
+    pub fn domain_ids(&self) -> Vec<DomainId> {
+        self.domains.keys().copied().collect()
+    }
+
+    //
+    // get state of scheduler state
+    //
+
+    /// judge exist scheduled event
+    pub fn have_event(&self) -> bool {
+        !self.event_list.is_empty()
+    }
+
+    /// check the scheduled events are ordered by the time and then the priority,
+    /// the identifiers and domains are consistent and the recurring schedules can calculate the next timer.
+    /// this is checked after each frame when Simulator::enable_invariant_checks is called.
+    /// the check scan all scheduled events, so it cost O(n log n) for n scheduled events.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut ids = BTreeSet::new();
+        for (index, entry) in self.event_list.iter().enumerate() {
+            if index > 0 {
+                let (timer, _, priority, _) = &self.event_list[index - 1].state;
+                if (timer, std::cmp::Reverse(priority))
+                    > (&entry.state.0, std::cmp::Reverse(&entry.state.2))
+                {
+                    return Err(InvariantViolation::OutOfOrder(index));
+                }
+            }
+            if !entry.state.1.is_valid_recurrence() {
+                return Err(InvariantViolation::InvalidSchedule(entry.id));
+            }
+        }
+        for entry in self
+            .event_list
+            .iter()
+            .chain(self.pending.iter().map(|(_, entry)| entry))
+        {
+            if entry.domain != ROOT_DOMAIN && !self.domains.contains_key(&entry.domain) {
+                return Err(InvariantViolation::UnknownDomain(entry.id, entry.domain));
+            }
+            if entry.id.0 >= self.next_id {
+                return Err(InvariantViolation::UnissuedId(entry.id));
+            }
+            if !ids.insert(entry.id) {
+                return Err(InvariantViolation::DuplicatedId(entry.id));
+            }
+        }
+        for (after, entry) in self.pending.iter() {
+            if !ids.contains(after) {
+                return Err(InvariantViolation::OrphanedEvent(entry.id));
+            }
+        }
+        Ok(())
+    }
+
+    /// all problems of the schedules of the scheduled events and the events which wait other event with the identifiers
+    pub fn validate(&self) -> Vec<(EventId, ScheduleValidationError)> {
+        self.event_list
+            .iter()
+            .chain(self.pending.iter().map(|(_, entry)| entry))
+            .flat_map(|entry| {
+                let id = entry.id;
+                entry
+                    .state
+                    .1
+                    .validate()
+                    .into_iter()
+                    .map(move |problem| (id, problem))
+            })
+            .collect()
+    }
+
+    /// get length of scheduled events. the events which wait other event are not contained.
+    pub fn count(&self) -> usize {
+        self.event_list.len()
+    }
+
+    /// get length of the events which wait other event
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// events which will fire in the next frame in fired order without changing the scheduler.
+    /// the rng is used to sample the timers of the next fires, so the clone of the rng of the simulation predict same timers.
+    /// the events which are blocked by the closed guards are not contained,
+    /// and the immediate events which the model will schedule in the frame are not known yet.
+    pub(crate) fn peek_next<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<PeekedEvent<'_, E>> {
+        let is_default =
+            self.suspended_tags.is_empty() && self.domains.values().all(|state| state.is_default());
+        let step_of = |entry: &Entry<E>| {
+            if entry
+                .tag
+                .map_or(false, |tag| self.suspended_tags.contains(tag))
+            {
+                return 0;
+            }
+            match self.domains.get(&entry.domain) {
+                Some(state) if state.paused => 0,
+                Some(state) => (state.progress + state.scale).floor() as LocalEventTime,
+                None => 1,
+            }
+        };
+        let mut fired: Vec<&Entry<E>> = self
+            .event_list
+            .iter()
+            .filter(|entry| {
+                let step = step_of(entry);
+                step != 0 && entry.state.0.saturating_sub(step) == 0
+            })
+            .filter(|entry| {
+                entry
+                    .guard
+                    .map_or(true, |guard| !self.closed_guards.contains(&guard.key))
+            })
+            .collect();
+        if !is_default {
+            // the order is changed by the time of each domain
+            fired.sort_by_key(|entry| std::cmp::Reverse(entry.state.2));
+        }
+        let mut peeked: Vec<PeekedEvent<'_, E>> = fired
+            .into_iter()
+            .map(|entry| {
+                let (_, schedule, priority, event) = &entry.state;
+                let next = match (schedule.to_next(), &entry.recurrence) {
+                    (Some(next_schedule), Some(_)) => {
+                        // scheduled event's schedule is already validated
+                        let (timer, _) = next_schedule
+                            .to_local_timer(rng, entry.jitter, self.clock.saturating_add(1))
+                            .unwrap();
+                        Some((next_schedule, timer))
+                    }
+                    _ => None,
+                };
+                PeekedEvent {
+                    id: entry.id,
+                    priority: match self.aging {
+                        Some(aging) => aging.boost(*priority, entry.age.saturating_add(1)),
+                        None => *priority,
+                    },
+                    schedule,
+                    event,
+                    next,
+                    provenance: entry.provenance,
+                }
+            })
+            .collect();
+        if self.aging.is_some() {
+            peeked.sort_by_key(|event| std::cmp::Reverse(event.priority));
+        }
+        peeked
+    }
+
+    /// scheduled events with the frames until the event fire in fire order.
+    /// the events which wait other event are not contained.
+    pub(crate) fn scheduled_entries(
+        &self,
+    ) -> impl Iterator<Item = (EventId, LocalEventTime, Priority, &E)> + '_ {
+        self.event_list.iter().map(|entry| {
+            let (timer, _, priority, event) = &entry.state;
+            (entry.id, *timer, *priority, event)
+        })
+    }
+
+    /// scheduled events as the trace records with the frame which the event will fire at from the frame.
+    /// the events which wait other event are not contained.
+    pub(crate) fn timeline_records<L>(&self, frame: FrameIndex, mut labeler: L) -> Vec<TraceRecord>
+    where
+        L: FnMut(&E) -> String,
+    {
+        self.event_list
+            .iter()
+            .map(|entry| {
+                let (timer, schedule, priority, event) = &entry.state;
+                let mut label = labeler(event);
+                if schedule.to_next().is_some() {
+                    label.push_str(" (recurring)");
+                }
+                TraceRecord {
+                    frame: frame + FrameIndex::from((*timer).max(1)),
+                    priority: *priority,
+                    label,
+                }
+            })
+            .collect()
+    }
+
+    /// write the scheduled events as Gantt chart in Mermaid with labeling event.
+    /// the frame of the event is the frames until the event fire.
+    /// the frames of the event in the paused or scaled domain are the local time of the domain.
+    pub fn export_timeline<W, L>(&self, writer: &mut W, labeler: L) -> io::Result<()>
+    where
+        W: Write,
+        L: FnMut(&E) -> String,
+    {
+        trace::write_timeline(writer, &[], &self.timeline_records(0, labeler))
+    }
+
+    /// write the scheduled events as the aligned timeline in fire order with labeling event.
+    /// the events which fire at same time are grouped under the time, e.g. `t+3  P=0  Repeat(2, Time(5))  Spawn`.
+    /// if the limit is specified, then only the events up to the limit are written and the rest are counted.
+    /// the time of the event in the paused or scaled domain is the local time of the domain.
+    /// the origin of the event is written after the label if tracked.
+    pub fn dump<W, L>(&self, writer: &mut W, mut labeler: L, limit: Option<usize>) -> io::Result<()>
+    where
+        W: Write,
+        L: FnMut(&E) -> String,
+    {
+        let shown = limit.map_or(self.event_list.len(), |limit| {
+            limit.min(self.event_list.len())
+        });
+        let rows: Vec<(String, String, String, String)> = self.event_list[..shown]
+            .iter()
+            .map(|entry| {
+                let (timer, schedule, priority, event) = &entry.state;
+                let mut label = labeler(event);
+                if let Some(provenance) = entry.provenance {
+                    label.push_str(&format!("  ({})", provenance));
+                }
+                (
+                    format!("t+{}", (*timer).max(1)),
+                    format!("P={}", priority),
+                    format!("{:?}", schedule),
+                    label,
+                )
+            })
+            .collect();
+        let time_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+        let priority_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0);
+        let schedule_width = rows.iter().map(|row| row.2.len()).max().unwrap_or(0);
+        let mut previous_time: Option<&str> = None;
+        for (time, priority, schedule, label) in rows.iter() {
+            // the time is written only at the head of the group
+            let time_column = if previous_time == Some(time.as_str()) {
+                ""
+            } else {
+                time.as_str()
+            };
+            previous_time = Some(time.as_str());
+            writeln!(
+                writer,
+                "{:<tw$}  {:<pw$}  {:<sw$}  {}",
+                time_column,
+                priority,
+                schedule,
+                label,
+                tw = time_width,
+                pw = priority_width,
+                sw = schedule_width
+            )?;
+        }
+        if shown < self.event_list.len() {
+            writeln!(writer, "... {} more events", self.event_list.len() - shown)?;
+        }
+        if !self.pending.is_empty() {
+            writeln!(writer, "{} events wait other events", self.pending.len())?;
+        }
+        Ok(())
+    }
+
+    /// count of the fired events for each kind since track_keys is called.
+    /// the events which fire in the split off scheduler (e.g. in the child simulation) are not counted.
+    pub fn fired_by_key(&self) -> &BTreeMap<&'static str, u64> {
+        &self.fired_by_key
+    }
+
+    /// record the frame and the cause of the scheduling for each event scheduled after this call.
+    /// the origins are reported by provenance, fired_provenance, the peek of the next frame and the dump.
+    pub fn track_provenance(&mut self) {
+        if self.provenance.is_none() {
+            self.provenance = Some(Cause::External);
+        }
+    }
+
+    /// judge the origins of the events are recorded
+    pub fn is_tracking_provenance(&self) -> bool {
+        self.provenance.is_some()
+    }
+
+    /// origin of the scheduled event or the event which wait other event. if not tracked or not scheduled, then None.
+    pub fn provenance(&self, id: EventId) -> Option<Provenance> {
+        self.event_list
+            .iter()
+            .chain(self.pending.iter().map(|(_, entry)| entry))
+            .find(|entry| entry.id == id)
+            .and_then(|entry| entry.provenance)
+    }
+
+    /// identifiers and origins of the events which are fired in the last pass in same order as the fired events,
+    /// e.g. to know why the fired event is scheduled while handling it. the origin of the event which is scheduled
+    /// before track_provenance is called is None.
+    pub fn fired_provenance(&self) -> &[(EventId, Option<Provenance>)] {
+        &self.fired_provenance
+    }
+
+    /// judge the event of the identifier is scheduled or wait other event
+    pub fn is_scheduled(&self, id: EventId) -> bool {
+        self.event_list.iter().any(|entry| entry.id == id)
+            || self.pending.iter().any(|(_, entry)| entry.id == id)
+    }
+
+    //
+    // schedule event
+    //
+
+    /// start to build the scheduling of the event with the fluent methods
+    pub fn build(&mut self, event: E) -> ScheduleBuilder<'_, E> {
+        ScheduleBuilder::new(self, ROOT_DOMAIN, event)
+    }
+
+    /// clear all scheduled events, including the held events of the batches and the expired events which are not handed to the model yet.
+    ///
+    /// the windows of the batches, the count of the fired events by the key and the clock are kept,
+    /// because these are the settings and the statistics of the events which are already fired.
+    pub fn clear(&mut self) {
+        self.event_list.clear();
+        self.pending.clear();
+        self.relations.clear();
+        self.held.clear();
+        self.released.clear();
+        self.expired.clear();
+        for batch in self.batches.values_mut() {
+            batch.remaining = None;
+        }
+    }
+
+    /// remove the event of the identifier with the events which wait it.
+    /// if the event is not scheduled, then return false.
+    pub fn cancel(&mut self, id: EventId) -> bool {
+        let scheduled = self.is_scheduled(id);
+        self.remove_entries(|entry| entry.id == id);
+        scheduled
+    }
+
+    /// remove scheduled events when predicate function is true.
+    /// the events which wait the removed event are also removed.
+    pub fn remove_when<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(&(LocalEventTime, Schedule, Priority, E)) -> bool,
+    {
+        self.remove_entries(|entry| predicate(&entry.state))
+    }
+
+    /// retains only the scheduled events specified by the predicate.
+    /// the events which wait the removed event are also removed.
+    pub fn retain<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(&(LocalEventTime, Schedule, Priority, E)) -> bool,
+    {
+        self.remove_entries(|entry| !predicate(&entry.state))
+    }
+
+    /// store event with scheduling
+    pub fn schedule<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions::default(),
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// store event with scheduling without cloning the event.
+    /// the event is moved into the handler when fired, so the recurring schedule is error.
+    pub fn schedule_once<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_cloned_by(rng, schedule, priority, event, None)
+    }
+
+    /// store the event which is made from the shared payload with scheduling.
+    /// the payload is kept in the scheduler as the template, and the event for each fire is made from the clone of the `Arc`,
+    /// so the recurring fires never deep-clone the payload and the event need not be `Clone`.
+    pub fn schedule_shared<R, P>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        payload: Arc<P>,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        R: Rng + ?Sized,
+        P: Send + Sync + ?Sized + 'static,
+        E: From<Arc<P>>,
+    {
+        let event = E::from(Arc::clone(&payload));
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions::default(),
+            Some(Recurrence::Shared(Arc::new(move || {
+                E::from(Arc::clone(&payload))
+            }))),
+        )
+    }
+
+    /// store event with scheduling. the recurring event is copied by the cloner.
+    pub(crate) fn schedule_cloned_by<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+        cloner: Option<fn(&E) -> E>,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions::default(),
+            cloner.map(Recurrence::Clone),
+        )
+    }
+
+    /// store event with scheduling which is delivered in the phase
+    pub fn schedule_in_phase<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        phase: &Phase,
+        schedule: Schedule,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule(rng, schedule, phase.priority(), event)
+    }
+
+    /// store event with scheduling when user judge ok from all scheduled events
+    pub fn schedule_when<R: Rng + ?Sized, P>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+        predicate: P,
+    ) -> Result<Option<EventId>, ScheduleEventError>
+    where
+        E: Clone,
+        P: FnOnce(&Self) -> bool,
+    {
+        if !predicate(self) {
+            return Ok(None);
+        }
+        self.schedule(rng, schedule, priority, event).map(Some)
+    }
+
+    /// store event which fire the offset frames after the event of the identifier fire.
+    /// the timer start when the event fire, so the offset 0 is treated as 1 as same as immediate.
+    /// if the event is skipped by the guard or removed, this event is also removed.
+    /// if the event is not scheduled, then error.
+    pub fn schedule_after(
+        &mut self,
+        event_id: EventId,
+        offset: LocalEventTime,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_after_in(ROOT_DOMAIN, event_id, offset, priority, event)
+    }
+
+    /// store event which fire the offset frames from the time when the anchor event of the identifier is due,
+    /// e.g. the offset -2 fire 2 frames before the anchor and 1 fire the next frame of the anchor.
+    /// the offset is counted in the domain of the anchor. the time which is already past fire in the next frame.
+    ///
+    /// this event follow the remaining time of the anchor until the anchor fire,
+    /// so the event move together when the anchor is deferred by the guard or stopped by the domain or the tag.
+    /// if the anchor is skipped by the guard, expired or removed, this event is also removed.
+    /// if the anchor is not scheduled or wait other event, then error.
+    pub fn schedule_relative(
+        &mut self,
+        anchor: EventId,
+        offset: i64,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        let (remaining, domain) = match self.event_list.iter().find(|entry| entry.id == anchor) {
+            Some(entry) => (entry.state.0, entry.domain),
+            None => return Err(ScheduleEventError::CannotFireEvent),
+        };
+        let timer = to_following_timer(i64::from(remaining).saturating_add(offset));
+        let id = self.next_event_id();
+        self.insert(Entry {
+            id,
+            state: (
+                timer,
+                Schedule::Timeout(EventTimer::Time(timer)),
+                priority,
+                event,
+            ),
+            domain,
+            age: 0,
+            guard: None,
+            ttl: None,
+            jitter: 0,
+            tag: None,
+            owner: None,
+            recurrence: None,
+            provenance: self.current_provenance(),
+        });
+        self.relations.push((id, anchor, offset));
+        Ok(id)
+    }
+
+    /// judge the event of the identifier follow the anchor event which is not fired yet
+    pub fn is_following(&self, id: EventId) -> bool {
+        self.relations.iter().any(|(event, _, _)| *event == id)
+    }
+
+    /// store event with the guard which is checked when the event fire.
+    /// if the guard is closed at that time, the event is skipped or deferred by the guard.
+    pub fn schedule_guarded<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+        guard: Guard,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions {
+                guard: Some(guard),
+                ..Default::default()
+            },
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// store event with the ttl. if the event is not fired within ttl frames from scheduled,
+    /// the event is dropped and handed to Model::on_expired. the recurring event is not rescheduled after expired.
+    pub fn schedule_with_ttl<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+        ttl: LocalEventTime,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions {
+                ttl: Some(ttl),
+                ..Default::default()
+            },
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// count of the events which are expired by the ttl
+    pub fn expired(&self) -> usize {
+        self.expired_count
+    }
+
+    /// store event with the tag. the events with same tag can be removed, suspended and counted together.
+    pub fn schedule_tagged<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+        tag: Tag,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions {
+                tag: Some(tag),
+                ..Default::default()
+            },
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// remove the events with the tag. the events which wait the removed event are also removed.
+    pub fn clear_tag(&mut self, tag: Tag) {
+        self.remove_entries(|entry| entry.tag == Some(tag))
+    }
+
+    /// retains only the events with the tag specified by the predicate. the events without the tag are kept.
+    /// the events which wait the removed event are also removed.
+    pub fn retain_tag<P>(&mut self, tag: Tag, mut predicate: P)
+    where
+        P: FnMut(&(LocalEventTime, Schedule, Priority, E)) -> bool,
+    {
+        self.remove_entries(|entry| entry.tag == Some(tag) && !predicate(&entry.state))
+    }
+
+    /// count of the scheduled events with the tag. the events which wait other event are not contained.
+    pub fn count_tag(&self, tag: Tag) -> usize {
+        self.event_list
+            .iter()
+            .filter(|entry| entry.tag == Some(tag))
+            .count()
+    }
+
+    /// stop the timers of the events with the tag. the suspended events never fire until resumed.
+    pub fn suspend_tag(&mut self, tag: Tag) {
+        self.suspended_tags.insert(tag);
+    }
+
+    /// restart the timers of the events with the tag
+    pub fn resume_tag(&mut self, tag: Tag) {
+        self.suspended_tags.remove(tag);
+    }
+
+    /// judge the events with the tag are suspended
+    pub fn is_tag_suspended(&self, tag: Tag) -> bool {
+        self.suspended_tags.contains(tag)
+    }
+
+    /// hold the fired events with the tag and deliver them together to Model::on_batch at the end of the window.
+    /// the window start when the first event is held and close after the frames including that frame. 0 is treated as 1.
+    /// None stop the batching, and the held events are delivered in the next frame.
+    pub fn set_batch(&mut self, tag: Tag, window: Option<LocalEventTime>) {
+        match window {
+            Some(window) => {
+                let batch = self.batches.entry(tag).or_insert(Batch {
+                    window: 1,
+                    remaining: None,
+                });
+                batch.window = window.max(1);
+            }
+            None => {
+                self.batches.remove(tag);
+                if let Some(events) = self.held.remove(tag) {
+                    if !events.is_empty() {
+                        self.released.push((tag, events));
+                    }
+                }
+            }
+        }
+    }
+
+    /// window of the batching of the tag. None is not batched.
+    pub fn batch_window(&self, tag: Tag) -> Option<LocalEventTime> {
+        self.batches.get(tag).map(|batch| batch.window)
+    }
+
+    /// count of the held events with the tag which are not delivered yet
+    pub fn batched_count(&self, tag: Tag) -> usize {
+        self.held.get(tag).map_or(0, Vec::len)
+    }
+
+    /// store event which is owned by the entity. the events of the owner can be removed and counted together.
+    pub fn schedule_owned<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        owner: OwnerId,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule_in(
+            rng,
+            ROOT_DOMAIN,
+            schedule,
+            priority,
+            event,
+            Conditions {
+                owner: Some(owner),
+                ..Default::default()
+            },
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// remove the events of the owner, e.g. when the entity is despawned.
+    /// the events which wait the removed event are also removed.
+    pub fn clear_owner(&mut self, owner: OwnerId) {
+        self.remove_entries(|entry| entry.owner == Some(owner))
+    }
+
+    /// count of the scheduled events of the owner. the events which wait other event are not contained.
+    pub fn count_owner(&self, owner: OwnerId) -> usize {
+        self.event_list
+            .iter()
+            .filter(|entry| entry.owner == Some(owner))
+            .count()
+    }
+
+    /// owner of the scheduled event of the identifier
+    pub fn owner_of(&self, id: EventId) -> Option<OwnerId> {
+        self.event_list
+            .iter()
+            .find(|entry| entry.id == id)
+            .and_then(|entry| entry.owner)
+    }
+
+    /// close the guard, e.g. when the agent is dead or the resource is gone
+    pub fn close_guard(&mut self, key: GuardKey) {
+        self.closed_guards.insert(key);
+    }
+
+    /// open the closed guard
+    pub fn open_guard(&mut self, key: GuardKey) {
+        self.closed_guards.remove(&key);
+    }
+
+    /// judge the guard is open. the guard is open until closed.
+    pub fn is_guard_open(&self, key: GuardKey) -> bool {
+        !self.closed_guards.contains(&key)
+    }
+
+    /// count of the guarded events which are skipped because the guard is closed
+    pub fn skipped_by_guard(&self) -> usize {
+        self.skipped
+    }
+
+    /// store event which fire at immediate timing
+    pub fn immediate<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_once(rng, Schedule::Immediate, priority, event)
+    }
+
+    /// store event which fire after timeout
+    pub fn timeout<R: Rng + ?Sized>(
+        &mut self,
         rng: &mut R,
         timeout: EventTimer,
         priority: Priority,
         event: E,
-    ) -> Result<(), ScheduleEventError> {
-        self.schedule(rng, Schedule::Timeout(timeout), priority, event)
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_once(rng, Schedule::Timeout(timeout), priority, event)
     }
 
     /// store event which fire every time
@@ -284,7 +3010,10 @@ impl<E: Event> EventScheduler<E> {
         rng: &mut R,
         priority: Priority,
         event: E,
-    ) -> Result<(), ScheduleEventError> {
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
         self.schedule(rng, Schedule::Everytime, priority, event)
     }
 
@@ -295,7 +3024,10 @@ impl<E: Event> EventScheduler<E> {
         interval: EventTimer,
         priority: Priority,
         event: E,
-    ) -> Result<(), ScheduleEventError> {
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
         self.schedule(rng, Schedule::EveryInterval(interval), priority, event)
     }
 
@@ -307,7 +3039,706 @@ impl<E: Event> EventScheduler<E> {
         interval: EventTimer,
         priority: Priority,
         event: E,
-    ) -> Result<(), ScheduleEventError> {
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule(rng, Schedule::Repeat(count, interval), priority, event)
+    }
+}
+
+impl<E: EventKey> EventScheduler<E> {
+    /// start to count the fired events for each kind
+    pub fn track_keys(&mut self) {
+        self.key_of = Some(E::key);
+    }
+
+    /// count of the scheduled events for each kind. the events which wait other event are not contained.
+    pub fn count_by_key(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in self.event_list.iter() {
+            *counts.entry(entry.state.3.key()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// frames until the first event of each kind fire.
+    /// the frames of the event in the paused or scaled domain are the local time of the domain.
+    pub fn next_time_by_key(&self) -> BTreeMap<&'static str, LocalEventTime> {
+        let mut times = BTreeMap::new();
+        for entry in self.event_list.iter() {
+            times
+                .entry(entry.state.3.key())
+                .and_modify(|time: &mut LocalEventTime| *time = (*time).min(entry.state.0))
+                .or_insert(entry.state.0);
+        }
+        times
+    }
+}
+
+/// view of the scheduling domain in the scheduler.
+///
+/// the events in the domain are driven by the parent frame loop,
+/// but can be cleared, paused and scaled independently.
+#[derive(Debug)]
+pub struct SchedulerDomain<'a, E: Event> {
+    scheduler: &'a mut EventScheduler<E>,
+    id: DomainId,
+}
+
+impl<'a, E: Event> SchedulerDomain<'a, E> {
+    /// getter for the identifier of this domain
+    pub fn id(&self) -> DomainId {
+        self.id
+    }
+
+    /// state of this domain. the domain is alive while this view is alive.
+    fn state(&mut self) -> &mut DomainState {
+        self.scheduler.domains.get_mut(&self.id).unwrap()
+    }
+
+    /// judge exist scheduled event in this domain
+    pub fn have_event(&self) -> bool {
+        self.scheduler
+            .event_list
+            .iter()
+            .any(|entry| entry.domain == self.id)
+    }
+
+    /// get length of scheduled events in this domain
+    pub fn count(&self) -> usize {
+        self.scheduler
+            .event_list
+            .iter()
+            .filter(|entry| entry.domain == self.id)
+            .count()
+    }
+
+    /// clear all scheduled events in this domain
+    pub fn clear(&mut self) {
+        let id = self.id;
+        self.scheduler.remove_entries(|entry| entry.domain == id);
+    }
+
+    /// stop the timers of the events in this domain
+    pub fn pause(&mut self) {
+        self.state().paused = true;
+    }
+
+    /// restart the timers of the events in this domain
+    pub fn resume(&mut self) {
+        self.state().paused = false;
+    }
+
+    /// judge this domain is paused
+    pub fn is_paused(&self) -> bool {
+        self.scheduler.domains[&self.id].paused
+    }
+
+    /// getter for the time dilation factor
+    pub fn scale(&self) -> f64 {
+        self.scheduler.domains[&self.id].scale
+    }
+
+    /// set the time dilation factor. the local time of this domain go on scale per one frame.
+    /// e.g. 0.5 is half speed, 2.0 is double speed. negative or NaN value is treated as 0.
+    /// the event in this domain fire at most once per frame.
+    pub fn set_scale(&mut self, scale: f64) {
+        let scale = if scale > 0.0 { scale } else { 0.0 };
+        let state = self.state();
+        state.scale = scale;
+        state.progress = 0.0;
+    }
+
+    /// start to build the scheduling of the event into this domain with the fluent methods
+    pub fn build(&mut self, event: E) -> ScheduleBuilder<'_, E> {
+        ScheduleBuilder::new(self.scheduler, self.id, event)
+    }
+
+    /// store event with scheduling into this domain
+    pub fn schedule<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.scheduler.schedule_in(
+            rng,
+            self.id,
+            schedule,
+            priority,
+            event,
+            Conditions::default(),
+            Some(Recurrence::Clone(E::clone)),
+        )
+    }
+
+    /// store event with scheduling into this domain without cloning the event.
+    /// the recurring schedule is error.
+    pub fn schedule_once<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.scheduler.schedule_in(
+            rng,
+            self.id,
+            schedule,
+            priority,
+            event,
+            Conditions::default(),
+            None,
+        )
+    }
+
+    /// store event which fire the offset frames after the event of the identifier fire into this domain
+    pub fn schedule_after(
+        &mut self,
+        event_id: EventId,
+        offset: LocalEventTime,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.scheduler
+            .schedule_after_in(self.id, event_id, offset, priority, event)
+    }
+
+    /// store event which fire at immediate timing into this domain
+    pub fn immediate<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_once(rng, Schedule::Immediate, priority, event)
+    }
+
+    /// store event which fire after timeout into this domain
+    pub fn timeout<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        timeout: EventTimer,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError> {
+        self.schedule_once(rng, Schedule::Timeout(timeout), priority, event)
+    }
+
+    /// store event which fire every time into this domain
+    pub fn everytime<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule(rng, Schedule::Everytime, priority, event)
+    }
+
+    /// store event which fire every interval into this domain
+    pub fn every_interval<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        interval: EventTimer,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
+        self.schedule(rng, Schedule::EveryInterval(interval), priority, event)
+    }
+
+    /// store event which fire every interval only count into this domain
+    pub fn repeat<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        count: u8,
+        interval: EventTimer,
+        priority: Priority,
+        event: E,
+    ) -> Result<EventId, ScheduleEventError>
+    where
+        E: Clone,
+    {
         self.schedule(rng, Schedule::Repeat(count, interval), priority, event)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Ev(&'static str);
+
+    impl Event for Ev {}
+
+    fn timeout(time: LocalEventTime) -> Schedule {
+        Schedule::Timeout(EventTimer::Time(time))
+    }
+
+    /// names of the events which fire in the next frame
+    fn fire(scheduler: &mut EventScheduler<Ev>, rng: &mut StdRng) -> Vec<&'static str> {
+        let mut fired = vec![];
+        scheduler.next_time_and_fire(rng, &mut fired);
+        fired.into_iter().map(|(_, Ev(name))| name).collect()
+    }
+
+    #[test]
+    fn weighted_timer_convert_from_u8_weights() {
+        match EventTimer::from(vec![(2, 3_u8), (5, 1_u8)]) {
+            EventTimer::WeightedIndex(items) => assert_eq!(items, vec![(2, 3), (5, 1)]),
+            timer => panic!("{:?} is not weighted", timer),
+        }
+        assert_eq!(
+            WeightedTimer::try_from(vec![(2, 3_u8)]).map(|timer| timer.items().to_vec()),
+            Ok(vec![(2, 3)])
+        );
+        assert_eq!(
+            WeightedTimer::try_from(vec![(2, 0_u8)]),
+            Err(WeightedError::AllWeightsZero)
+        );
+    }
+
+    #[test]
+    fn guard_skip_or_defer_closed_events() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        let skipped = scheduler
+            .schedule_guarded(
+                &mut rng,
+                timeout(1),
+                Priority::NORMAL,
+                Ev("skipped"),
+                Guard::skip(1),
+            )
+            .unwrap();
+        scheduler
+            .schedule_after(skipped, 1, Priority::NORMAL, Ev("dependent"))
+            .unwrap();
+        scheduler
+            .schedule_guarded(
+                &mut rng,
+                timeout(1),
+                Priority::NORMAL,
+                Ev("deferred"),
+                Guard::defer(2, 2),
+            )
+            .unwrap();
+        scheduler
+            .schedule_guarded(
+                &mut rng,
+                timeout(1),
+                Priority::LOW,
+                Ev("open"),
+                Guard::skip(3),
+            )
+            .unwrap();
+        scheduler.close_guard(1);
+        scheduler.close_guard(2);
+        assert!(!scheduler.is_guard_open(1));
+
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["open"]);
+        assert_eq!(scheduler.skipped_by_guard(), 1);
+        // the event which wait the skipped event never start
+        assert_eq!(scheduler.count(), 1);
+
+        scheduler.open_guard(2);
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["deferred"]);
+        assert!(!scheduler.have_event());
+    }
+
+    #[test]
+    fn cascade_fire_immediate_events_in_same_frame() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        scheduler.set_cascade(Some(1));
+        scheduler
+            .schedule(&mut rng, timeout(1), Priority::NORMAL, Ev("first"))
+            .unwrap();
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["first"]);
+
+        // scheduled while handling the first event
+        scheduler
+            .immediate(&mut rng, Priority::NORMAL, Ev("cascaded"))
+            .unwrap();
+        let mut fired = vec![];
+        assert!(scheduler.fire_cascade(&mut rng, 0, &mut fired));
+        assert_eq!(fired, vec![(Priority::NORMAL, Ev("cascaded"))]);
+
+        // over the limit, the immediate event fire in the next frame
+        scheduler
+            .immediate(&mut rng, Priority::NORMAL, Ev("truncated"))
+            .unwrap();
+        fired.clear();
+        assert!(!scheduler.fire_cascade(&mut rng, 1, &mut fired));
+        assert!(fired.is_empty());
+        assert_eq!(scheduler.truncated_cascades(), 1);
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["truncated"]);
+
+        // without the cascade, nothing fire in the same frame
+        scheduler.set_cascade(None);
+        scheduler
+            .immediate(&mut rng, Priority::NORMAL, Ev("next frame"))
+            .unwrap();
+        assert!(!scheduler.fire_cascade(&mut rng, 0, &mut fired));
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["next frame"]);
+    }
+
+    #[test]
+    fn fire_in_order_of_time_then_priority() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        for (time, priority, name) in [
+            (2, Priority::LOW, "late low"),
+            (1, Priority::NORMAL, "early normal"),
+            (2, Priority::HIGH, "late high"),
+            (1, Priority::HIGH, "early high"),
+            (2, Priority::HIGH, "late high again"),
+        ] {
+            scheduler
+                .schedule(&mut rng, timeout(time), priority, Ev(name))
+                .unwrap();
+            assert_eq!(scheduler.check_invariants(), Ok(()));
+        }
+
+        assert_eq!(
+            fire(&mut scheduler, &mut rng),
+            vec!["early high", "early normal"]
+        );
+        assert_eq!(scheduler.check_invariants(), Ok(()));
+        // same time and same priority keep the scheduled order
+        assert_eq!(
+            fire(&mut scheduler, &mut rng),
+            vec!["late high", "late high again", "late low"]
+        );
+        assert!(!scheduler.have_event());
+    }
+
+    #[test]
+    fn check_invariants_find_out_of_order() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        scheduler
+            .schedule(&mut rng, timeout(1), Priority::LOW, Ev("a"))
+            .unwrap();
+        scheduler
+            .schedule(&mut rng, timeout(1), Priority::HIGH, Ev("b"))
+            .unwrap();
+        scheduler
+            .schedule(&mut rng, timeout(2), Priority::HIGH, Ev("c"))
+            .unwrap();
+        assert_eq!(scheduler.check_invariants(), Ok(()));
+
+        // lower priority before higher priority at the same time
+        scheduler.event_list.swap(0, 1);
+        assert_eq!(
+            scheduler.check_invariants(),
+            Err(InvariantViolation::OutOfOrder(1))
+        );
+        // later time before earlier time
+        scheduler.event_list.swap(0, 1);
+        scheduler.event_list.swap(1, 2);
+        assert_eq!(
+            scheduler.check_invariants(),
+            Err(InvariantViolation::OutOfOrder(2))
+        );
+    }
+
+    #[test]
+    fn check_invariants_find_broken_identifiers() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        let id = scheduler
+            .schedule(&mut rng, timeout(1), Priority::NORMAL, Ev("a"))
+            .unwrap();
+
+        let mut duplicated = scheduler.clone();
+        let entry = duplicated.event_list[0].clone();
+        duplicated.event_list.push(entry);
+        assert_eq!(
+            duplicated.check_invariants(),
+            Err(InvariantViolation::DuplicatedId(id))
+        );
+
+        let mut unissued = scheduler.clone();
+        unissued.next_id = 0;
+        assert_eq!(
+            unissued.check_invariants(),
+            Err(InvariantViolation::UnissuedId(id))
+        );
+
+        let mut unknown = scheduler.clone();
+        unknown.event_list[0].domain = ROOT_DOMAIN + 1;
+        assert_eq!(
+            unknown.check_invariants(),
+            Err(InvariantViolation::UnknownDomain(id, ROOT_DOMAIN + 1))
+        );
+
+        let waiting = scheduler
+            .schedule_after(id, 1, Priority::NORMAL, Ev("b"))
+            .unwrap();
+        assert_eq!(scheduler.check_invariants(), Ok(()));
+        scheduler.event_list.clear();
+        assert_eq!(
+            scheduler.check_invariants(),
+            Err(InvariantViolation::OrphanedEvent(waiting))
+        );
+    }
+
+    #[test]
+    fn ttl_expire_waiting_events() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        scheduler
+            .schedule_with_ttl(&mut rng, timeout(5), Priority::NORMAL, Ev("expired"), 2)
+            .unwrap();
+        scheduler
+            .schedule_with_ttl(&mut rng, timeout(1), Priority::NORMAL, Ev("in time"), 2)
+            .unwrap();
+
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["in time"]);
+        assert!(scheduler.take_expired().is_empty());
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        assert_eq!(
+            scheduler.take_expired(),
+            vec![(Priority::NORMAL, Ev("expired"))]
+        );
+        assert_eq!(scheduler.expired(), 1);
+        assert!(!scheduler.have_event());
+    }
+
+    #[test]
+    fn jitter_not_accumulated() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut scheduler = EventScheduler::new();
+        scheduler
+            .every_interval(
+                &mut rng,
+                EventTimer::Time(5).with_jitter(2),
+                Priority::NORMAL,
+                Ev("jittered"),
+            )
+            .unwrap();
+        let mut fired_frames = vec![];
+        for frame in 1..=100 {
+            if !fire(&mut scheduler, &mut rng).is_empty() {
+                fired_frames.push(frame);
+            }
+        }
+        assert_eq!(fired_frames.len(), 20);
+        for (index, frame) in fired_frames.into_iter().enumerate() {
+            let base = 5 * (index as u64 + 1);
+            assert!(
+                base - 2 <= frame && frame <= base + 2,
+                "{}-th fire at frame {}",
+                index,
+                frame
+            );
+        }
+    }
+
+    #[test]
+    fn relative_events_follow_anchor() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        let anchor = scheduler
+            .schedule_guarded(
+                &mut rng,
+                timeout(3),
+                Priority::NORMAL,
+                Ev("anchor"),
+                Guard::defer(1, 2),
+            )
+            .unwrap();
+        let before = scheduler
+            .schedule_relative(anchor, -1, Priority::NORMAL, Ev("before"))
+            .unwrap();
+        scheduler
+            .schedule_relative(anchor, 1, Priority::NORMAL, Ev("after"))
+            .unwrap();
+        assert!(scheduler.is_following(before));
+        assert_eq!(scheduler.check_invariants(), Ok(()));
+
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["before"]);
+        assert!(!scheduler.is_following(before));
+        // the anchor is deferred 2 frames, so the following event move together
+        scheduler.close_guard(1);
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        scheduler.open_guard(1);
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["anchor"]);
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["after"]);
+
+        // removing the anchor remove the following events
+        let anchor = scheduler
+            .schedule(&mut rng, timeout(3), Priority::NORMAL, Ev("removed"))
+            .unwrap();
+        let following = scheduler
+            .schedule_relative(anchor, 1, Priority::NORMAL, Ev("following"))
+            .unwrap();
+        assert!(scheduler.cancel(anchor));
+        assert!(!scheduler.is_scheduled(following));
+        assert!(scheduler
+            .schedule_relative(anchor, 1, Priority::NORMAL, Ev("orphan"))
+            .is_err());
+    }
+
+    #[test]
+    fn batch_hold_events_until_window_closed() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        scheduler.set_batch("order", Some(2));
+        assert_eq!(scheduler.batch_window("order"), Some(2));
+        for (time, name) in [(1, "first"), (2, "second"), (3, "third")] {
+            scheduler
+                .schedule_tagged(&mut rng, timeout(time), Priority::NORMAL, Ev(name), "order")
+                .unwrap();
+        }
+        scheduler
+            .schedule(&mut rng, timeout(1), Priority::NORMAL, Ev("untagged"))
+            .unwrap();
+
+        assert_eq!(fire(&mut scheduler, &mut rng), vec!["untagged"]);
+        assert_eq!(scheduler.batched_count("order"), 1);
+        assert!(scheduler.take_batches().is_empty());
+
+        // the window contain the frame of the first held event
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        assert_eq!(
+            scheduler.take_batches(),
+            vec![(
+                "order",
+                vec![
+                    (Priority::NORMAL, Ev("first")),
+                    (Priority::NORMAL, Ev("second"))
+                ]
+            )]
+        );
+
+        // stop batching deliver the held events in the next frame
+        assert!(fire(&mut scheduler, &mut rng).is_empty());
+        scheduler.set_batch("order", None);
+        assert_eq!(
+            scheduler.take_batches(),
+            vec![("order", vec![(Priority::NORMAL, Ev("third"))])]
+        );
+    }
+
+    #[test]
+    fn periodic_fire_aligned_to_clock() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        let periodic = Schedule::Periodic {
+            period: 3,
+            offset: 1,
+        };
+        scheduler
+            .schedule(&mut rng, periodic.clone(), Priority::NORMAL, Ev("early"))
+            .unwrap();
+        let mut fired_frames = vec![];
+        for frame in 1..=8 {
+            if frame == 3 {
+                // scheduled later, but fire on the same frames
+                scheduler
+                    .schedule(&mut rng, periodic.clone(), Priority::LOW, Ev("late"))
+                    .unwrap();
+            }
+            for name in fire(&mut scheduler, &mut rng) {
+                fired_frames.push((frame, name));
+            }
+        }
+        assert_eq!(
+            fired_frames,
+            vec![
+                (1, "early"),
+                (4, "early"),
+                (4, "late"),
+                (7, "early"),
+                (7, "late")
+            ]
+        );
+        assert_eq!(
+            Schedule::Periodic {
+                period: 0,
+                offset: 0
+            }
+            .checked()
+            .unwrap_err(),
+            ScheduleValidationError::ZeroPeriod
+        );
+    }
+
+    #[test]
+    fn provenance_record_origin_of_events() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut scheduler = EventScheduler::new();
+        let untracked = scheduler
+            .schedule(&mut rng, timeout(1), Priority::HIGH, Ev("untracked"))
+            .unwrap();
+        assert_eq!(scheduler.provenance(untracked), None);
+
+        scheduler.track_provenance();
+        assert!(scheduler.is_tracking_provenance());
+        let recurring = scheduler
+            .everytime(&mut rng, Priority::NORMAL, Ev("recurring"))
+            .unwrap();
+        assert_eq!(
+            scheduler.provenance(recurring),
+            Some(Provenance {
+                scheduled_at: 0,
+                cause: Cause::External
+            })
+        );
+
+        assert_eq!(
+            fire(&mut scheduler, &mut rng),
+            vec!["untracked", "recurring"]
+        );
+        assert_eq!(
+            scheduler
+                .fired_provenance()
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>(),
+            vec![untracked, recurring]
+        );
+        assert_eq!(
+            scheduler.provenance(recurring),
+            Some(Provenance {
+                scheduled_at: 1,
+                cause: Cause::Recurring
+            })
+        );
+
+        scheduler.handle_fired(1);
+        let caused = scheduler
+            .schedule(&mut rng, timeout(1), Priority::NORMAL, Ev("caused"))
+            .unwrap();
+        scheduler.finish_handling();
+        let external = scheduler
+            .schedule(&mut rng, timeout(1), Priority::NORMAL, Ev("external"))
+            .unwrap();
+        assert_eq!(
+            scheduler.provenance(caused).map(|origin| origin.cause),
+            Some(Cause::Handler(Some(recurring)))
+        );
+        assert_eq!(
+            scheduler.provenance(external).map(|origin| origin.cause),
+            Some(Cause::External)
+        );
+    }
+}
@@ -0,0 +1,382 @@
+//! Parameter sweep which run replications for each cell of the parameter grid
+
+use crate::ensemble::{run_replications, SeedError, SeedStrategy};
+use crate::event::Event;
+use crate::model::Model;
+use crate::recorder::{summarize, FrameSummary, MergeRecorder};
+use crate::validation::mean_and_std_dev;
+use crate::{FrameIndex, Simulator};
+use rand::rngs::StdRng;
+use std::fmt;
+
+/// value of the parameter
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    /// boolean value
+    Bool(bool),
+    /// integer value
+    Int(i64),
+    /// real value
+    Float(f64),
+    /// text value
+    Text(String),
+}
+
+impl ParamValue {
+    /// getter as bool
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ParamValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// getter as integer
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ParamValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// getter as real. integer is converted.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ParamValue::Int(i) => Some(*i as f64),
+            ParamValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// getter as text
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ParamValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamValue::Bool(b) => write!(f, "{}", b),
+            ParamValue::Int(i) => write!(f, "{}", i),
+            ParamValue::Float(v) => write!(f, "{}", v),
+            ParamValue::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! impl_param_from {
+    ($variant:ident, $as:ty, [$($t:ty),*]) => {
+        $(
+            impl From<$t> for ParamValue {
+                fn from(value: $t) -> Self {
+                    ParamValue::$variant(value as $as)
+                }
+            }
+        )*
+    };
+}
+
+impl_param_from!(Int, i64, [i8, i16, i32, i64, u8, u16, u32, usize]);
+impl_param_from!(Float, f64, [f32, f64]);
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self {
+        ParamValue::Bool(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self {
+        ParamValue::Text(value)
+    }
+}
+
+/// parameters of one cell in the grid with defined order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Params {
+    values: Vec<(String, ParamValue)>,
+}
+
+impl Params {
+    /// getter for the value of the parameter
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.values
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// getter for the value of the parameter as real
+    pub fn f64(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(ParamValue::as_f64)
+    }
+
+    /// getter for the value of the parameter as integer
+    pub fn i64(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(ParamValue::as_i64)
+    }
+
+    /// pairs of the name and the value in defined order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ParamValue)> {
+        self.values.iter().map(|(key, value)| (key.as_str(), value))
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (key, value)) in self.values.iter().enumerate() {
+            if index != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// builder of the factorial parameter sweep
+#[derive(Debug, Clone)]
+pub struct SweepBuilder {
+    factors: Vec<(String, Vec<ParamValue>)>,
+    replications: usize,
+    seed_strategy: SeedStrategy,
+}
+
+impl Default for SweepBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SweepBuilder {
+    /// initializer with one replication for each cell
+    pub fn new() -> Self {
+        SweepBuilder {
+            factors: vec![],
+            replications: 1,
+            seed_strategy: SeedStrategy::Sequential(0),
+        }
+    }
+
+    /// add the parameter with the levels. the first parameter change slowest in the grid.
+    pub fn param<T, I>(mut self, name: &str, levels: I) -> Self
+    where
+        T: Into<ParamValue>,
+        I: IntoIterator<Item = T>,
+    {
+        self.factors.push((
+            name.to_string(),
+            levels.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// count of replications for each cell
+    pub fn replications(mut self, k: usize) -> Self {
+        self.replications = k;
+        self
+    }
+
+    /// seed strategy for the replications.
+    /// all cells use same seeds, so the cells are compared with common random numbers.
+    pub fn seed_strategy(mut self, seed_strategy: SeedStrategy) -> Self {
+        self.seed_strategy = seed_strategy;
+        self
+    }
+
+    /// all cells of the grid
+    pub fn cells(&self) -> Vec<Params> {
+        let mut cells = vec![Params::default()];
+        for (name, levels) in self.factors.iter() {
+            let mut next = Vec::with_capacity(cells.len() * levels.len());
+            for cell in cells.iter() {
+                for level in levels.iter() {
+                    let mut cell = cell.clone();
+                    cell.values.push((name.clone(), level.clone()));
+                    next.push(cell);
+                }
+            }
+            cells = next;
+        }
+        cells
+    }
+
+    /// run the replications for all cells with creating model and recorder from the parameters.
+    /// if the seed strategy cannot give the seeds for the replications, then error without running.
+    pub fn run<M, E, Rec, MF, RF, F>(
+        &self,
+        model_factory: MF,
+        recorder_factory: RF,
+        run: F,
+    ) -> Result<SweepResult<M, Rec>, SeedError>
+    where
+        M: Model<Rec, ModelEvent = E> + Send,
+        E: Event,
+        Rec: Send,
+        MF: Fn(&Params) -> M + Sync,
+        RF: Fn(&Params) -> Rec + Sync,
+        F: Fn(&mut Simulator<M, E, Rec>, &mut StdRng) + Sync,
+    {
+        self.seed_strategy.seeds(self.replications)?;
+        let mut rows = vec![];
+        for (cell_index, params) in self.cells().into_iter().enumerate() {
+            let replications = run_replications(
+                self.replications,
+                self.seed_strategy.clone(),
+                |_| model_factory(&params),
+                |_| recorder_factory(&params),
+                &run,
+            )?;
+            for replication in replications.into_iter() {
+                rows.push(SweepRow {
+                    cell_index,
+                    params: params.clone(),
+                    replication: replication.index,
+                    seed: replication.seed,
+                    model: replication.model,
+                    recorder: replication.recorder,
+                });
+            }
+        }
+        Ok(SweepResult { rows })
+    }
+}
+
+/// final state of one replication in the cell
+#[derive(Debug, Clone)]
+pub struct SweepRow<M, Rec> {
+    /// index of the cell in the grid
+    pub cell_index: usize,
+    /// parameters of the cell
+    pub params: Params,
+    /// index of the replication in the cell
+    pub replication: usize,
+    /// seed of the replication
+    pub seed: u64,
+    /// model at the end of the replication
+    pub model: M,
+    /// recorder at the end of the replication
+    pub recorder: Rec,
+}
+
+/// summary of the metric in one cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellSummary {
+    /// parameters of the cell
+    pub params: Params,
+    /// value of the metric for each replication
+    pub values: Vec<f64>,
+    /// mean of the values
+    pub mean: f64,
+    /// sample standard deviation of the values
+    pub std_dev: f64,
+}
+
+/// result table of the sweep which has one row for each replication in each cell
+#[derive(Debug, Clone)]
+pub struct SweepResult<M, Rec> {
+    rows: Vec<SweepRow<M, Rec>>,
+}
+
+impl<M, Rec> SweepResult<M, Rec> {
+    /// getter for rows in order of the cell and the replication
+    pub fn rows(&self) -> &[SweepRow<M, Rec>] {
+        &self.rows
+    }
+
+    /// take all rows
+    pub fn into_rows(self) -> Vec<SweepRow<M, Rec>> {
+        self.rows
+    }
+
+    /// rows of the cell which has the parameters
+    pub fn rows_for(&self, params: &Params) -> Vec<&SweepRow<M, Rec>> {
+        self.rows
+            .iter()
+            .filter(|row| &row.params == params)
+            .collect()
+    }
+
+    /// summarize the metric which is extracted from each row for each cell
+    pub fn summarize<F>(&self, metric: F) -> Vec<CellSummary>
+    where
+        F: Fn(&SweepRow<M, Rec>) -> f64,
+    {
+        let mut summaries: Vec<CellSummary> = vec![];
+        let mut last_cell = None;
+        for row in self.rows.iter() {
+            if last_cell != Some(row.cell_index) {
+                last_cell = Some(row.cell_index);
+                summaries.push(CellSummary {
+                    params: row.params.clone(),
+                    values: vec![],
+                    mean: f64::NAN,
+                    std_dev: f64::NAN,
+                });
+            }
+            if let Some(summary) = summaries.last_mut() {
+                summary.values.push(metric(row));
+            }
+        }
+        for summary in summaries.iter_mut() {
+            let (mean, std_dev) = mean_and_std_dev(&summary.values);
+            summary.mean = mean;
+            summary.std_dev = std_dev;
+        }
+        summaries
+    }
+
+    /// summarize the per-frame metric of the recorders across the replications for each cell.
+    /// the confidence interval use the quantile of normal distribution z, e.g. 1.96 for 95%.
+    pub fn summarize_frames<F>(&self, z: f64, metric: F) -> Vec<(Params, Vec<FrameSummary>)>
+    where
+        F: Fn(&Rec) -> Vec<(FrameIndex, f64)>,
+    {
+        let mut cells: Vec<(usize, Params)> = vec![];
+        for row in self.rows.iter() {
+            if cells.last().map(|(index, _)| *index) != Some(row.cell_index) {
+                cells.push((row.cell_index, row.params.clone()));
+            }
+        }
+        cells
+            .into_iter()
+            .map(|(cell_index, params)| {
+                let recorders = self
+                    .rows
+                    .iter()
+                    .filter(|row| row.cell_index == cell_index)
+                    .map(|row| &row.recorder);
+                (params, summarize(recorders, z, &metric))
+            })
+            .collect()
+    }
+}
+
+impl<M, Rec: MergeRecorder> SweepResult<M, Rec> {
+    /// merge the recorders of the replications for each cell in order of the cell
+    pub fn merge_cells(self) -> Vec<(Params, Rec)> {
+        let mut cells: Vec<(usize, Params, Rec)> = vec![];
+        for row in self.rows.into_iter() {
+            match cells.last_mut() {
+                Some((cell_index, _, recorder)) if *cell_index == row.cell_index => {
+                    recorder.merge(row.recorder)
+                }
+                _ => cells.push((row.cell_index, row.params, row.recorder)),
+            }
+        }
+        cells
+            .into_iter()
+            .map(|(_, params, recorder)| (params, recorder))
+            .collect()
+    }
+}
@@ -0,0 +1,55 @@
+//! Policy for the errors of the fallible model steps
+
+use crate::event::Priority;
+use crate::FrameIndex;
+use std::fmt;
+
+/// error which occurred in the step of the frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepIncident<Err, E> {
+    /// index of the frame which the error occurred at
+    pub frame: FrameIndex,
+    /// occurred error
+    pub error: Err,
+    /// fired events in the frame which are not stepped because of the error, in fired order.
+    /// the events which the model take in bulk are owned by the model, so these are not contained.
+    pub skipped: Vec<(Priority, E)>,
+}
+
+/// callback which judge to continue the simulation after the error
+pub type StepErrorCallback<Err, E> = Box<dyn FnMut(&StepIncident<Err, E>) -> bool>;
+
+/// policy when the model step return the error.
+///
+/// the remaining fired events in the frame are not stepped after the error and are reported as skipped in the incident,
+/// but the frame is finished with after_last_event and finish_frame as usual.
+pub enum OnStepError<Err, E> {
+    /// stop the simulation with the error
+    Abort,
+    /// skip the rest of the frame, record the incident and continue
+    SkipFrame,
+    /// call the callback with the incident.
+    /// if the callback return true, then skip the rest of the frame and continue, else abort.
+    Callback(StepErrorCallback<Err, E>),
+}
+
+impl<Err, E> OnStepError<Err, E> {
+    /// judge to continue the simulation after the incident
+    pub(crate) fn can_continue(&mut self, incident: &StepIncident<Err, E>) -> bool {
+        match self {
+            OnStepError::Abort => false,
+            OnStepError::SkipFrame => true,
+            OnStepError::Callback(callback) => callback(incident),
+        }
+    }
+}
+
+impl<Err, E> fmt::Debug for OnStepError<Err, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnStepError::Abort => write!(f, "Abort"),
+            OnStepError::SkipFrame => write!(f, "SkipFrame"),
+            OnStepError::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Pull based stepping of the simulator
+
+use crate::event::{Event, EventScheduler, Priority};
+use crate::model::Model;
+use crate::{FrameIndex, Simulator};
+use rand::Rng;
+
+/// handler which is used by the bulk and each event run methods
+pub type FrameHandler<R, M, Rec, E> =
+    fn(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>);
+
+/// report of the frame which is simulated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameReport<E: Event> {
+    /// index of the simulated frame. first frame is 1.
+    pub index: FrameIndex,
+    /// fired events at the frame
+    pub fired_events: Vec<(Priority, E)>,
+    /// annotations which are attached in the frame
+    pub annotations: Vec<String>,
+}
+
+/// counts of the frame which is simulated by run_step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameOutcome {
+    /// index of the simulated frame. first frame is 1.
+    pub frame_index: FrameIndex,
+    /// count of the fired events at the frame including the cascades
+    pub fired: usize,
+    /// count of the events which are scheduled by the model hooks and the handler in the frame.
+    /// the injected events and the rescheduled events are not contained.
+    pub scheduled_during_frame: usize,
+    /// count of the events which are scheduled or wait other event after the frame
+    pub pending_after: usize,
+}
+
+/// iterator which simulate one frame for each next call.
+///
+/// this iterator never finish, so use with take, take_while and so on.
+pub struct Frames<'a, M, E, Rec, R, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+{
+    simulator: &'a mut Simulator<M, E, Rec>,
+    rng: &'a mut R,
+    handler: H,
+}
+
+impl<'a, M, E, Rec, R, H> Frames<'a, M, E, Rec, R, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+{
+    /// initializer
+    pub(crate) fn new(simulator: &'a mut Simulator<M, E, Rec>, rng: &'a mut R, handler: H) -> Self {
+        Frames {
+            simulator,
+            rng,
+            handler,
+        }
+    }
+
+    /// getter for simulator
+    pub fn get_simulator(&self) -> &Simulator<M, E, Rec> {
+        self.simulator
+    }
+}
+
+impl<'a, M, E, Rec, R, H> Iterator for Frames<'a, M, E, Rec, R, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event + Clone,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+{
+    type Item = FrameReport<E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut fired_events = Vec::new();
+        let handler = &mut self.handler;
+        let capture_filter = self.simulator.capture_filter.clone();
+        self.simulator
+            .run_step_buffered(self.rng, |rng, model, recorder, scheduler, events| {
+                // the handler is called again for each cascade in the frame
+                match &capture_filter {
+                    Some(filter) => fired_events.extend(
+                        events
+                            .iter()
+                            .filter(|(priority, event)| filter(*priority, event))
+                            .cloned(),
+                    ),
+                    None => fired_events.extend(events.iter().cloned()),
+                }
+                handler(rng, model, recorder, scheduler, events)
+            });
+
+        let index = self.simulator.frame_index();
+        let annotations = self
+            .simulator
+            .annotations_at(index)
+            .map(str::to_string)
+            .collect();
+        Some(FrameReport {
+            index,
+            fired_events,
+            annotations,
+        })
+    }
+}
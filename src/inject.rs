@@ -0,0 +1,111 @@
+//! Injection of the events from the outside of the simulation
+
+use crate::event::{Event, EventScheduler, Priority, Schedule};
+use rand::Rng;
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// scheduled entry which is sent by the injector
+pub type InjectedEvent<E> = (Schedule, Priority, E);
+
+/// Error for injecting event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectEventError {
+    /// the simulator which receive events is already dropped
+    Disconnected,
+}
+
+impl std::error::Error for InjectEventError {}
+
+impl fmt::Display for InjectEventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InjectEventError::Disconnected => write!(f, "The simulator is disconnected"),
+        }
+    }
+}
+
+/// handle which inject events into running simulation from other thread.
+///
+/// injected events are scheduled at the start of next frame before firing events.
+#[derive(Debug, Clone)]
+pub struct EventInjector<E: Event> {
+    sender: Sender<InjectedEvent<E>>,
+}
+
+impl<E: Event> EventInjector<E> {
+    /// inject event with scheduling
+    pub fn inject(
+        &self,
+        schedule: Schedule,
+        priority: Priority,
+        event: E,
+    ) -> Result<(), InjectEventError> {
+        self.sender
+            .send((schedule, priority, event))
+            .map_err(|_| InjectEventError::Disconnected)
+    }
+
+    /// inject event which fire at immediate timing
+    pub fn immediate(&self, priority: Priority, event: E) -> Result<(), InjectEventError> {
+        self.inject(Schedule::Immediate, priority, event)
+    }
+}
+
+/// receiver side of the injectors which is held by the simulator
+#[derive(Debug, Clone)]
+pub(crate) struct Injection<E: Event> {
+    sender: Sender<InjectedEvent<E>>,
+    receiver: Arc<Mutex<Receiver<InjectedEvent<E>>>>,
+    rejected: usize,
+    cloner: fn(&E) -> E,
+}
+
+impl<E: Event + Clone> Injection<E> {
+    /// initializer
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = channel();
+        Injection {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            rejected: 0,
+            cloner: E::clone,
+        }
+    }
+}
+
+impl<E: Event> Injection<E> {
+    /// create new injector which send to this
+    pub(crate) fn injector(&self) -> EventInjector<E> {
+        EventInjector {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// count of injected events which cannot be scheduled
+    pub(crate) fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// schedule all injected events.
+    /// the event which cannot be scheduled is discarded and counted as rejected.
+    pub(crate) fn drain_into<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+    ) {
+        let receiver = match self.receiver.lock() {
+            Ok(receiver) => receiver,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        for (schedule, priority, event) in receiver.try_iter() {
+            if scheduler
+                .schedule_cloned_by(rng, schedule, priority, event, Some(self.cloner))
+                .is_err()
+            {
+                self.rejected += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Minimal JSON writer and reader for the files which are shared with other tools
+
+use std::fmt::Write;
+
+/// parsed JSON value. number is kept as text to parse as any number type without loss.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// value for the key when this is object
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// getter as string
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// getter as number
+    pub(crate) fn as_number<T: std::str::FromStr>(&self) -> Option<T> {
+        match self {
+            JsonValue::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// getter as members of object
+    pub(crate) fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        match self {
+            JsonValue::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+}
+
+/// append the string as JSON string literal
+pub(crate) fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// parse one JSON value which fill the text
+pub(crate) fn parse(text: &str) -> Option<JsonValue> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        index: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.index == parser.chars.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// recursive descent parser
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        if self.next()? == expected {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.index += 1;
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: JsonValue) -> Option<JsonValue> {
+        for c in keyword.chars() {
+            self.expect(c)?;
+        }
+        Some(value)
+    }
+
+    fn value(&mut self) -> Option<JsonValue> {
+        self.skip_whitespace();
+        match self.peek()? {
+            'n' => self.keyword("null", JsonValue::Null),
+            't' => self.keyword("true", JsonValue::Bool(true)),
+            'f' => self.keyword("false", JsonValue::Bool(false)),
+            '"' => self.string().map(JsonValue::String),
+            '[' => self.array(),
+            '{' => self.object(),
+            '-' | '0'..='9' => self.number(),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Option<JsonValue> {
+        let start = self.index;
+        while let Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9') = self.peek() {
+            self.index += 1;
+        }
+        let text: String = self.chars[start..self.index].iter().collect();
+        text.parse::<f64>().ok()?;
+        Some(JsonValue::Number(text))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Some(result),
+                '\\' => match self.next()? {
+                    '"' => result.push('"'),
+                    '\\' => result.push('\\'),
+                    '/' => result.push('/'),
+                    'b' => result.push('\u{8}'),
+                    'f' => result.push('\u{c}'),
+                    'n' => result.push('\n'),
+                    'r' => result.push('\r'),
+                    't' => result.push('\t'),
+                    'u' => {
+                        let high = self.hex4()?;
+                        let code = if (0xD800..0xDC00).contains(&high) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            0x10000 + ((high - 0xD800) << 10) + (low.checked_sub(0xDC00)?)
+                        } else {
+                            high
+                        };
+                        result.push(char::from_u32(code)?);
+                    }
+                    _ => return None,
+                },
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            code = code * 16 + self.next()?.to_digit(16)?;
+        }
+        Some(code)
+    }
+
+    fn array(&mut self) -> Option<JsonValue> {
+        self.expect('[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.peek()? == ']' {
+            self.index += 1;
+            return Some(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                ']' => return Some(JsonValue::Array(items)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<JsonValue> {
+        self.expect('{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek()? == '}' {
+            self.index += 1;
+            return Some(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                '}' => return Some(JsonValue::Object(members)),
+                _ => return None,
+            }
+        }
+    }
+}
@@ -1,23 +1,126 @@
 //! Simulator is discrete time simulator with event which fire at scheduled timing.
 
-use crate::event::{Event, EventScheduler, Priority};
-use crate::model::{BulkEvents, Model, NothingEventModel, StepEachEvent};
-use rand::Rng;
+use crate::annotation::Annotation;
+use crate::clock::VirtualClock;
+use crate::closure::CallbackEvent;
+use crate::control::{ControlToken, StopReason};
+use crate::dynamic::{Dispatcher, DynEvent};
+#[cfg(feature = "rayon")]
+use crate::event::ScheduleRequests;
+use crate::event::{
+    extract_matching, Delivery, Event, EventClass, EventScheduler, EventTimer, FrameActions,
+    PeekedEvent, Priority, Tag,
+};
+use crate::fallible::{OnStepError, StepIncident};
+use crate::frame::{FrameHandler, FrameOutcome, Frames};
+use crate::inject::{EventInjector, Injection};
+#[cfg(feature = "rayon")]
+use crate::model::ParStepEachEvent;
+use crate::model::{
+    BulkEvents, MixedStep, Model, NothingEventModel, Phase, StepByPhase, StepByPriorityGroup,
+    StepEachEvent, TryBulkEvents, TryStepEachEvent,
+};
+use crate::progress::{Progress, SimulationReport};
+use crate::realtime::{Pacer, Pacing, RealtimeReport};
+use crate::recorder::{FinalizeRecorder, Recorder, RecorderHooks, ResettableRecorder};
+use crate::stop::{StopCondition, StopContext};
+#[cfg(feature = "async")]
+use crate::stream::{FrameStream, YieldNow};
+use crate::trace::TraceRecord;
+#[cfg(feature = "rayon")]
+use rand::rngs::StdRng;
+#[cfg(feature = "rayon")]
+use rand::SeedableRng;
+use rand::{Rng, RngCore};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::any::Any;
+#[cfg(feature = "rayon")]
+use std::collections::BTreeMap;
+use std::convert::{Infallible, TryFrom};
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::io::{self, Write};
 use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+pub mod accounting;
+pub mod agents;
+pub mod annotation;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod bridge;
+pub mod characterize;
+pub mod checkpoint;
+pub mod clock;
+pub mod closure;
+#[cfg(feature = "continuous")]
+pub mod continuous;
+pub mod control;
+pub mod correlation;
+#[cfg(feature = "continuous")]
+pub mod coupling;
+pub mod describe;
+pub mod diff;
+pub mod dynamic;
+pub mod ensemble;
 pub mod event;
+pub mod experiment;
+pub mod fallible;
+pub mod frame;
+pub mod inject;
+mod json;
+pub mod memo;
 pub mod model;
+pub mod nested;
+pub mod pool;
+pub mod processes;
+pub mod progress;
+pub mod realtime;
+pub mod recorder;
+pub mod report;
+pub mod resources;
+pub mod routing;
+pub mod scaffold;
+pub mod stop;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod tick;
+pub mod trace;
+pub mod validation;
+
+/// index of the simulated frame
+pub type FrameIndex = u64;
 
 /// TimeCounter for user
-pub trait FrameCounter: Copy {
+pub trait FrameCounter {
     /// start state. this value used always.
     fn start_index() -> Self;
 
     /// get next index
     fn next_index(&mut self);
 
+    /// get next index. if the index cannot count up anymore (e.g. overflow), then return false.
+    /// the simulation stop when false is returned, so the index never wrap around.
+    fn try_next_index(&mut self) -> bool {
+        self.next_index();
+        true
+    }
+
     /// check can continue
     fn can_continue(&self, specified: &Self) -> bool;
+
+    /// count of the frames which are not simulated yet when this index is simulated. if unknown, then None.
+    #[allow(unused_variables)]
+    fn remaining(&self, specified: &Self) -> Option<FrameIndex> {
+        // usually not use
+        None
+    }
 }
 
 macro_rules! impl_counter {
@@ -28,12 +131,26 @@ macro_rules! impl_counter {
             }
 
             fn next_index(&mut self) {
-                *self += 1;
+                *self = self.saturating_add(1);
+            }
+
+            fn try_next_index(&mut self) -> bool {
+                match self.checked_add(1) {
+                    Some(next) => {
+                        *self = next;
+                        true
+                    }
+                    None => false,
+                }
             }
 
             fn can_continue(&self, specified: &$t) -> bool {
                 self <= specified
             }
+
+            fn remaining(&self, specified: &$t) -> Option<FrameIndex> {
+                FrameIndex::try_from(specified.saturating_sub(*self)).ok()
+            }
         }
     };
 }
@@ -44,6 +161,148 @@ impl_counter!(u64, u64);
 impl_counter!(u128, u128);
 impl_counter!(usize, usize);
 
+macro_rules! impl_signed_counter {
+    ($t:ty) => {
+        impl FrameCounter for $t {
+            /// count from 0. if specified value is not positive, then no frame is simulated.
+            fn start_index() -> $t {
+                0
+            }
+
+            fn next_index(&mut self) {
+                *self = self.saturating_add(1);
+            }
+
+            fn try_next_index(&mut self) -> bool {
+                match self.checked_add(1) {
+                    Some(next) => {
+                        *self = next;
+                        true
+                    }
+                    None => false,
+                }
+            }
+
+            fn can_continue(&self, specified: &$t) -> bool {
+                self <= specified
+            }
+
+            fn remaining(&self, specified: &$t) -> Option<FrameIndex> {
+                FrameIndex::try_from(specified.saturating_sub(*self).max(0)).ok()
+            }
+        }
+    };
+}
+impl_signed_counter!(i8);
+impl_signed_counter!(i16);
+impl_signed_counter!(i32);
+impl_signed_counter!(i64);
+impl_signed_counter!(i128);
+impl_signed_counter!(isize);
+
+/// simulate the count of frames in the range, i.e. end - start frames.
+impl FrameCounter for Range<FrameIndex> {
+    fn start_index() -> Self {
+        0..0
+    }
+
+    fn next_index(&mut self) {
+        self.end = self.end.saturating_add(1);
+    }
+
+    fn try_next_index(&mut self) -> bool {
+        match self.end.checked_add(1) {
+            Some(end) => {
+                self.end = end;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn can_continue(&self, specified: &Self) -> bool {
+        self.end <= specified.end.saturating_sub(specified.start)
+    }
+
+    fn remaining(&self, specified: &Self) -> Option<FrameIndex> {
+        Some(
+            specified
+                .end
+                .saturating_sub(specified.start)
+                .saturating_sub(self.end),
+        )
+    }
+}
+
+/// counter which never overflow for the extremely long simulation
+#[cfg(feature = "num-bigint")]
+impl FrameCounter for num_bigint::BigUint {
+    fn start_index() -> Self {
+        num_bigint::BigUint::default()
+    }
+
+    fn next_index(&mut self) {
+        *self += 1_u32;
+    }
+
+    fn can_continue(&self, specified: &Self) -> bool {
+        self <= specified
+    }
+
+    fn remaining(&self, specified: &Self) -> Option<FrameIndex> {
+        if self > specified {
+            return Some(0);
+        }
+        FrameIndex::try_from(specified - self).ok()
+    }
+}
+
+/// counter which simulate frames until the duration of the real time is elapsed from the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationCounter {
+    duration: Duration,
+    started: Option<Instant>,
+}
+
+impl DurationCounter {
+    /// initializer
+    pub fn new(duration: Duration) -> Self {
+        DurationCounter {
+            duration,
+            started: None,
+        }
+    }
+
+    /// getter for the duration
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl From<Duration> for DurationCounter {
+    fn from(duration: Duration) -> Self {
+        Self::new(duration)
+    }
+}
+
+impl FrameCounter for DurationCounter {
+    fn start_index() -> Self {
+        DurationCounter {
+            duration: Duration::default(),
+            started: Some(Instant::now()),
+        }
+    }
+
+    fn next_index(&mut self) {
+        // the elapsed time is measured in can_continue
+    }
+
+    fn can_continue(&self, specified: &Self) -> bool {
+        self.started
+            .map_or(false, |started| started.elapsed() < specified.duration)
+    }
+}
+
 /// simulator for Nothing event
 #[derive(Debug, Clone)]
 pub struct NothingEventSimulator<M, Rec>
@@ -52,6 +311,17 @@ where
 {
     model: M,
     recorder: Rec,
+    frame: FrameIndex,
+}
+
+impl<M, Rec> Default for NothingEventSimulator<M, Rec>
+where
+    M: NothingEventModel<Rec> + Default,
+    Rec: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<M, Rec> NothingEventSimulator<M, Rec>
@@ -67,6 +337,7 @@ where
         let mut sim = Self {
             model: Default::default(),
             recorder: Default::default(),
+            frame: 0,
         };
         sim.initialize();
         sim
@@ -74,21 +345,50 @@ where
 
     /// create simulator from model
     pub fn create_from(model: M, recorder: Rec) -> Self {
-        let mut sim = Self { model, recorder };
+        let mut sim = Self {
+            model,
+            recorder,
+            frame: 0,
+        };
         sim.initialize();
         sim
     }
 
     /// initialize simulator
     fn initialize(&mut self) {
+        self.frame = 0;
         self.model.initialize(&mut self.recorder);
     }
 
+    /// index of the last simulated frame. if not simulated yet, then 0.
+    pub fn frame_index(&self) -> FrameIndex {
+        self.frame
+    }
+
+    /// reinitialize the model in place for the next run. the recorder is kept.
+    pub fn reset(&mut self) {
+        self.initialize();
+    }
+
+    /// reset the recorder and reinitialize the model in place for the next run
+    pub fn reset_with_recorder(&mut self)
+    where
+        Rec: ResettableRecorder,
+    {
+        self.recorder.reset();
+        self.initialize();
+    }
+
     /// getter for model
     pub fn get_model(&self) -> &M {
         &self.model
     }
 
+    /// getter for model
+    pub fn get_model_as_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+
     /// getter for recorder
     pub fn get_recorder(&self) -> &Rec {
         &self.recorder
@@ -104,23 +404,28 @@ where
         mem::replace(&mut self.recorder, new_recorder)
     }
 
+    /// decompose simulator into model and recorder
+    pub fn into_parts(self) -> (M, Rec) {
+        (self.model, self.recorder)
+    }
+
     //
     // run simulation
     //
 
     /// run simulate for one frame
     pub fn run_step(&mut self) {
-        self.model.start_frame(&mut self.recorder);
+        self.frame += 1;
+        self.model.start_frame_at(&mut self.recorder, self.frame);
         self.model.step(&mut self.recorder);
-        self.model.finish_frame(&mut self.recorder);
+        self.model.finish_frame_at(&mut self.recorder, self.frame);
     }
 
     /// run simulate for frames
     pub fn run_n<FC: FrameCounter>(&mut self, counter: FC) {
         let mut index = FC::start_index();
         loop {
-            index.next_index();
-            if !index.can_continue(&counter) {
+            if !index.try_next_index() || !index.can_continue(&counter) {
                 break;
             }
 
@@ -129,12 +434,12 @@ where
     }
 
     /// run simulation until condition is true
-    pub fn run_until<F>(&mut self, can_continue: F)
+    pub fn run_until<F>(&mut self, mut can_continue: F)
     where
-        F: Fn(&M) -> bool,
+        F: FnMut(&M, &Rec, FrameIndex) -> bool,
     {
         loop {
-            if !can_continue(&self.model) {
+            if !can_continue(&self.model, &self.recorder, self.frame) {
                 break;
             }
 
@@ -143,14 +448,14 @@ where
     }
 
     /// run simulation with update model's state
-    pub fn run_with_state<F, P>(&mut self, update_state: F, can_continue: P)
+    pub fn run_with_state<F, P>(&mut self, mut update_state: F, mut can_continue: P)
     where
-        F: Fn(&mut M),
-        P: Fn(&M) -> bool,
+        F: FnMut(&mut M, &Rec, FrameIndex),
+        P: FnMut(&M, &Rec, FrameIndex) -> bool,
     {
         loop {
-            update_state(&mut self.model);
-            if !can_continue(&self.model) {
+            update_state(&mut self.model, &self.recorder, self.frame);
+            if !can_continue(&self.model, &self.recorder, self.frame) {
                 break;
             }
 
@@ -159,8 +464,25 @@ where
     }
 }
 
+/// action at the end of the warm-up period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warmup<Rec> {
+    /// replace the recorder with the fresh recorder.
+    ///
+    /// args is the recorder which is used after the warm-up period.
+    SwapRecorder(Rec),
+    /// call Model::on_warmup_complete
+    Notify,
+}
+
+/// filter which judge the fired event is captured
+type CaptureFilter<E> = Arc<dyn Fn(Priority, &E) -> bool + Send + Sync>;
+
+/// labeler of the fired event which is kept in the history
+type HistoryLabeler<E> = Arc<dyn Fn(&E) -> String + Send + Sync>;
+
 /// simulator
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Simulator<M, E, Rec>
 where
     M: Model<Rec, ModelEvent = E>,
@@ -169,6 +491,144 @@ where
     model: M,
     recorder: Rec,
     scheduler: EventScheduler<E>,
+    frame: FrameIndex,
+    injection: Option<Injection<E>>,
+    capture_filter: Option<CaptureFilter<E>>,
+    recorder_hooks: Option<RecorderHooks<E, Rec>>,
+    /// finalizer of the recorder which is called at the end of each run
+    finalizer: Option<fn(&mut Rec, &SimulationReport)>,
+    history_labeler: Option<HistoryLabeler<E>>,
+    /// captured fired events which are kept for the export
+    fired_history: Vec<TraceRecord>,
+    /// count of the fired events from the start
+    fired_total: u64,
+    /// count of the events which are scheduled by the model in the last frame
+    scheduled_in_frame: u64,
+    /// buffer for the fired events which is reused in each frame
+    fired_events: Vec<(Priority, E)>,
+    annotations: Vec<Annotation>,
+    /// handlers for the type-erased events
+    dispatcher: Dispatcher<M, Rec>,
+    /// simulated duration of one frame
+    tick: Duration,
+    /// check the invariants of the scheduler after each frame
+    check_invariants: bool,
+}
+
+/// parts of the simulator which act for the frame which is fired by the scheduler
+struct StepActions<'a, M, E: Event, Rec, H> {
+    model: &'a mut M,
+    recorder: &'a mut Rec,
+    handler: H,
+    frame: FrameIndex,
+    fired_total: &'a mut u64,
+    capture_filter: &'a Option<CaptureFilter<E>>,
+    recorder_hooks: Option<RecorderHooks<E, Rec>>,
+    history_labeler: &'a Option<HistoryLabeler<E>>,
+    fired_history: &'a mut Vec<TraceRecord>,
+}
+
+impl<'a, M, E, Rec, R, H, Err> FrameActions<E, R> for StepActions<'a, M, E, Rec, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng + ?Sized,
+    H: FnMut(
+        &mut R,
+        &mut M,
+        &mut Rec,
+        &mut EventScheduler<E>,
+        &mut Vec<(Priority, E)>,
+    ) -> Result<(), Err>,
+{
+    type Error = Err;
+
+    fn fired(&mut self, fired_events: &[(Priority, E)]) {
+        *self.fired_total += fired_events.len() as u64;
+        if self.recorder_hooks.is_none() && self.history_labeler.is_none() {
+            return;
+        }
+        for (priority, event) in fired_events.iter() {
+            let captured = self
+                .capture_filter
+                .as_ref()
+                .map_or(true, |filter| filter(*priority, event));
+            if !captured {
+                continue;
+            }
+            if let Some(hooks) = self.recorder_hooks {
+                (hooks.event_fired)(self.recorder, self.frame, *priority, event);
+            }
+            if let Some(labeler) = self.history_labeler.as_ref() {
+                self.fired_history.push(TraceRecord {
+                    frame: self.frame,
+                    priority: *priority,
+                    label: labeler(event),
+                });
+            }
+        }
+    }
+
+    fn expired(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        priority: Priority,
+        event: E,
+    ) {
+        self.model
+            .on_expired(rng, self.recorder, scheduler, priority, event);
+    }
+
+    fn batch(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        tag: Tag,
+        events: Vec<(Priority, E)>,
+    ) {
+        self.model
+            .on_batch(rng, self.recorder, scheduler, tag, events);
+    }
+
+    fn before_first(&mut self, rng: &mut R, scheduler: &mut EventScheduler<E>) {
+        self.model.before_first_event(rng, self.recorder, scheduler);
+    }
+
+    fn handle(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) -> Result<(), Err> {
+        (self.handler)(rng, self.model, self.recorder, scheduler, fired_events)
+    }
+
+    fn after_last(&mut self, rng: &mut R, scheduler: &mut EventScheduler<E>) {
+        self.model.after_last_event(rng, self.recorder, scheduler);
+    }
+}
+
+impl<M, E, Rec> fmt::Debug for Simulator<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E> + fmt::Debug,
+    E: Event + fmt::Debug,
+    Rec: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulator")
+            .field("model", &self.model)
+            .field("recorder", &self.recorder)
+            .field("scheduler", &self.scheduler)
+            .field("frame", &self.frame)
+            .field("injection", &self.injection)
+            .field("capture_filter", &self.capture_filter.is_some())
+            .field("recorder_hooks", &self.recorder_hooks.is_some())
+            .field("finalizer", &self.finalizer.is_some())
+            .field("fired_history", &self.fired_history.len())
+            .field("dispatcher", &self.dispatcher)
+            .finish()
+    }
 }
 
 impl<M, E, Rec> Simulator<M, E, Rec>
@@ -186,6 +646,20 @@ where
             model: Default::default(),
             recorder: Default::default(),
             scheduler: EventScheduler::new(),
+            frame: 0,
+            injection: None,
+            capture_filter: None,
+            recorder_hooks: None,
+            finalizer: None,
+            history_labeler: None,
+            fired_history: vec![],
+            fired_total: 0,
+            scheduled_in_frame: 0,
+            fired_events: vec![],
+            annotations: vec![],
+            dispatcher: Dispatcher::new(),
+            tick: Duration::from_secs(1),
+            check_invariants: false,
         };
         sim.initialize(rng);
         sim
@@ -197,15 +671,70 @@ where
             model,
             recorder,
             scheduler: EventScheduler::new(),
+            frame: 0,
+            injection: None,
+            capture_filter: None,
+            recorder_hooks: None,
+            finalizer: None,
+            history_labeler: None,
+            fired_history: vec![],
+            fired_total: 0,
+            scheduled_in_frame: 0,
+            fired_events: vec![],
+            annotations: vec![],
+            dispatcher: Dispatcher::new(),
+            tick: Duration::from_secs(1),
+            check_invariants: false,
         };
         sim.initialize(rng);
         sim
     }
 
+    /// create simulator from model with calling the hooks of the recorder
+    pub fn create_recorded<R: Rng + ?Sized>(rng: &mut R, model: M, recorder: Rec) -> Self
+    where
+        Rec: Recorder<E>,
+    {
+        let mut sim = Self::create_from(rng, model, recorder);
+        sim.enable_recorder_hooks();
+        sim
+    }
+
     /// initialize simulator
     fn initialize<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         self.model
             .initialize(rng, &mut self.recorder, &mut self.scheduler);
+        self.attach_annotations();
+    }
+
+    /// attach the annotations in the scheduler to the current frame
+    fn attach_annotations(&mut self) {
+        let frame = self.frame;
+        self.annotations.extend(
+            self.scheduler
+                .take_annotations()
+                .map(|text| Annotation { frame, text }),
+        );
+    }
+
+    /// clear the scheduler, reset the frame clock and reinitialize the model in place for the next run.
+    /// the allocated capacity of the scheduler and the recorder is reused.
+    pub fn reset<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.scheduler.reset();
+        self.frame = 0;
+        self.annotations.clear();
+        self.fired_history.clear();
+        self.fired_total = 0;
+        self.initialize(rng);
+    }
+
+    /// reset with resetting the recorder
+    pub fn reset_with_recorder<R: Rng + ?Sized>(&mut self, rng: &mut R)
+    where
+        Rec: ResettableRecorder,
+    {
+        self.recorder.reset();
+        self.reset(rng);
     }
 
     /// getter for model
@@ -213,11 +742,28 @@ where
         &self.model
     }
 
+    /// getter for model
+    pub fn get_model_as_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+
     /// getter for scheduler
     pub fn get_scheduler(&self) -> &EventScheduler<E> {
         &self.scheduler
     }
 
+    /// events which will fire in the next frame in fired order without changing the simulator.
+    /// pass the clone of the rng of the simulation to predict the timers of the rescheduled events.
+    /// the immediate events which the model will schedule in the frame are not contained.
+    pub fn peek_next_frame<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<PeekedEvent<'_, E>> {
+        self.scheduler.peek_next(rng)
+    }
+
+    /// getter for scheduler. use between frames, e.g. to schedule events from outside of the model.
+    pub fn get_scheduler_as_mut(&mut self) -> &mut EventScheduler<E> {
+        &mut self.scheduler
+    }
+
     /// getter for recorder
     pub fn get_recorder(&self) -> &Rec {
         &self.recorder
@@ -233,154 +779,1230 @@ where
         mem::replace(&mut self.recorder, new_recorder)
     }
 
-    //
-    // run simulation
-    //
+    /// index of the last simulated frame. if not simulated yet, then 0.
+    pub fn frame_index(&self) -> FrameIndex {
+        self.frame
+    }
 
-    /// run simulate for one frame
-    pub fn run_step<R: Rng + ?Sized, H>(&mut self, rng: &mut R, mut handler: H)
+    /// set the simulated duration of one frame. the default is 1 second.
+    pub fn set_tick_duration(&mut self, tick: Duration) {
+        self.tick = tick;
+    }
+
+    /// getter for the simulated duration of one frame
+    pub fn tick_duration(&self) -> Duration {
+        self.tick
+    }
+
+    /// clock at the last simulated frame which start at the unix epoch
+    pub fn clock(&self) -> VirtualClock {
+        let mut clock = VirtualClock::from_unix_epoch(self.tick);
+        clock.set_frame(self.frame);
+        clock
+    }
+
+    /// simulated duration from the start to the last simulated frame
+    pub fn sim_elapsed(&self) -> Duration {
+        self.clock().elapsed()
+    }
+
+    /// timer which fire after the simulated duration. the partial frame is counted as one frame.
+    pub fn timer_for(&self, duration: Duration) -> EventTimer {
+        self.clock().timer_for(duration)
+    }
+
+    /// pacing which simulate the speed times faster than the real time, e.g. 1.0 is the real time.
+    /// the speed 0 or less is treated as the real time.
+    pub fn realtime_pacing(&self, speed: f64) -> Pacing {
+        let speed = if speed > 0.0 { speed } else { 1.0 };
+        Pacing::catch_up(self.tick.div_f64(speed))
+    }
+
+    /// attach the annotation to the last simulated frame, e.g. by the controller between frames
+    pub fn annotate<S: Into<String>>(&mut self, text: S) {
+        self.annotations.push(Annotation {
+            frame: self.frame,
+            text: text.into(),
+        });
+    }
+
+    /// annotations in attached order
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// texts of the annotations which are attached to the frame
+    pub fn annotations_at(&self, frame: FrameIndex) -> impl Iterator<Item = &str> {
+        // the annotations are always attached in frame order
+        let start = self
+            .annotations
+            .partition_point(|annotation| annotation.frame < frame);
+        self.annotations[start..]
+            .iter()
+            .take_while(move |annotation| annotation.frame == frame)
+            .map(|annotation| annotation.text.as_str())
+    }
+
+    /// take the annotations out of the simulator
+    pub fn take_annotations(&mut self) -> Vec<Annotation> {
+        mem::take(&mut self.annotations)
+    }
+
+    /// decompose simulator into model, recorder and scheduler
+    pub fn into_parts(self) -> (M, Rec, EventScheduler<E>) {
+        (self.model, self.recorder, self.scheduler)
+    }
+
+    /// create handle which inject events into this simulator from other thread.
+    /// injected events are scheduled at the start of each frame before firing events.
+    ///
+    /// cloned simulator share the injected events with original.
+    pub fn injector(&mut self) -> EventInjector<E>
     where
-        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+        E: Clone,
     {
-        self.model.start_frame(&mut self.recorder);
-        let fired_events: Vec<(Priority, E)> = self.scheduler.next_time_and_fire(rng);
-        self.model
-            .before_first_event(rng, &mut self.recorder, &mut self.scheduler);
-        handler(
-            rng,
-            &mut self.model,
-            &mut self.recorder,
-            &mut self.scheduler,
-            fired_events,
-        );
-        self.model
-            .after_last_event(rng, &mut self.recorder, &mut self.scheduler);
+        self.injection.get_or_insert_with(Injection::new).injector()
+    }
 
-        self.model.finish_frame(&mut self.recorder);
+    /// count of injected events which are discarded because these cannot be scheduled
+    pub fn rejected_injections(&self) -> usize {
+        self.injection
+            .as_ref()
+            .map_or(0, |injection| injection.rejected())
     }
 
-    /// run simulate for frames
-    pub fn run_n<R: Rng + ?Sized, FC: FrameCounter, H>(
-        &mut self,
-        rng: &mut R,
-        counter: FC,
-        mut handler: H,
-    ) where
-        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    /// capture only the fired events which the filter accept.
+    /// the captured events are reported to the frame reports, traces and recorder hooks,
+    /// but all fired events are passed to the model as usual.
+    pub fn capture_only<F>(&mut self, filter: F)
+    where
+        F: Fn(Priority, &E) -> bool + Send + Sync + 'static,
     {
-        let mut index = FC::start_index();
-        loop {
-            index.next_index();
-            if !index.can_continue(&counter) {
-                break;
-            }
+        self.capture_filter = Some(Arc::new(filter));
+    }
 
-            self.run_step(rng, |rng, model, recorder, scheduler, events| {
-                handler(rng, model, recorder, scheduler, events)
-            });
-        }
+    /// capture all fired events
+    pub fn capture_all(&mut self) {
+        self.capture_filter = None;
     }
 
-    /// run simulation until condition is true
-    pub fn run_until<R: Rng + ?Sized, F, H>(&mut self, rng: &mut R, can_continue: F, mut handler: H)
+    /// call the hooks of the recorder at the start of the frame, for each captured event and at the end of the frame
+    pub fn enable_recorder_hooks(&mut self)
     where
-        F: Fn(&M) -> bool,
-        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+        Rec: Recorder<E>,
     {
-        loop {
-            if !can_continue(&self.model) {
-                break;
-            }
+        self.recorder_hooks = Some(RecorderHooks::new());
+    }
 
-            self.run_step(rng, |rng, model, recorder, scheduler, events| {
-                handler(rng, model, recorder, scheduler, events)
-            });
-        }
+    /// stop to call the hooks of the recorder
+    pub fn disable_recorder_hooks(&mut self) {
+        self.recorder_hooks = None;
     }
 
-    /// run simulation with update model's state
-    pub fn run_with_state<R: Rng + ?Sized, F, P, H>(
-        &mut self,
-        rng: &mut R,
-        update_state: F,
-        can_continue: P,
-        mut handler: H,
-    ) where
-        F: Fn(&mut M),
-        P: Fn(&M) -> bool,
-        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    /// finalize the recorder at the end of each run
+    pub fn enable_recorder_finalize(&mut self)
+    where
+        Rec: FinalizeRecorder,
     {
-        loop {
-            update_state(&mut self.model);
-            if !can_continue(&self.model) {
-                break;
-            }
+        self.finalizer = Some(Rec::finalize);
+    }
 
-            self.run_step(rng, |rng, model, recorder, scheduler, events| {
-                handler(rng, model, recorder, scheduler, events)
-            });
-        }
+    /// stop to finalize the recorder
+    pub fn disable_recorder_finalize(&mut self) {
+        self.finalizer = None;
     }
-}
 
-// TODO If concat_idents macro is to be stable, then replace $suffix:ident and concat_idents!.
-macro_rules! impl_base_set {
-    ($handler:ident, [$run_step:ident,$run_n:ident,$run_until:ident,$run_with_state:ident]) => {
-        /// run simulate for one frame
-        pub fn $run_step<R: Rng + ?Sized>(&mut self, rng: &mut R) {
-            self.model.start_frame(&mut self.recorder);
-            let fired_events: Vec<(Priority, E)> = self.scheduler.next_time_and_fire(rng);
-            self.model
-                .before_first_event(rng, &mut self.recorder, &mut self.scheduler);
-            self.$handler(rng, fired_events);
-            self.model
-                .after_last_event(rng, &mut self.recorder, &mut self.scheduler);
+    /// check the invariants of the scheduler after each frame and panic when broken.
+    /// the check scan all scheduled events in each frame, so enable it in the tests or while debugging the model.
+    pub fn enable_invariant_checks(&mut self) {
+        self.check_invariants = true;
+    }
 
-            self.model.finish_frame(&mut self.recorder);
-        }
+    /// stop to check the invariants of the scheduler after each frame
+    pub fn disable_invariant_checks(&mut self) {
+        self.check_invariants = false;
+    }
 
-        /// run simulate for frames
-        pub fn $run_n<R: Rng + ?Sized, FC: FrameCounter>(&mut self, rng: &mut R, counter: FC) {
-            let mut index = FC::start_index();
-            loop {
-                index.next_index();
-                if !index.can_continue(&counter) {
-                    break;
-                }
-                self.$run_step(rng);
-            }
-        }
+    /// keep the captured fired events with labeling event for the export of the timeline
+    pub fn keep_fired_history<L>(&mut self, labeler: L)
+    where
+        L: Fn(&E) -> String + Send + Sync + 'static,
+    {
+        self.history_labeler = Some(Arc::new(labeler));
+    }
 
-        /// run simulation until condition is true
-        pub fn $run_until<R: Rng + ?Sized, F>(&mut self, rng: &mut R, can_continue: F)
-        where
-            F: Fn(&M) -> bool,
-        {
-            loop {
-                if !can_continue(&self.model) {
-                    break;
-                }
-                self.$run_step(rng);
-            }
-        }
+    /// stop to keep the fired events. the kept history is not removed.
+    pub fn stop_fired_history(&mut self) {
+        self.history_labeler = None;
+    }
 
-        /// run simulation with update model's state
-        pub fn $run_with_state<R: Rng + ?Sized, S, F, P>(
-            &mut self,
-            rng: &mut R,
-            update_state: F,
-            can_continue: P,
-        ) where
-            F: Fn(&mut M),
-            P: Fn(&M) -> bool,
+    /// captured fired events which are kept in fired order
+    pub fn fired_history(&self) -> &[TraceRecord] {
+        &self.fired_history
+    }
+
+    /// write the kept fired events and the scheduled events with labeling event as Gantt chart in Mermaid.
+    /// the frame of the scheduled event is the frame which the event will fire at.
+    pub fn export_timeline<W, L>(&self, writer: &mut W, labeler: L) -> io::Result<()>
+    where
+        W: Write,
+        L: FnMut(&E) -> String,
+    {
+        trace::write_timeline(
+            writer,
+            &self.fired_history,
+            &self.scheduler.timeline_records(self.frame, labeler),
+        )
+    }
+
+    /// judge the fired event is captured
+    pub fn is_captured(&self, priority: Priority, event: &E) -> bool {
+        self.capture_filter
+            .as_ref()
+            .map_or(true, |filter| filter(priority, event))
+    }
+
+    //
+    // run simulation
+    //
+
+    /// run simulate for one frame
+    pub fn run_step<R: Rng + ?Sized, H>(&mut self, rng: &mut R, handler: H) -> FrameOutcome
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_step_buffered(rng, owned_events(handler))
+    }
+
+    /// same as run_step, but the handler take the buffer of the fired events.
+    /// the buffer is reused in each frame, so the handler can drain it without allocation.
+    pub fn run_step_buffered<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        mut handler: H,
+    ) -> FrameOutcome
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let fired_before = self.fired_total;
+        let _ = self.step(rng, |rng, model, recorder, scheduler, events| {
+            handler(rng, model, recorder, scheduler, events);
+            Ok::<(), Infallible>(())
+        });
+        FrameOutcome {
+            frame_index: self.frame,
+            fired: usize::try_from(self.fired_total - fired_before).unwrap_or(usize::MAX),
+            scheduled_during_frame: usize::try_from(self.scheduled_in_frame).unwrap_or(usize::MAX),
+            pending_after: self.scheduler.count() + self.scheduler.pending_count(),
+        }
+    }
+
+    /// simulate one frame. the handler is called again for each cascade while the handler is ok.
+    /// when the handler fail, the events which the handler leave in the buffer are returned with the error as skipped.
+    fn step<R: Rng + ?Sized, H, Err>(
+        &mut self,
+        rng: &mut R,
+        handler: H,
+    ) -> Result<(), (Err, Vec<(Priority, E)>)>
+    where
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            &mut Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        self.frame += 1;
+        if let Some(hooks) = self.recorder_hooks {
+            (hooks.frame_start)(&mut self.recorder, self.frame);
+        }
+        self.model.start_frame_at(&mut self.recorder, self.frame);
+        if let Some(injection) = self.injection.as_mut() {
+            injection.drain_into(rng, &mut self.scheduler);
+        }
+        // the injected events are not scheduled by the model
+        let issued_before = self.scheduler.issued_count();
+        let mut fired_events = mem::take(&mut self.fired_events);
+        let mut actions = StepActions {
+            model: &mut self.model,
+            recorder: &mut self.recorder,
+            handler,
+            frame: self.frame,
+            fired_total: &mut self.fired_total,
+            capture_filter: &self.capture_filter,
+            recorder_hooks: self.recorder_hooks,
+            history_labeler: &self.history_labeler,
+            fired_history: &mut self.fired_history,
+        };
+        let result = self
+            .scheduler
+            .fire_frame(rng, &mut fired_events, &mut actions)
+            .map_err(|error| {
+                let mut skipped = Vec::with_capacity(fired_events.len());
+                skipped.append(&mut fired_events);
+                (error, skipped)
+            });
+        self.fired_events = fired_events;
+        self.scheduled_in_frame = self.scheduler.issued_count() - issued_before;
+
+        self.model.finish_frame_at(&mut self.recorder, self.frame);
+        if let Some(hooks) = self.recorder_hooks {
+            (hooks.frame_end)(&mut self.recorder, self.frame);
+        }
+        self.attach_annotations();
+        if self.check_invariants {
+            if let Err(violation) = self.scheduler.check_invariants() {
+                panic!(
+                    "broken scheduler at the frame {}: {}",
+                    self.frame, violation
+                );
+            }
+        }
+        result
+    }
+
+    /// run simulate for one frame with the fallible handler
+    pub fn try_run_step<R: Rng + ?Sized, H, Err>(
+        &mut self,
+        rng: &mut R,
+        handler: H,
+    ) -> Result<(), StepIncident<Err, E>>
+    where
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        self.try_run_step_buffered(rng, owned_events(handler))
+    }
+
+    /// same as try_run_step, but the handler take the buffer of the fired events.
+    /// the events which the handler leave in the buffer when fail are reported as skipped in the incident.
+    pub fn try_run_step_buffered<R: Rng + ?Sized, H, Err>(
+        &mut self,
+        rng: &mut R,
+        handler: H,
+    ) -> Result<(), StepIncident<Err, E>>
+    where
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            &mut Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        let frame = self.frame + 1;
+        self.step(rng, handler)
+            .map_err(|(error, skipped)| StepIncident {
+                frame,
+                error,
+                skipped,
+            })
+    }
+
+    /// run simulate for frames with the fallible handler.
+    /// return the incidents which are skipped by the policy, or the incident which abort the simulation.
+    pub fn try_run_n<R: Rng + ?Sized, FC: FrameCounter, H, Err>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        policy: OnStepError<Err, E>,
+        handler: H,
+    ) -> Result<Vec<StepIncident<Err, E>>, StepIncident<Err, E>>
+    where
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        self.try_run_n_buffered(rng, counter, policy, owned_events(handler))
+    }
+
+    /// same as try_run_n, but the handler take the buffer of the fired events.
+    pub fn try_run_n_buffered<R: Rng + ?Sized, FC: FrameCounter, H, Err>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        mut policy: OnStepError<Err, E>,
+        mut handler: H,
+    ) -> Result<Vec<StepIncident<Err, E>>, StepIncident<Err, E>>
+    where
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            &mut Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let mut incidents = vec![];
+        let mut index = FC::start_index();
+        loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break;
+            }
+
+            if let Err(incident) = self.try_run_step_buffered(rng, &mut handler) {
+                if !policy.can_continue(&incident) {
+                    self.run_report(frame, fired_total, started, StopReason::StepFailed);
+                    return Err(incident);
+                }
+                incidents.push(incident);
+            }
+        }
+        self.run_report(frame, fired_total, started, StopReason::Finished);
+        Ok(incidents)
+    }
+
+    /// run simulation until condition is true with the fallible handler.
+    /// return the incidents which are skipped by the policy, or the incident which abort the simulation.
+    pub fn try_run_until<R: Rng + ?Sized, F, H, Err>(
+        &mut self,
+        rng: &mut R,
+        can_continue: F,
+        policy: OnStepError<Err, E>,
+        handler: H,
+    ) -> Result<Vec<StepIncident<Err, E>>, StepIncident<Err, E>>
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        self.try_run_until_buffered(rng, can_continue, policy, owned_events(handler))
+    }
+
+    /// same as try_run_until, but the handler take the buffer of the fired events.
+    pub fn try_run_until_buffered<R: Rng + ?Sized, F, H, Err>(
+        &mut self,
+        rng: &mut R,
+        mut can_continue: F,
+        mut policy: OnStepError<Err, E>,
+        mut handler: H,
+    ) -> Result<Vec<StepIncident<Err, E>>, StepIncident<Err, E>>
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(
+            &mut R,
+            &mut M,
+            &mut Rec,
+            &mut EventScheduler<E>,
+            &mut Vec<(Priority, E)>,
+        ) -> Result<(), Err>,
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let mut incidents = vec![];
+        loop {
+            if !can_continue(&self.model, &self.recorder, &self.scheduler, self.frame) {
+                break;
+            }
+
+            if let Err(incident) = self.try_run_step_buffered(rng, &mut handler) {
+                if !policy.can_continue(&incident) {
+                    self.run_report(frame, fired_total, started, StopReason::StepFailed);
+                    return Err(incident);
+                }
+                incidents.push(incident);
+            }
+        }
+        self.run_report(frame, fired_total, started, StopReason::ConditionMet);
+        Ok(incidents)
+    }
+
+    /// count of the fired events from the start
+    pub fn fired_total(&self) -> u64 {
+        self.fired_total
+    }
+
+    /// summary of the run which started at the frame with the count of the fired events.
+    /// the recorder is finalized with the summary if enabled.
+    fn run_report(
+        &mut self,
+        frame: FrameIndex,
+        fired_total: u64,
+        started: Instant,
+        stop_reason: StopReason,
+    ) -> SimulationReport {
+        let report = SimulationReport {
+            frames_run: self.frame - frame,
+            total_events_fired: self.fired_total - fired_total,
+            events_remaining: self.scheduler.count() + self.scheduler.pending_count(),
+            wall_time: started.elapsed(),
+            stop_reason,
+        };
+        if let Some(finalizer) = self.finalizer {
+            finalizer(&mut self.recorder, &report);
+        }
+        report
+    }
+
+    /// run simulate for frames
+    pub fn run_n<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_n_buffered(rng, counter, owned_events(handler))
+    }
+
+    /// same as run_n, but the handler take the buffer of the fired events.
+    pub fn run_n_buffered<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let mut index = FC::start_index();
+        loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+        }
+        self.run_report(frame, fired_total, started, StopReason::Finished)
+    }
+
+    /// run simulate for frames with calling the progress function every report_every frames and after the last frame.
+    /// report_every 0 is treated as 1.
+    pub fn run_n_with_progress<R: Rng + ?Sized, FC: FrameCounter, P, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        report_every: FrameIndex,
+        progress_fn: P,
+        handler: H,
+    ) -> SimulationReport
+    where
+        P: FnMut(&Progress),
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_n_with_progress_buffered(
+            rng,
+            counter,
+            report_every,
+            progress_fn,
+            owned_events(handler),
+        )
+    }
+
+    /// same as run_n_with_progress, but the handler take the buffer of the fired events.
+    pub fn run_n_with_progress_buffered<R: Rng + ?Sized, FC: FrameCounter, P, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        report_every: FrameIndex,
+        mut progress_fn: P,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        P: FnMut(&Progress),
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total) = (self.frame, self.fired_total);
+        let report_every = report_every.max(1);
+        let started = Instant::now();
+        let mut completed: FrameIndex = 0;
+        let mut index = FC::start_index();
+        loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+            completed += 1;
+
+            let remaining = index.remaining(&counter);
+            if completed % report_every == 0 || remaining == Some(0) {
+                // the fired total count the batched events which the handler does not take
+                progress_fn(&Progress::new(
+                    completed,
+                    remaining,
+                    self.fired_total - fired_total,
+                    started.elapsed(),
+                ));
+            }
+        }
+        self.run_report(frame, fired_total, started, StopReason::Finished)
+    }
+
+    /// run simulate for the frames whose absolute index is in start..end.
+    /// the start must be the next frame of the current frame. if not, then no frame is simulated
+    /// and the stop reason of the report is StopReason::StartMismatch.
+    pub fn run_n_from<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        start: FrameIndex,
+        end: FrameIndex,
+        handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_n_from_buffered(rng, start, end, owned_events(handler))
+    }
+
+    /// same as run_n_from, but the handler take the buffer of the fired events.
+    pub fn run_n_from_buffered<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        start: FrameIndex,
+        end: FrameIndex,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        if self.frame.checked_add(1) != Some(start) {
+            return self.run_report(frame, fired_total, started, StopReason::StartMismatch);
+        }
+        while self.frame.checked_add(1).map_or(false, |next| next < end) {
+            self.run_step_buffered(rng, &mut handler);
+        }
+        self.run_report(frame, fired_total, started, StopReason::Finished)
+    }
+
+    /// run simulate for more frames continuing from the current frame
+    pub fn run_more<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        n: FrameIndex,
+        handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_more_buffered(rng, n, owned_events(handler))
+    }
+
+    /// same as run_more, but the handler take the buffer of the fired events.
+    pub fn run_more_buffered<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        n: FrameIndex,
+        handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let start = self.frame.saturating_add(1);
+        self.run_n_from_buffered(rng, start, start.saturating_add(n), handler)
+    }
+
+    /// run simulation until condition is true
+    pub fn run_until<R: Rng + ?Sized, F, H>(
+        &mut self,
+        rng: &mut R,
+        can_continue: F,
+        handler: H,
+    ) -> SimulationReport
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_until_buffered(rng, can_continue, owned_events(handler))
+    }
+
+    /// same as run_until, but the handler take the buffer of the fired events.
+    pub fn run_until_buffered<R: Rng + ?Sized, F, H>(
+        &mut self,
+        rng: &mut R,
+        mut can_continue: F,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        loop {
+            if !can_continue(&self.model, &self.recorder, &self.scheduler, self.frame) {
+                break;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+        }
+        self.run_report(frame, fired_total, started, StopReason::ConditionMet)
+    }
+
+    /// run simulation with update model's state
+    pub fn run_with_state<R: Rng + ?Sized, F, P, H>(
+        &mut self,
+        rng: &mut R,
+        update_state: F,
+        can_continue: P,
+        handler: H,
+    ) -> SimulationReport
+    where
+        F: FnMut(&mut M, &Rec, FrameIndex),
+        P: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_with_state_buffered(rng, update_state, can_continue, owned_events(handler))
+    }
+
+    /// same as run_with_state, but the handler take the buffer of the fired events.
+    pub fn run_with_state_buffered<R: Rng + ?Sized, F, P, H>(
+        &mut self,
+        rng: &mut R,
+        mut update_state: F,
+        mut can_continue: P,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        F: FnMut(&mut M, &Rec, FrameIndex),
+        P: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        loop {
+            update_state(&mut self.model, &self.recorder, self.frame);
+            if !can_continue(&self.model, &self.recorder, &self.scheduler, self.frame) {
+                break;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+        }
+        self.run_report(frame, fired_total, started, StopReason::ConditionMet)
+    }
+
+    /// run simulation while the stop condition is not satisfied
+    pub fn run_while<R: Rng + ?Sized, C, H>(
+        &mut self,
+        rng: &mut R,
+        condition: C,
+        handler: H,
+    ) -> SimulationReport
+    where
+        C: StopCondition<M, E, Rec>,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_while_buffered(rng, condition, owned_events(handler))
+    }
+
+    /// same as run_while, but the handler take the buffer of the fired events.
+    pub fn run_while_buffered<R: Rng + ?Sized, C, H>(
+        &mut self,
+        rng: &mut R,
+        mut condition: C,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        C: StopCondition<M, E, Rec>,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        loop {
+            let ctx = StopContext {
+                model: &self.model,
+                recorder: &self.recorder,
+                scheduler: &self.scheduler,
+                frame: self.frame,
+            };
+            if condition.should_stop(&ctx) {
+                break;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+        }
+        self.run_report(frame, fired_total, started, StopReason::ConditionMet)
+    }
+
+    /// run simulate for frames with pacing each frame against the wall clock
+    pub fn run_realtime<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        pacing: Pacing,
+        handler: H,
+    ) -> (SimulationReport, RealtimeReport)
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_realtime_buffered(rng, counter, pacing, owned_events(handler))
+    }
+
+    /// same as run_realtime, but the handler take the buffer of the fired events.
+    pub fn run_realtime_buffered<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        pacing: Pacing,
+        mut handler: H,
+    ) -> (SimulationReport, RealtimeReport)
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let mut pacer = Pacer::new(pacing);
+        let mut index = FC::start_index();
+        loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break;
+            }
+
+            pacer.wait_frame();
+            self.run_step_buffered(rng, &mut handler);
+        }
+        let report = self.run_report(frame, fired_total, started, StopReason::Finished);
+        (report, pacer.finish())
+    }
+
+    /// run simulate for frames with checking the control token before each frame.
+    /// when the run is aborted, the frames which are simulated until then are kept in the simulator.
+    pub fn run_controlled<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        token: &ControlToken,
+        handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_controlled_buffered(rng, counter, token, owned_events(handler))
+    }
+
+    /// same as run_controlled, but the handler take the buffer of the fired events.
+    pub fn run_controlled_buffered<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        token: &ControlToken,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let mut index = FC::start_index();
+        let stop_reason = loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break StopReason::Finished;
+            }
+            if !token.wait() {
+                break StopReason::Aborted;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+        };
+        self.run_report(frame, fired_total, started, stop_reason)
+    }
+
+    /// run simulate for frames until the deadline is passed.
+    /// the deadline is checked before each frame, so the frames which are simulated until then are kept in the simulator.
+    pub fn run_with_deadline<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        deadline: Instant,
+        handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_with_deadline_buffered(rng, counter, deadline, owned_events(handler))
+    }
+
+    /// same as run_with_deadline, but the handler take the buffer of the fired events.
+    pub fn run_with_deadline_buffered<R: Rng + ?Sized, FC: FrameCounter, H>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+        deadline: Instant,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let mut index = FC::start_index();
+        let stop_reason = loop {
+            if !index.try_next_index() || !index.can_continue(&counter) {
+                break StopReason::Finished;
+            }
+            if Instant::now() >= deadline {
+                break StopReason::DeadlineExceeded;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+        };
+        self.run_report(frame, fired_total, started, stop_reason)
+    }
+
+    /// run simulate for total frames with the warm-up period at the start.
+    /// the warm-up frames are contained in the total frames.
+    /// if the recorder is swapped, then return the recorder which is used in the warm-up period.
+    pub fn run_with_warmup<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        warmup_frames: FrameIndex,
+        total_frames: FrameIndex,
+        warmup: Warmup<Rec>,
+        handler: H,
+    ) -> (SimulationReport, Option<Rec>)
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_with_warmup_buffered(
+            rng,
+            warmup_frames,
+            total_frames,
+            warmup,
+            owned_events(handler),
+        )
+    }
+
+    /// same as run_with_warmup, but the handler take the buffer of the fired events.
+    pub fn run_with_warmup_buffered<R: Rng + ?Sized, H>(
+        &mut self,
+        rng: &mut R,
+        warmup_frames: FrameIndex,
+        total_frames: FrameIndex,
+        warmup: Warmup<Rec>,
+        mut handler: H,
+    ) -> (SimulationReport, Option<Rec>)
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        let warmup_frames = warmup_frames.min(total_frames);
+        for _ in 0..warmup_frames {
+            self.run_step_buffered(rng, &mut handler);
+        }
+
+        let warmup_recorder = match warmup {
+            Warmup::SwapRecorder(recorder) => Some(self.swap_recorder(recorder)),
+            Warmup::Notify => {
+                self.model.on_warmup_complete(&mut self.recorder);
+                None
+            }
+        };
+
+        for _ in warmup_frames..total_frames {
+            self.run_step_buffered(rng, &mut handler);
+        }
+        let report = self.run_report(frame, fired_total, started, StopReason::Finished);
+        (report, warmup_recorder)
+    }
+
+    /// iterator which simulate one frame for each step and report the frame
+    #[allow(clippy::type_complexity)]
+    pub fn frames<'a, R: Rng + ?Sized, H>(
+        &'a mut self,
+        rng: &'a mut R,
+        handler: H,
+    ) -> Frames<
+        'a,
+        M,
+        E,
+        Rec,
+        R,
+        impl FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    >
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.frames_buffered(rng, owned_events(handler))
+    }
+
+    /// same as frames, but the handler take the buffer of the fired events.
+    pub fn frames_buffered<'a, R: Rng + ?Sized, H>(
+        &'a mut self,
+        rng: &'a mut R,
+        handler: H,
+    ) -> Frames<'a, M, E, Rec, R, H>
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        Frames::new(self, rng, handler)
+    }
+
+    /// stream which simulate one frame for each polled item and report the frame
+    #[cfg(feature = "async")]
+    #[allow(clippy::type_complexity)]
+    pub fn run_stream<'a, R: Rng + ?Sized, H>(
+        &'a mut self,
+        rng: &'a mut R,
+        handler: H,
+    ) -> FrameStream<
+        'a,
+        M,
+        E,
+        Rec,
+        R,
+        impl FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>) + Unpin,
+    >
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>) + Unpin,
+    {
+        self.run_stream_buffered(rng, owned_events(handler))
+    }
+
+    /// same as run_stream, but the handler take the buffer of the fired events.
+    #[cfg(feature = "async")]
+    pub fn run_stream_buffered<'a, R: Rng + ?Sized, H>(
+        &'a mut self,
+        rng: &'a mut R,
+        handler: H,
+    ) -> FrameStream<'a, M, E, Rec, R, H>
+    where
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>) + Unpin,
+    {
+        FrameStream::new(self.frames_buffered(rng, handler))
+    }
+
+    /// run simulation until asynchronous condition is true with yield to the runtime between frames.
+    /// the condition get the same arguments as run_until, and the future must not borrow them.
+    /// the yield does not depend on the runtime, so the run can be awaited on tokio, async-std and so on.
+    #[cfg(feature = "async")]
+    pub async fn run_until_async<R: Rng + ?Sized, F, Fut, H>(
+        &mut self,
+        rng: &mut R,
+        can_continue: F,
+        handler: H,
+    ) -> SimulationReport
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> Fut,
+        Fut: Future<Output = bool>,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_until_async_buffered(rng, can_continue, owned_events(handler))
+            .await
+    }
+
+    /// same as run_until_async, but the handler take the buffer of the fired events.
+    #[cfg(feature = "async")]
+    pub async fn run_until_async_buffered<R: Rng + ?Sized, F, Fut, H>(
+        &mut self,
+        rng: &mut R,
+        mut can_continue: F,
+        mut handler: H,
+    ) -> SimulationReport
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> Fut,
+        Fut: Future<Output = bool>,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        let (frame, fired_total, started) = (self.frame, self.fired_total, Instant::now());
+        loop {
+            if !can_continue(&self.model, &self.recorder, &self.scheduler, self.frame).await {
+                break;
+            }
+
+            self.run_step_buffered(rng, &mut handler);
+            YieldNow::default().await;
+        }
+        self.run_report(frame, fired_total, started, StopReason::ConditionMet)
+    }
+}
+
+/// handler which take the fired events by value as the handler which take the buffer of the fired events.
+/// the buffer keeps its capacity, and the handler get the fired events in a new vector.
+#[allow(clippy::type_complexity)]
+pub(crate) fn owned_events<R, M, Rec, E, T, H>(
+    mut handler: H,
+) -> impl FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>) -> T
+where
+    R: ?Sized,
+    E: Event,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>) -> T,
+{
+    move |rng, model, recorder, scheduler, events| {
+        let mut owned = Vec::with_capacity(events.len());
+        owned.append(events);
+        handler(rng, model, recorder, scheduler, owned)
+    }
+}
+
+// TODO If concat_idents macro is to be stable, then replace $suffix:ident and concat_idents!.
+macro_rules! impl_base_set {
+    ($handler:ident, [$run_step:ident,$run_n:ident,$run_n_with_progress:ident,$run_n_from:ident,$run_more:ident,$run_until:ident,$run_with_state:ident,$run_while:ident,$run_realtime:ident,$run_controlled:ident,$run_with_deadline:ident,$run_with_warmup:ident,$frames:ident,$run_stream:ident,$run_until_async:ident]) => {
+        /// run simulate for one frame
+        pub fn $run_step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> FrameOutcome {
+            self.run_step_buffered(rng, Self::$handler)
+        }
+
+        /// run simulate for frames
+        pub fn $run_n<R: Rng + ?Sized, FC: FrameCounter>(
+            &mut self,
+            rng: &mut R,
+            counter: FC,
+        ) -> SimulationReport {
+            self.run_n_buffered(rng, counter, Self::$handler)
+        }
+
+        /// run simulate for frames with calling the progress function every report_every frames
+        pub fn $run_n_with_progress<R: Rng + ?Sized, FC: FrameCounter, P>(
+            &mut self,
+            rng: &mut R,
+            counter: FC,
+            report_every: FrameIndex,
+            progress_fn: P,
+        ) -> SimulationReport
+        where
+            P: FnMut(&Progress),
+        {
+            self.run_n_with_progress_buffered(
+                rng,
+                counter,
+                report_every,
+                progress_fn,
+                Self::$handler,
+            )
+        }
+
+        /// run simulate for the frames whose absolute index is in start..end.
+        /// if the start is not the next frame, then no frame is simulated.
+        pub fn $run_n_from<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            start: FrameIndex,
+            end: FrameIndex,
+        ) -> SimulationReport {
+            self.run_n_from_buffered(rng, start, end, Self::$handler)
+        }
+
+        /// run simulate for more frames continuing from the current frame
+        pub fn $run_more<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            n: FrameIndex,
+        ) -> SimulationReport {
+            self.run_more_buffered(rng, n, Self::$handler)
+        }
+
+        /// run simulation until condition is true
+        pub fn $run_until<R: Rng + ?Sized, F>(
+            &mut self,
+            rng: &mut R,
+            can_continue: F,
+        ) -> SimulationReport
+        where
+            F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        {
+            self.run_until_buffered(rng, can_continue, Self::$handler)
+        }
+
+        /// run simulation with update model's state
+        pub fn $run_with_state<R: Rng + ?Sized, F, P>(
+            &mut self,
+            rng: &mut R,
+            update_state: F,
+            can_continue: P,
+        ) -> SimulationReport
+        where
+            F: FnMut(&mut M, &Rec, FrameIndex),
+            P: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        {
+            self.run_with_state_buffered(rng, update_state, can_continue, Self::$handler)
+        }
+
+        /// run simulation while the stop condition is not satisfied
+        pub fn $run_while<R: Rng + ?Sized, C>(
+            &mut self,
+            rng: &mut R,
+            condition: C,
+        ) -> SimulationReport
+        where
+            C: StopCondition<M, E, Rec>,
+        {
+            self.run_while_buffered(rng, condition, Self::$handler)
+        }
+
+        /// run simulate for frames with pacing each frame against the wall clock
+        pub fn $run_realtime<R: Rng + ?Sized, FC: FrameCounter>(
+            &mut self,
+            rng: &mut R,
+            counter: FC,
+            pacing: Pacing,
+        ) -> (SimulationReport, RealtimeReport) {
+            self.run_realtime_buffered(rng, counter, pacing, Self::$handler)
+        }
+
+        /// run simulate for frames with checking the control token before each frame
+        pub fn $run_controlled<R: Rng + ?Sized, FC: FrameCounter>(
+            &mut self,
+            rng: &mut R,
+            counter: FC,
+            token: &ControlToken,
+        ) -> SimulationReport {
+            self.run_controlled_buffered(rng, counter, token, Self::$handler)
+        }
+
+        /// run simulate for frames until the deadline is passed
+        pub fn $run_with_deadline<R: Rng + ?Sized, FC: FrameCounter>(
+            &mut self,
+            rng: &mut R,
+            counter: FC,
+            deadline: Instant,
+        ) -> SimulationReport {
+            self.run_with_deadline_buffered(rng, counter, deadline, Self::$handler)
+        }
+
+        /// run simulate for total frames with the warm-up period at the start
+        pub fn $run_with_warmup<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            warmup_frames: FrameIndex,
+            total_frames: FrameIndex,
+            warmup: Warmup<Rec>,
+        ) -> (SimulationReport, Option<Rec>) {
+            self.run_with_warmup_buffered(rng, warmup_frames, total_frames, warmup, Self::$handler)
+        }
+
+        /// iterator which simulate one frame for each step and report the frame
+        pub fn $frames<'a, R: Rng + ?Sized>(
+            &'a mut self,
+            rng: &'a mut R,
+        ) -> Frames<'a, M, E, Rec, R, FrameHandler<R, M, Rec, E>> {
+            self.frames_buffered(rng, Self::$handler as FrameHandler<R, M, Rec, E>)
+        }
+
+        /// stream which simulate one frame for each polled item and report the frame
+        #[cfg(feature = "async")]
+        pub fn $run_stream<'a, R: Rng + ?Sized>(
+            &'a mut self,
+            rng: &'a mut R,
+        ) -> FrameStream<'a, M, E, Rec, R, FrameHandler<R, M, Rec, E>> {
+            self.run_stream_buffered(rng, Self::$handler as FrameHandler<R, M, Rec, E>)
+        }
+
+        /// run simulation until asynchronous condition is true with yield to the runtime between frames
+        #[cfg(feature = "async")]
+        pub async fn $run_until_async<R: Rng + ?Sized, F, Fut>(
+            &mut self,
+            rng: &mut R,
+            can_continue: F,
+        ) -> SimulationReport
+        where
+            F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> Fut,
+            Fut: Future<Output = bool>,
         {
-            loop {
-                update_state(&mut self.model);
-                if !can_continue(&self.model) {
-                    break;
-                }
-                self.$run_step(rng);
-            }
+            self.run_until_async_buffered(rng, can_continue, Self::$handler)
+                .await
         }
     };
 }
@@ -392,12 +2014,13 @@ where
     E: Event,
 {
     fn handler_in_bulk_event<R: Rng + ?Sized>(
-        &mut self,
         rng: &mut R,
-        fired_events: Vec<(Priority, E)>,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
     ) {
-        self.model
-            .step_in_bulk(rng, &mut self.recorder, &mut self.scheduler, fired_events);
+        model.step_in_bulk_buffer(rng, recorder, scheduler, fired_events);
     }
 
     impl_base_set!(
@@ -405,8 +2028,19 @@ where
         [
             run_step_in_bulk_event,
             run_n_in_bulk_event,
+            run_n_with_progress_in_bulk_event,
+            run_n_from_in_bulk_event,
+            run_more_in_bulk_event,
             run_until_in_bulk_event,
-            run_with_state_in_bulk_event
+            run_with_state_in_bulk_event,
+            run_while_in_bulk_event,
+            run_realtime_in_bulk_event,
+            run_controlled_in_bulk_event,
+            run_with_deadline_in_bulk_event,
+            run_with_warmup_in_bulk_event,
+            frames_in_bulk_event,
+            run_stream_in_bulk_event,
+            run_until_async_in_bulk_event
         ]
     );
 }
@@ -418,13 +2052,15 @@ where
     E: Event,
 {
     fn handler_each_event<R: Rng + ?Sized>(
-        &mut self,
         rng: &mut R,
-        fired_events: Vec<(Priority, E)>,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
     ) {
-        for (p, e) in fired_events.into_iter() {
-            self.model
-                .step_each_event(rng, &mut self.recorder, &mut self.scheduler, p, e);
+        for (index, (p, e)) in fired_events.drain(..).enumerate() {
+            scheduler.handle_fired(index);
+            model.step_each_event(rng, recorder, scheduler, p, e);
         }
     }
 
@@ -433,8 +2069,875 @@ where
         [
             run_step_each_event,
             run_n_each_event,
+            run_n_with_progress_each_event,
+            run_n_from_each_event,
+            run_more_each_event,
             run_until_each_event,
-            run_with_state_each_event
+            run_with_state_each_event,
+            run_while_each_event,
+            run_realtime_each_event,
+            run_controlled_each_event,
+            run_with_deadline_each_event,
+            run_with_warmup_each_event,
+            frames_each_event,
+            run_stream_each_event,
+            run_until_async_each_event
+        ]
+    );
+}
+
+/// simulate for fired event with calculate each group of the same priority
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: StepByPriorityGroup<Rec, E>,
+    E: Event,
+{
+    fn handler_by_priority<R: Rng + ?Sized>(
+        rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        // highest priority group first with keeping the fired order in the group
+        fired_events.sort_by(|(p1, _), (p2, _)| p2.cmp(p1));
+        let mut group: Option<(Priority, Vec<E>)> = None;
+        for (p, e) in fired_events.drain(..) {
+            match group.as_mut() {
+                Some((priority, events)) if *priority == p => events.push(e),
+                _ => {
+                    if let Some((priority, events)) = group.replace((p, vec![e])) {
+                        model.step_priority_group(rng, recorder, scheduler, priority, events);
+                    }
+                }
+            }
+        }
+        if let Some((priority, events)) = group {
+            model.step_priority_group(rng, recorder, scheduler, priority, events);
+        }
+    }
+
+    impl_base_set!(
+        handler_by_priority,
+        [
+            run_step_by_priority,
+            run_n_by_priority,
+            run_n_with_progress_by_priority,
+            run_n_from_by_priority,
+            run_more_by_priority,
+            run_until_by_priority,
+            run_with_state_by_priority,
+            run_while_by_priority,
+            run_realtime_by_priority,
+            run_controlled_by_priority,
+            run_with_deadline_by_priority,
+            run_with_warmup_by_priority,
+            frames_by_priority,
+            run_stream_by_priority,
+            run_until_async_by_priority
+        ]
+    );
+}
+
+/// simulate for fired event with calculate phase by phase
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: StepByPhase<Rec, E>,
+    E: Event,
+{
+    fn handler_phased<R: Rng + ?Sized>(
+        rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        let mut phases = model.phases();
+        if phases.is_empty() {
+            phases.push(Phase::all("step"));
+        }
+        let last = phases.len() - 1;
+        for (index, phase) in phases.iter().enumerate() {
+            // the events which are not in any phase remain until the last phase
+            let events: Vec<(Priority, E)> = if index == last {
+                fired_events.split_off(0)
+            } else {
+                extract_matching(fired_events, |(priority, _)| phase.contains(*priority))
+            };
+            if !events.is_empty() {
+                model.step_phase(rng, recorder, scheduler, phase, events);
+            }
+        }
+    }
+
+    impl_base_set!(
+        handler_phased,
+        [
+            run_step_phased,
+            run_n_phased,
+            run_n_with_progress_phased,
+            run_n_from_phased,
+            run_more_phased,
+            run_until_phased,
+            run_with_state_phased,
+            run_while_phased,
+            run_realtime_phased,
+            run_controlled_phased,
+            run_with_deadline_phased,
+            run_with_warmup_phased,
+            frames_phased,
+            run_stream_phased,
+            run_until_async_phased
+        ]
+    );
+}
+
+/// simulate for fired event with calculate each event or in bulk by the class of the event
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: MixedStep<Rec, E>,
+    E: EventClass,
+{
+    fn handler_mixed<R: Rng + ?Sized>(
+        rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        let mut bulk_events = vec![];
+        for (p, e) in fired_events.drain(..) {
+            match e.delivery() {
+                Delivery::Each => model.step_individual(rng, recorder, scheduler, p, e),
+                Delivery::Bulk => bulk_events.push((p, e)),
+            }
+        }
+        if !bulk_events.is_empty() {
+            model.step_batch(rng, recorder, scheduler, bulk_events);
+        }
+    }
+
+    impl_base_set!(
+        handler_mixed,
+        [
+            run_step_mixed,
+            run_n_mixed,
+            run_n_with_progress_mixed,
+            run_n_from_mixed,
+            run_more_mixed,
+            run_until_mixed,
+            run_with_state_mixed,
+            run_while_mixed,
+            run_realtime_mixed,
+            run_controlled_mixed,
+            run_with_deadline_mixed,
+            run_with_warmup_mixed,
+            frames_mixed,
+            run_stream_mixed,
+            run_until_async_mixed
+        ]
+    );
+}
+
+/// simulate for the closure events by calling the callbacks in fired order
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: CallbackEvent<M, Rec>,
+{
+    fn handler_closure<R: Rng + ?Sized>(
+        mut rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        for (_, event) in fired_events.drain(..) {
+            event.call(&mut rng, model, recorder, scheduler);
+        }
+    }
+
+    impl_base_set!(
+        handler_closure,
+        [
+            run_step_closure,
+            run_n_closure,
+            run_n_with_progress_closure,
+            run_n_from_closure,
+            run_more_closure,
+            run_until_closure,
+            run_with_state_closure,
+            run_while_closure,
+            run_realtime_closure,
+            run_controlled_closure,
+            run_with_deadline_closure,
+            run_with_warmup_closure,
+            frames_closure,
+            run_stream_closure,
+            run_until_async_closure
+        ]
+    );
+}
+
+/// simulate for the disjoint parts of the model in parallel
+#[cfg(feature = "rayon")]
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: ParStepEachEvent<Rec, E>,
+    E: Event + Clone + Send,
+{
+    fn handler_par_each_event<R: Rng + ?Sized>(
+        rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) {
+        // the fired order is kept to step the shared events in order
+        let mut groups: BTreeMap<M::Key, Vec<(usize, Priority, E)>> = BTreeMap::new();
+        let mut shared: Vec<(usize, Priority, E)> = vec![];
+        for (index, (priority, event)) in fired_events.drain(..).enumerate() {
+            match model.partition_key(priority, &event) {
+                Some(key) => groups
+                    .entry(key)
+                    .or_default()
+                    .push((index, priority, event)),
+                None => shared.push((index, priority, event)),
+            }
+        }
+
+        if !groups.is_empty() {
+            let mut parts: BTreeMap<M::Key, &mut M::Part> = model.parts_mut().into_iter().collect();
+            let mut jobs = Vec::with_capacity(groups.len());
+            for (key, events) in groups {
+                match parts.remove(&key) {
+                    Some(part) => {
+                        let seed: u64 = rng.gen();
+                        jobs.push((key, part, events, seed));
+                    }
+                    None => shared.extend(events),
+                }
+            }
+            let requests: Vec<ScheduleRequests<E>> = jobs
+                .into_par_iter()
+                .map(|(key, part, events, seed)| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let mut requests = ScheduleRequests::new();
+                    for (_, priority, event) in events {
+                        M::step_part(&key, part, &mut rng, &mut requests, priority, event);
+                    }
+                    requests
+                })
+                .collect();
+            for requests in requests {
+                // ScheduleRequests accept only the validated schedules and E is Clone,
+                // so the scheduler has no reason to reject them
+                requests
+                    .schedule_into(rng, scheduler)
+                    .expect("the requests of ScheduleRequests are validated when requested");
+            }
+        }
+
+        shared.sort_by_key(|(index, _, _)| *index);
+        for (_, priority, event) in shared {
+            model.step_shared(rng, recorder, scheduler, priority, event);
+        }
+    }
+
+    impl_base_set!(
+        handler_par_each_event,
+        [
+            run_step_par_each_event,
+            run_n_par_each_event,
+            run_n_with_progress_par_each_event,
+            run_n_from_par_each_event,
+            run_more_par_each_event,
+            run_until_par_each_event,
+            run_with_state_par_each_event,
+            run_while_par_each_event,
+            run_realtime_par_each_event,
+            run_controlled_par_each_event,
+            run_with_deadline_par_each_event,
+            run_with_warmup_par_each_event,
+            frames_par_each_event,
+            run_stream_par_each_event,
+            run_until_async_par_each_event
+        ]
+    );
+}
+
+/// simulate for the type-erased event with the handlers registered by the type
+impl<M, Rec> Simulator<M, DynEvent, Rec>
+where
+    M: Model<Rec, ModelEvent = DynEvent>,
+{
+    /// register the handler for the fired event of the type.
+    /// the fired event of the type which is not registered is dropped.
+    pub fn register_handler<T, F>(&mut self, handler: F)
+    where
+        T: Any,
+        F: Fn(&mut dyn RngCore, &mut M, &mut Rec, &mut EventScheduler<DynEvent>, Priority, T)
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.dispatcher.register(handler);
+    }
+
+    /// getter for the dispatcher
+    pub fn get_dispatcher(&self) -> &Dispatcher<M, Rec> {
+        &self.dispatcher
+    }
+
+    /// run simulate for one frame with dispatching the fired events
+    pub fn run_step_dispatched<R: Rng + ?Sized>(&mut self, rng: &mut R) -> FrameOutcome {
+        let mut dispatcher = mem::take(&mut self.dispatcher);
+        let outcome = self.run_step_buffered(rng, |rng, model, recorder, scheduler, events| {
+            dispatcher.dispatch(rng, model, recorder, scheduler, events)
+        });
+        self.dispatcher = dispatcher;
+        outcome
+    }
+
+    /// run simulate for frames with dispatching the fired events
+    pub fn run_n_dispatched<R: Rng + ?Sized, FC: FrameCounter>(
+        &mut self,
+        rng: &mut R,
+        counter: FC,
+    ) -> SimulationReport {
+        let mut dispatcher = mem::take(&mut self.dispatcher);
+        let report =
+            self.run_n_buffered(rng, counter, |rng, model, recorder, scheduler, events| {
+                dispatcher.dispatch(rng, model, recorder, scheduler, events)
+            });
+        self.dispatcher = dispatcher;
+        report
+    }
+
+    /// run simulation until condition is true with dispatching the fired events
+    pub fn run_until_dispatched<R: Rng + ?Sized, F>(
+        &mut self,
+        rng: &mut R,
+        can_continue: F,
+    ) -> SimulationReport
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<DynEvent>, FrameIndex) -> bool,
+    {
+        let mut dispatcher = mem::take(&mut self.dispatcher);
+        let report = self.run_until_buffered(
+            rng,
+            can_continue,
+            |rng, model, recorder, scheduler, events| {
+                dispatcher.dispatch(rng, model, recorder, scheduler, events)
+            },
+        );
+        self.dispatcher = dispatcher;
+        report
+    }
+}
+
+macro_rules! impl_try_set {
+    ($handler:ident, $err:ty, [$try_run_step:ident,$try_run_n:ident,$try_run_until:ident]) => {
+        /// run simulate for one frame with the fallible step
+        pub fn $try_run_step<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+        ) -> Result<(), StepIncident<$err, E>> {
+            self.try_run_step_buffered(rng, Self::$handler)
+        }
+
+        /// run simulate for frames with the fallible step
+        pub fn $try_run_n<R: Rng + ?Sized, FC: FrameCounter>(
+            &mut self,
+            rng: &mut R,
+            counter: FC,
+            policy: OnStepError<$err, E>,
+        ) -> Result<Vec<StepIncident<$err, E>>, StepIncident<$err, E>> {
+            self.try_run_n_buffered(rng, counter, policy, Self::$handler)
+        }
+
+        /// run simulation until condition is true with the fallible step
+        pub fn $try_run_until<R: Rng + ?Sized, F>(
+            &mut self,
+            rng: &mut R,
+            can_continue: F,
+            policy: OnStepError<$err, E>,
+        ) -> Result<Vec<StepIncident<$err, E>>, StepIncident<$err, E>>
+        where
+            F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        {
+            self.try_run_until_buffered(rng, can_continue, policy, Self::$handler)
+        }
+    };
+}
+
+/// simulate for fired event with calculate in bulk which may fail
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: TryBulkEvents<Rec, E>,
+    E: Event,
+{
+    fn handler_try_in_bulk_event<R: Rng + ?Sized>(
+        rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) -> Result<(), M::Error> {
+        model.try_step_in_bulk_buffer(rng, recorder, scheduler, fired_events)
+    }
+
+    impl_try_set!(
+        handler_try_in_bulk_event,
+        M::Error,
+        [
+            try_run_step_in_bulk_event,
+            try_run_n_in_bulk_event,
+            try_run_until_in_bulk_event
+        ]
+    );
+}
+
+/// simulate for fired event with calculate each event which may fail
+impl<M, E, Rec> Simulator<M, E, Rec>
+where
+    M: TryStepEachEvent<Rec, E>,
+    E: Event,
+{
+    fn handler_try_each_event<R: Rng + ?Sized>(
+        rng: &mut R,
+        model: &mut M,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) -> Result<(), M::Error> {
+        // the events after the error are left in the buffer to be reported as skipped
+        let mut result = Ok(());
+        let mut skipped = vec![];
+        for (index, (p, e)) in fired_events.drain(..).enumerate() {
+            if result.is_err() {
+                skipped.push((p, e));
+                continue;
+            }
+            scheduler.handle_fired(index);
+            result = model.try_step_each_event(rng, recorder, scheduler, p, e);
+        }
+        fired_events.append(&mut skipped);
+        result
+    }
+
+    impl_try_set!(
+        handler_try_each_event,
+        M::Error,
+        [
+            try_run_step_each_event,
+            try_run_n_each_event,
+            try_run_until_each_event
         ]
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::SimulationController;
+    use crate::event::Schedule;
+    use crate::stop::MaxFrames;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Ev {
+        Tick,
+        Tock,
+    }
+
+    impl Event for Ev {}
+
+    /// model which count the ticks and the tocks fired in every frame
+    #[derive(Debug, Clone, Default)]
+    struct Counter {
+        ticks: u64,
+        tocks: u64,
+        fail_at: Option<u64>,
+        warmed_up: bool,
+    }
+
+    impl Counter {
+        fn count(&mut self, recorder: &mut Vec<Ev>, event: Ev) {
+            match event {
+                Ev::Tick => self.ticks += 1,
+                Ev::Tock => self.tocks += 1,
+            }
+            recorder.push(event);
+        }
+    }
+
+    impl Model<Vec<Ev>> for Counter {
+        type ModelEvent = Ev;
+
+        fn initialize<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            _: &mut Vec<Ev>,
+            scheduler: &mut EventScheduler<Ev>,
+        ) {
+            scheduler.everytime(rng, Priority::LOW, Ev::Tock).unwrap();
+            scheduler
+                .everytime(rng, Priority::NORMAL, Ev::Tick)
+                .unwrap();
+        }
+
+        fn on_warmup_complete(&mut self, _: &mut Vec<Ev>) {
+            self.warmed_up = true;
+        }
+    }
+
+    impl StepEachEvent<Vec<Ev>, Ev> for Counter {
+        fn step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<Ev>,
+            _: &mut EventScheduler<Ev>,
+            _: Priority,
+            fired_event: Ev,
+        ) {
+            self.count(recorder, fired_event);
+        }
+    }
+
+    impl BulkEvents<Vec<Ev>, Ev> for Counter {
+        fn step_in_bulk<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<Ev>,
+            _: &mut EventScheduler<Ev>,
+            fired_events: Vec<(Priority, Ev)>,
+        ) {
+            for (_, event) in fired_events {
+                self.count(recorder, event);
+            }
+        }
+    }
+
+    impl TryStepEachEvent<Vec<Ev>, Ev> for Counter {
+        type Error = u64;
+
+        fn try_step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<Ev>,
+            _: &mut EventScheduler<Ev>,
+            _: Priority,
+            fired_event: Ev,
+        ) -> Result<(), u64> {
+            // fail only once at the tick
+            if fired_event == Ev::Tick && self.fail_at == Some(self.ticks + 1) {
+                self.fail_at = None;
+                return Err(self.ticks + 1);
+            }
+            self.count(recorder, fired_event);
+            Ok(())
+        }
+    }
+
+    impl TryBulkEvents<Vec<Ev>, Ev> for Counter {
+        type Error = u64;
+
+        fn try_step_in_bulk<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            recorder: &mut Vec<Ev>,
+            scheduler: &mut EventScheduler<Ev>,
+            mut fired_events: Vec<(Priority, Ev)>,
+        ) -> Result<(), u64> {
+            self.try_step_in_bulk_buffer(rng, recorder, scheduler, &mut fired_events)
+        }
+
+        fn try_step_in_bulk_buffer<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<Ev>,
+            _: &mut EventScheduler<Ev>,
+            fired_events: &mut Vec<(Priority, Ev)>,
+        ) -> Result<(), u64> {
+            // leave the events after the failed tick in the buffer
+            for index in 0..fired_events.len() {
+                let event = fired_events[index].1;
+                if event == Ev::Tick && self.fail_at == Some(self.ticks + 1) {
+                    self.fail_at = None;
+                    fired_events.drain(..=index);
+                    return Err(self.ticks + 1);
+                }
+                self.count(recorder, event);
+            }
+            fired_events.clear();
+            Ok(())
+        }
+    }
+
+    fn simulator(rng: &mut StdRng, model: Counter) -> Simulator<Counter, Ev, Vec<Ev>> {
+        Simulator::create_from(rng, model, vec![])
+    }
+
+    #[test]
+    fn frames_report_each_frame() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let frames: Vec<(FrameIndex, usize)> = simulator
+            .frames_each_event(&mut rng)
+            .take(3)
+            .map(|frame| (frame.index, frame.fired_events.len()))
+            .collect();
+        assert_eq!(frames, vec![(1, 2), (2, 2), (3, 2)]);
+        assert_eq!(simulator.get_model().ticks, 3);
+    }
+
+    #[test]
+    fn run_with_warmup_swap_recorder_or_notify() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let (report, warmup) =
+            simulator.run_with_warmup_each_event(&mut rng, 2, 5, Warmup::SwapRecorder(vec![]));
+        assert_eq!(report.frames_run, 5);
+        assert_eq!(warmup.map(|recorder| recorder.len()), Some(4));
+        assert_eq!(simulator.get_recorder().len(), 6);
+        assert!(!simulator.get_model().warmed_up);
+
+        let (_, warmup) = simulator.run_with_warmup_each_event(&mut rng, 1, 1, Warmup::Notify);
+        assert_eq!(warmup, None);
+        assert!(simulator.get_model().warmed_up);
+    }
+
+    #[test]
+    fn run_while_stop_by_condition() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let report = simulator.run_while_each_event(&mut rng, MaxFrames::new(4));
+        assert_eq!(report.frames_run, 4);
+        assert_eq!(simulator.frame_index(), 4);
+        assert_eq!(simulator.get_model().ticks, 4);
+    }
+
+    #[test]
+    fn run_more_and_run_n_from_continue_frames() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        simulator.run_n_each_event(&mut rng, 2_u64);
+        let report = simulator.run_more_each_event(&mut rng, 3);
+        assert_eq!(report.frames_run, 3);
+        assert_eq!(simulator.frame_index(), 5);
+
+        // the start which is not the next frame simulate nothing
+        let report = simulator.run_n_from_each_event(&mut rng, 1, 8);
+        assert_eq!(report.frames_run, 0);
+        assert_eq!(report.stop_reason, StopReason::StartMismatch);
+        let report = simulator.run_n_from_each_event(&mut rng, 7, 8);
+        assert_eq!(report.frames_run, 0);
+        assert_eq!(report.stop_reason, StopReason::StartMismatch);
+        let report = simulator.run_n_from_each_event(&mut rng, 6, 8);
+        assert_eq!(report.frames_run, 2);
+        assert_eq!(report.stop_reason, StopReason::Finished);
+        assert_eq!(simulator.get_model().ticks, 7);
+    }
+
+    #[test]
+    fn try_run_n_skip_or_abort_by_policy() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let failing = Counter {
+            fail_at: Some(2),
+            ..Counter::default()
+        };
+
+        let mut skipping = simulator(&mut rng, failing.clone());
+        let incidents = skipping
+            .try_run_n_each_event(&mut rng, 3_u64, OnStepError::SkipFrame)
+            .unwrap();
+        assert_eq!(
+            incidents,
+            vec![StepIncident {
+                frame: 2,
+                error: 2,
+                skipped: vec![(Priority::LOW, Ev::Tock)],
+            }]
+        );
+        assert_eq!(skipping.frame_index(), 3);
+        assert_eq!(skipping.get_model().ticks, 2);
+        assert_eq!(skipping.get_model().tocks, 2);
+
+        let mut aborting = simulator(&mut rng, failing.clone());
+        let incident = aborting
+            .try_run_n_each_event(&mut rng, 3_u64, OnStepError::Abort)
+            .unwrap_err();
+        assert_eq!(incident.frame, 2);
+        assert_eq!(aborting.frame_index(), 2);
+
+        // the events which are left in the buffer of the bulk step are skipped
+        let mut bulk = simulator(&mut rng, failing);
+        let incidents = bulk
+            .try_run_n_in_bulk_event(&mut rng, 3_u64, OnStepError::SkipFrame)
+            .unwrap();
+        assert_eq!(
+            incidents,
+            vec![StepIncident {
+                frame: 2,
+                error: 2,
+                skipped: vec![(Priority::LOW, Ev::Tock)],
+            }]
+        );
+        assert_eq!(bulk.get_model().ticks, 2);
+        assert_eq!(bulk.get_model().tocks, 2);
+    }
+
+    #[test]
+    fn run_until_and_run_with_state_stop_by_condition() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let mut checked = vec![];
+        let report = simulator.run_until_each_event(&mut rng, |model, _, _, frame| {
+            checked.push(frame);
+            model.ticks < 3
+        });
+        assert_eq!(checked, vec![0, 1, 2, 3]);
+        assert_eq!(report.frames_run, 3);
+        assert_eq!(report.stop_reason, StopReason::ConditionMet);
+
+        let mut updated = vec![];
+        let report = simulator.run_with_state_each_event(
+            &mut rng,
+            |model, _, frame| updated.push((frame, model.ticks)),
+            |_, _, _, frame| frame < 5,
+        );
+        assert_eq!(report.frames_run, 2);
+        assert_eq!(updated, vec![(3, 3), (4, 4), (5, 5)]);
+    }
+
+    #[test]
+    fn run_controlled_stop_before_frame() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let controller = SimulationController::new();
+        let report = simulator.run_controlled_each_event(&mut rng, 3_u64, &controller.token());
+        assert_eq!(report.frames_run, 3);
+        assert_eq!(report.stop_reason, StopReason::Finished);
+
+        controller.abort();
+        let report = simulator.run_controlled_each_event(&mut rng, 3_u64, &controller.token());
+        assert_eq!(report.frames_run, 0);
+        assert_eq!(report.stop_reason, StopReason::Aborted);
+        assert_eq!(simulator.frame_index(), 3);
+    }
+
+    #[test]
+    fn run_with_deadline_stop_before_frame() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let report = simulator.run_with_deadline_each_event(&mut rng, 3_u64, deadline);
+        assert_eq!(report.frames_run, 3);
+        assert_eq!(report.stop_reason, StopReason::Finished);
+
+        let report = simulator.run_with_deadline_each_event(&mut rng, 3_u64, Instant::now());
+        assert_eq!(report.frames_run, 0);
+        assert_eq!(report.stop_reason, StopReason::DeadlineExceeded);
+        assert_eq!(simulator.frame_index(), 3);
+    }
+
+    #[test]
+    fn run_n_with_progress_report_every_frames_and_last_frame() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        // the batched event is counted in the fired events
+        let scheduler = simulator.get_scheduler_as_mut();
+        scheduler.set_batch("late", Some(1));
+        scheduler
+            .schedule_tagged(
+                &mut rng,
+                Schedule::Timeout(EventTimer::Time(1)),
+                Priority::LOW,
+                Ev::Tock,
+                "late",
+            )
+            .unwrap();
+        let mut progresses = vec![];
+        simulator.run_n_with_progress_each_event(&mut rng, 5_u64, 2, |progress| {
+            progresses.push((progress.completed(), progress.fired_events()))
+        });
+        assert_eq!(progresses, vec![(2, 5), (4, 9), (5, 11)]);
+    }
+
+    #[test]
+    fn run_step_count_events_scheduled_by_model() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        simulator
+            .injector()
+            .immediate(Priority::LOW, Ev::Tock)
+            .unwrap();
+        let outcome = simulator.run_step(&mut rng, |rng, _, _, scheduler, _| {
+            scheduler
+                .schedule(
+                    rng,
+                    Schedule::Timeout(EventTimer::Time(2)),
+                    Priority::NORMAL,
+                    Ev::Tick,
+                )
+                .unwrap();
+        });
+        // the injected event is not scheduled by the model
+        assert_eq!(outcome.scheduled_during_frame, 1);
+        assert_eq!(outcome.fired, 3);
+    }
+
+    #[test]
+    fn invariant_checks_pass_for_sound_scheduler() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        simulator.enable_invariant_checks();
+        simulator.run_n_each_event(&mut rng, 5_u64);
+        assert_eq!(simulator.get_scheduler().check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn run_n_fire_in_priority_order_and_report() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut each = simulator(&mut rng, Counter::default());
+        let report = each.run_n_each_event(&mut rng, 3_u64);
+        assert_eq!(report.frames_run, 3);
+        assert_eq!(report.total_events_fired, 6);
+        assert_eq!(report.events_remaining, 2);
+        assert_eq!(report.stop_reason, StopReason::Finished);
+        assert_eq!(each.frame_index(), 3);
+        assert_eq!(each.fired_total(), 6);
+        assert_eq!(
+            each.get_recorder().as_slice(),
+            &[Ev::Tick, Ev::Tock, Ev::Tick, Ev::Tock, Ev::Tick, Ev::Tock]
+        );
+
+        let mut bulk = simulator(&mut rng, Counter::default());
+        bulk.run_n_in_bulk_event(&mut rng, 3_u64);
+        assert_eq!(bulk.get_recorder(), each.get_recorder());
+    }
+
+    #[test]
+    fn run_with_closure_handler() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = simulator(&mut rng, Counter::default());
+        let outcome = simulator.run_step(&mut rng, |_, model, recorder, _, events| {
+            for (_, event) in events {
+                model.count(recorder, event);
+            }
+        });
+        assert_eq!(outcome.frame_index, 1);
+        assert_eq!(outcome.fired, 2);
+
+        let mut sizes = vec![];
+        simulator.run_n(&mut rng, 2_u64, |rng, _, _, scheduler, events| {
+            sizes.push(events.len());
+            // the events which are scheduled in the handler fire in the next frame
+            scheduler.immediate(rng, Priority::HIGH, Ev::Tick).unwrap();
+        });
+        assert_eq!(sizes, vec![2, 3]);
+        assert_eq!(simulator.get_model().ticks, 1);
+    }
+}
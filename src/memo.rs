@@ -0,0 +1,136 @@
+//! Memoization of deterministic sub-simulations
+
+use crate::event::Event;
+use crate::model::Model;
+use crate::Simulator;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// shared state of the cache
+struct CacheState<K, V> {
+    results: Mutex<HashMap<(u64, K), V>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// cache of the results of the sub-simulations which are keyed by the seed and the inputs.
+///
+/// the sub-simulation must be deterministic for the seed and the inputs.
+/// cloned cache share the results with original, so the cache can be shared across replications.
+pub struct SubSimulationCache<K, V> {
+    state: Arc<CacheState<K, V>>,
+}
+
+impl<K, V> Clone for SubSimulationCache<K, V> {
+    fn clone(&self) -> Self {
+        SubSimulationCache {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for SubSimulationCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> SubSimulationCache<K, V> {
+    /// initializer
+    pub fn new() -> Self {
+        SubSimulationCache {
+            state: Arc::new(CacheState {
+                results: Mutex::new(HashMap::new()),
+                hits: AtomicUsize::new(0),
+                misses: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// lock the results even if other thread panicked
+    fn results(&self) -> MutexGuard<'_, HashMap<(u64, K), V>> {
+        match self.state.results.lock() {
+            Ok(results) => results,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// get the memoized result or run with the random number generator seeded by the seed.
+    /// the cache is not locked while running, so same run may be computed at the same time.
+    pub fn run<F>(&self, seed: u64, inputs: K, run: F) -> V
+    where
+        F: FnOnce(&mut StdRng, &K) -> V,
+    {
+        let key = (seed, inputs);
+        if let Some(result) = self.results().get(&key) {
+            self.state.hits.fetch_add(1, Ordering::Relaxed);
+            return result.clone();
+        }
+
+        self.state.misses.fetch_add(1, Ordering::Relaxed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let result = run(&mut rng, &key.1);
+        self.results().entry(key).or_insert_with(|| result.clone());
+        result
+    }
+
+    /// get the memoized result or run the sub-simulation which is built from the inputs.
+    /// the simulator is created and run with the random number generator seeded by the seed.
+    pub fn run_simulator<M, E, Rec, B, F>(&self, seed: u64, inputs: K, build: B, run: F) -> V
+    where
+        M: Model<Rec, ModelEvent = E>,
+        E: Event,
+        B: FnOnce(&K) -> (M, Rec),
+        F: FnOnce(&mut Simulator<M, E, Rec>, &mut StdRng) -> V,
+    {
+        self.run(seed, inputs, |rng, inputs| {
+            let (model, recorder) = build(inputs);
+            let mut simulator = Simulator::create_from(rng, model, recorder);
+            run(&mut simulator, rng)
+        })
+    }
+
+    /// judge the result is memoized
+    pub fn contains(&self, seed: u64, inputs: &K) -> bool {
+        self.results().contains_key(&(seed, inputs.clone()))
+    }
+
+    /// count of memoized results
+    pub fn len(&self) -> usize {
+        self.results().len()
+    }
+
+    /// judge no result is memoized
+    pub fn is_empty(&self) -> bool {
+        self.results().is_empty()
+    }
+
+    /// count of the runs which use the memoized result
+    pub fn hits(&self) -> usize {
+        self.state.hits.load(Ordering::Relaxed)
+    }
+
+    /// count of the runs which are computed
+    pub fn misses(&self) -> usize {
+        self.state.misses.load(Ordering::Relaxed)
+    }
+
+    /// forget all memoized results
+    pub fn clear(&self) {
+        self.results().clear();
+    }
+}
+
+impl<K, V> fmt::Debug for SubSimulationCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubSimulationCache")
+            .field("hits", &self.state.hits.load(Ordering::Relaxed))
+            .field("misses", &self.state.misses.load(Ordering::Relaxed))
+            .finish()
+    }
+}
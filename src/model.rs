@@ -1,21 +1,43 @@
 //! Simulator's model
 
-use crate::event::{Event, EventScheduler, Priority};
+#[cfg(feature = "rayon")]
+use crate::event::ScheduleRequests;
+use crate::event::{Either, Event, EventClass, EventScheduler, Priority, Tag};
+use crate::FrameIndex;
 use rand::Rng;
+use std::ops::RangeInclusive;
 
 /// can store model as Simulator's model for Nothing event
 pub trait NothingEventModel<Rec> {
     /// initialize model
     fn initialize(&mut self, recorder: &mut Rec);
 
+    #[allow(unused_variables)]
     /// action when start frame
-    fn start_frame(&mut self, recorder: &mut Rec);
+    fn start_frame(&mut self, recorder: &mut Rec) {
+        // usually not use when override start_frame_at
+    }
+
+    #[allow(unused_variables)]
+    /// action when start frame with the index of the frame. the first frame is 1.
+    fn start_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.start_frame(recorder);
+    }
 
     /// action step
     fn step(&mut self, recorder: &mut Rec);
 
+    #[allow(unused_variables)]
     /// action when finish frame
-    fn finish_frame(&mut self, recorder: &mut Rec);
+    fn finish_frame(&mut self, recorder: &mut Rec) {
+        // usually not use when override finish_frame_at
+    }
+
+    #[allow(unused_variables)]
+    /// action when finish frame with the index of the frame. the first frame is 1.
+    fn finish_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.finish_frame(recorder);
+    }
 }
 
 /// can store model as Simulator's model
@@ -31,8 +53,18 @@ pub trait Model<Rec> {
         scheduler: &mut EventScheduler<Self::ModelEvent>,
     );
 
+    #[allow(unused_variables)]
     /// action when start frame
-    fn start_frame(&mut self, recorder: &mut Rec);
+    fn start_frame(&mut self, recorder: &mut Rec) {
+        // usually not use when override start_frame_at
+    }
+
+    #[allow(unused_variables)]
+    /// action when start frame with the index of the frame. the first frame is 1.
+    /// the default call start_frame, so the model which need the frame number override this instead of counting the frames.
+    fn start_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.start_frame(recorder);
+    }
 
     #[allow(unused_variables)]
     /// schedule event before first event in each frame
@@ -56,8 +88,88 @@ pub trait Model<Rec> {
         // usually not use
     }
 
+    #[allow(unused_variables)]
+    /// action for the event which is expired by the ttl without firing.
+    /// called after the timers are advanced and before before_first_event.
+    fn on_expired<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        event: Self::ModelEvent,
+    ) {
+        // usually not use
+    }
+
+    #[allow(unused_variables)]
+    /// action for the fired events with the tag which are held in the window set by EventScheduler::set_batch.
+    /// called at the end of the window after on_expired. the events are in fired order.
+    fn on_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        tag: Tag,
+        events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        // usually not use
+    }
+
+    #[allow(unused_variables)]
     /// action when finish frame
-    fn finish_frame(&mut self, recorder: &mut Rec);
+    fn finish_frame(&mut self, recorder: &mut Rec) {
+        // usually not use when override finish_frame_at
+    }
+
+    #[allow(unused_variables)]
+    /// action when finish frame with the index of the frame. the default call finish_frame.
+    fn finish_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.finish_frame(recorder);
+    }
+
+    #[allow(unused_variables)]
+    /// action when the warm-up period is finished. e.g. reset the statistics in the recorder.
+    fn on_warmup_complete(&mut self, recorder: &mut Rec) {
+        // usually not use
+    }
+
+    /// phases in each frame in delivered order. used when simulate with the phases.
+    /// if empty, all fired events are delivered in one phase.
+    fn phases(&self) -> Vec<Phase> {
+        Vec::new()
+    }
+}
+
+/// phase in the frame. the fired events of the priorities in the phase are delivered together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    /// name of the phase, e.g. "perception"
+    pub name: &'static str,
+    /// priorities of the events which are delivered in the phase
+    pub priorities: RangeInclusive<Priority>,
+}
+
+impl Phase {
+    /// initializer
+    pub fn new(name: &'static str, priorities: RangeInclusive<Priority>) -> Self {
+        Phase { name, priorities }
+    }
+
+    /// phase which contains all priorities
+    pub fn all(name: &'static str) -> Self {
+        Phase::new(name, Priority::MIN..=Priority::MAX)
+    }
+
+    /// judge the event of the priority is delivered in this phase
+    pub fn contains(&self, priority: Priority) -> bool {
+        self.priorities.contains(&priority)
+    }
+
+    /// priority to schedule the event which is delivered in this phase
+    pub fn priority(&self) -> Priority {
+        *self.priorities.end()
+    }
 }
 
 /// can calculate fired events in bulk
@@ -70,6 +182,21 @@ pub trait BulkEvents<Rec, E: Event>: Model<Rec, ModelEvent = E> {
         scheduler: &mut EventScheduler<Self::ModelEvent>,
         fired_events: Vec<(Priority, Self::ModelEvent)>,
     );
+
+    /// action for each one step with the buffer of fired events which is reused in each frame.
+    /// by default the fired events are moved from the buffer into step_in_bulk, so the buffer keeps its capacity.
+    /// override to avoid the allocation in each frame. the buffer is cleared after the step.
+    fn step_in_bulk_buffer<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: &mut Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        let mut events = Vec::with_capacity(fired_events.len());
+        events.append(fired_events);
+        self.step_in_bulk(rng, recorder, scheduler, events);
+    }
 }
 
 /// can calculate fired each event
@@ -84,3 +211,618 @@ pub trait StepEachEvent<Rec, E: Event>: Model<Rec, ModelEvent = E> {
         fired_event: Self::ModelEvent,
     );
 }
+
+/// can calculate fired each event of the disjoint parts of the model in parallel, e.g. the independent agents.
+///
+/// the fired events are grouped by the key of the part, and the groups are stepped in parallel.
+/// the events of the same part are stepped in fired order.
+/// the scheduling requests of the parts are stored into the scheduler after all parts are stepped in order of the key.
+#[cfg(feature = "rayon")]
+pub trait ParStepEachEvent<Rec, E: Event + Send>: Model<Rec, ModelEvent = E> {
+    /// key of the part, e.g. identifier of the agent
+    type Key: Ord + Send;
+    /// part of the model which is stepped independently of the other parts
+    type Part: Send;
+
+    /// key of the part which the event touch. None is the event which touch the whole model.
+    fn partition_key(&self, priority: Priority, event: &E) -> Option<Self::Key>;
+
+    /// all parts of the model with the keys
+    fn parts_mut(&mut self) -> Vec<(Self::Key, &mut Self::Part)>;
+
+    /// action for one event of the part. the rng is seeded for each part by the rng of the simulator.
+    fn step_part<R: Rng + ?Sized>(
+        key: &Self::Key,
+        part: &mut Self::Part,
+        rng: &mut R,
+        requests: &mut ScheduleRequests<E>,
+        priority: Priority,
+        fired_event: E,
+    );
+
+    /// action for one event which touch the whole model or whose part is not found.
+    /// called in fired order after all parts are stepped.
+    fn step_shared<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<E>,
+        priority: Priority,
+        fired_event: E,
+    );
+}
+
+/// can calculate fired events grouped by the priority
+pub trait StepByPriorityGroup<Rec, E: Event>: Model<Rec, ModelEvent = E> {
+    /// action for the fired events which have the same priority.
+    /// the groups are delivered from the highest priority.
+    fn step_priority_group<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_events: Vec<Self::ModelEvent>,
+    );
+}
+
+/// can calculate fired events phase by phase declared by Model::phases.
+///
+/// the fired event whose priority is not in any phase is delivered in the last phase.
+pub trait StepByPhase<Rec, E: Event>: Model<Rec, ModelEvent = E> {
+    /// action for the fired events in the phase. called only for the phase which has the fired events.
+    fn step_phase<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        phase: &Phase,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    );
+}
+
+/// can calculate fired events each event or in bulk by the class of the event.
+///
+/// the events which are delivered each event are calculated first in fired order,
+/// and then the other events are calculated in bulk.
+pub trait MixedStep<Rec, E: EventClass>: Model<Rec, ModelEvent = E> {
+    /// action for one event which is delivered each event
+    fn step_individual<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_event: Self::ModelEvent,
+    );
+
+    /// action for the events which are delivered in bulk
+    fn step_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    );
+}
+
+/// can calculate fired events in bulk with the recoverable error
+pub trait TryBulkEvents<Rec, E: Event>: Model<Rec, ModelEvent = E> {
+    /// error of the step
+    type Error;
+
+    /// action for each one step
+    fn try_step_in_bulk<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    ) -> Result<(), Self::Error>;
+
+    /// action for each one step with the buffer of fired events which is reused in each frame.
+    /// by default the fired events are moved from the buffer into try_step_in_bulk, so the buffer keeps its capacity.
+    /// override to leave the events which are not stepped after the error in the buffer, then they are reported as skipped.
+    fn try_step_in_bulk_buffer<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: &mut Vec<(Priority, Self::ModelEvent)>,
+    ) -> Result<(), Self::Error> {
+        let mut events = Vec::with_capacity(fired_events.len());
+        events.append(fired_events);
+        self.try_step_in_bulk(rng, recorder, scheduler, events)
+    }
+}
+
+/// can calculate fired each event with the recoverable error
+pub trait TryStepEachEvent<Rec, E: Event>: Model<Rec, ModelEvent = E> {
+    /// error of the step
+    type Error;
+
+    /// action for each one step for one event
+    fn try_step_each_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_event: Self::ModelEvent,
+    ) -> Result<(), Self::Error>;
+}
+
+/// model which combine two independent models sharing one scheduler.
+///
+/// fired events are routed by the variant. each model get the projection of the shared scheduler
+/// which has only own events and share the settings (guards, tags, domains, batches and the clock),
+/// so count, cancel, clear and clear_tag reach all own events but never the events of the other model.
+/// the projection move own events in and out of the shared scheduler at each call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Composed<M1, M2> {
+    /// first model which handle left events
+    pub left: M1,
+    /// second model which handle right events
+    pub right: M2,
+}
+
+impl<M1, M2> Composed<M1, M2> {
+    /// initializer
+    pub fn new(left: M1, right: M2) -> Self {
+        Composed { left, right }
+    }
+
+    /// action with the projection to the left events
+    fn with_left<L: Event + Clone + 'static, R: Event + Clone + 'static, F>(
+        scheduler: &mut EventScheduler<Either<L, R>>,
+        action: F,
+    ) where
+        F: FnOnce(&mut EventScheduler<L>),
+    {
+        scheduler.with_projection(
+            |event| match event {
+                Either::Left(event) => Ok(event),
+                other => Err(other),
+            },
+            Either::Left,
+            action,
+        );
+    }
+
+    /// action with the projection to the right events
+    fn with_right<L: Event + Clone + 'static, R: Event + Clone + 'static, F>(
+        scheduler: &mut EventScheduler<Either<L, R>>,
+        action: F,
+    ) where
+        F: FnOnce(&mut EventScheduler<R>),
+    {
+        scheduler.with_projection(
+            |event| match event {
+                Either::Right(event) => Ok(event),
+                other => Err(other),
+            },
+            Either::Right,
+            action,
+        );
+    }
+}
+
+impl<Rec, M1, M2> Model<Rec> for Composed<M1, M2>
+where
+    M1: Model<Rec>,
+    M2: Model<Rec>,
+    M1::ModelEvent: Clone + 'static,
+    M2::ModelEvent: Clone + 'static,
+{
+    type ModelEvent = Either<M1::ModelEvent, M2::ModelEvent>;
+
+    fn initialize<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+    ) {
+        let (left, right) = (&mut self.left, &mut self.right);
+        Self::with_left(scheduler, |sub| left.initialize(rng, recorder, sub));
+        Self::with_right(scheduler, |sub| right.initialize(rng, recorder, sub));
+    }
+
+    fn start_frame(&mut self, recorder: &mut Rec) {
+        self.left.start_frame(recorder);
+        self.right.start_frame(recorder);
+    }
+
+    fn start_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.left.start_frame_at(recorder, frame);
+        self.right.start_frame_at(recorder, frame);
+    }
+
+    fn before_first_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+    ) {
+        let (left, right) = (&mut self.left, &mut self.right);
+        Self::with_left(scheduler, |sub| left.before_first_event(rng, recorder, sub));
+        Self::with_right(scheduler, |sub| {
+            right.before_first_event(rng, recorder, sub)
+        });
+    }
+
+    fn after_last_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+    ) {
+        let (left, right) = (&mut self.left, &mut self.right);
+        Self::with_left(scheduler, |sub| left.after_last_event(rng, recorder, sub));
+        Self::with_right(scheduler, |sub| right.after_last_event(rng, recorder, sub));
+    }
+
+    fn on_expired<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        event: Self::ModelEvent,
+    ) {
+        let (left, right) = (&mut self.left, &mut self.right);
+        match event {
+            Either::Left(event) => Self::with_left(scheduler, |sub| {
+                left.on_expired(rng, recorder, sub, priority, event)
+            }),
+            Either::Right(event) => Self::with_right(scheduler, |sub| {
+                right.on_expired(rng, recorder, sub, priority, event)
+            }),
+        }
+    }
+
+    fn on_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        tag: Tag,
+        events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        let mut left_events = vec![];
+        let mut right_events = vec![];
+        for (priority, event) in events {
+            match event {
+                Either::Left(event) => left_events.push((priority, event)),
+                Either::Right(event) => right_events.push((priority, event)),
+            }
+        }
+        let (left, right) = (&mut self.left, &mut self.right);
+        if !left_events.is_empty() {
+            Self::with_left(scheduler, |sub| {
+                left.on_batch(rng, recorder, sub, tag, left_events)
+            });
+        }
+        if !right_events.is_empty() {
+            Self::with_right(scheduler, |sub| {
+                right.on_batch(rng, recorder, sub, tag, right_events)
+            });
+        }
+    }
+
+    fn finish_frame(&mut self, recorder: &mut Rec) {
+        self.left.finish_frame(recorder);
+        self.right.finish_frame(recorder);
+    }
+
+    fn finish_frame_at(&mut self, recorder: &mut Rec, frame: FrameIndex) {
+        self.left.finish_frame_at(recorder, frame);
+        self.right.finish_frame_at(recorder, frame);
+    }
+
+    fn on_warmup_complete(&mut self, recorder: &mut Rec) {
+        self.left.on_warmup_complete(recorder);
+        self.right.on_warmup_complete(recorder);
+    }
+}
+
+impl<Rec, E1, E2, M1, M2> BulkEvents<Rec, Either<E1, E2>> for Composed<M1, M2>
+where
+    E1: Event + Clone + 'static,
+    E2: Event + Clone + 'static,
+    M1: BulkEvents<Rec, E1>,
+    M2: BulkEvents<Rec, E2>,
+{
+    fn step_in_bulk<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        let mut left_events = vec![];
+        let mut right_events = vec![];
+        for (priority, event) in fired_events.into_iter() {
+            match event {
+                Either::Left(left) => left_events.push((priority, left)),
+                Either::Right(right) => right_events.push((priority, right)),
+            }
+        }
+
+        let (left, right) = (&mut self.left, &mut self.right);
+        Self::with_left(scheduler, |sub| {
+            left.step_in_bulk(rng, recorder, sub, left_events)
+        });
+        Self::with_right(scheduler, |sub| {
+            right.step_in_bulk(rng, recorder, sub, right_events)
+        });
+    }
+}
+
+impl<Rec, E1, E2, M1, M2> StepEachEvent<Rec, Either<E1, E2>> for Composed<M1, M2>
+where
+    E1: Event + Clone + 'static,
+    E2: Event + Clone + 'static,
+    M1: StepEachEvent<Rec, E1>,
+    M2: StepEachEvent<Rec, E2>,
+{
+    fn step_each_event<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_event: Self::ModelEvent,
+    ) {
+        match fired_event {
+            Either::Left(event) => {
+                let left = &mut self.left;
+                Self::with_left(scheduler, |sub| {
+                    left.step_each_event(rng, recorder, sub, priority, event)
+                });
+            }
+            Either::Right(event) => {
+                let right = &mut self.right;
+                Self::with_right(scheduler, |sub| {
+                    right.step_each_event(rng, recorder, sub, priority, event)
+                });
+            }
+        }
+    }
+}
+
+impl<Rec, E1, E2, M1, M2> StepByPriorityGroup<Rec, Either<E1, E2>> for Composed<M1, M2>
+where
+    E1: Event + Clone + 'static,
+    E2: Event + Clone + 'static,
+    M1: StepByPriorityGroup<Rec, E1>,
+    M2: StepByPriorityGroup<Rec, E2>,
+{
+    fn step_priority_group<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_events: Vec<Self::ModelEvent>,
+    ) {
+        let mut left_events = vec![];
+        let mut right_events = vec![];
+        for event in fired_events.into_iter() {
+            match event {
+                Either::Left(left) => left_events.push(left),
+                Either::Right(right) => right_events.push(right),
+            }
+        }
+
+        let (left, right) = (&mut self.left, &mut self.right);
+        if !left_events.is_empty() {
+            Self::with_left(scheduler, |sub| {
+                left.step_priority_group(rng, recorder, sub, priority, left_events)
+            });
+        }
+        if !right_events.is_empty() {
+            Self::with_right(scheduler, |sub| {
+                right.step_priority_group(rng, recorder, sub, priority, right_events)
+            });
+        }
+    }
+}
+
+impl<Rec, E1, E2, M1, M2> MixedStep<Rec, Either<E1, E2>> for Composed<M1, M2>
+where
+    E1: EventClass + Clone + 'static,
+    E2: EventClass + Clone + 'static,
+    M1: MixedStep<Rec, E1>,
+    M2: MixedStep<Rec, E2>,
+{
+    fn step_individual<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        priority: Priority,
+        fired_event: Self::ModelEvent,
+    ) {
+        match fired_event {
+            Either::Left(event) => {
+                let left = &mut self.left;
+                Self::with_left(scheduler, |sub| {
+                    left.step_individual(rng, recorder, sub, priority, event)
+                });
+            }
+            Either::Right(event) => {
+                let right = &mut self.right;
+                Self::with_right(scheduler, |sub| {
+                    right.step_individual(rng, recorder, sub, priority, event)
+                });
+            }
+        }
+    }
+
+    fn step_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        recorder: &mut Rec,
+        scheduler: &mut EventScheduler<Self::ModelEvent>,
+        fired_events: Vec<(Priority, Self::ModelEvent)>,
+    ) {
+        let mut left_events = vec![];
+        let mut right_events = vec![];
+        for (priority, event) in fired_events.into_iter() {
+            match event {
+                Either::Left(left) => left_events.push((priority, left)),
+                Either::Right(right) => right_events.push((priority, right)),
+            }
+        }
+
+        let (left, right) = (&mut self.left, &mut self.right);
+        Self::with_left(scheduler, |sub| {
+            left.step_batch(rng, recorder, sub, left_events)
+        });
+        Self::with_right(scheduler, |sub| {
+            right.step_batch(rng, recorder, sub, right_events)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventId, EventTimer};
+    use crate::Simulator;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Alarm {
+        Set,
+        Ring,
+    }
+
+    impl Event for Alarm {}
+
+    /// model which set the alarm and can cancel the ring which is scheduled in initialize
+    #[derive(Debug, Default)]
+    struct Clock {
+        cancel: bool,
+        ring: Option<EventId>,
+        counts: Vec<usize>,
+        cancelled: bool,
+    }
+
+    impl Model<Vec<&'static str>> for Clock {
+        type ModelEvent = Alarm;
+
+        fn initialize<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            _: &mut Vec<&'static str>,
+            scheduler: &mut EventScheduler<Alarm>,
+        ) {
+            scheduler
+                .timeout(rng, EventTimer::Time(1), Priority::NORMAL, Alarm::Set)
+                .unwrap();
+            self.ring = scheduler
+                .timeout(rng, EventTimer::Time(3), Priority::NORMAL, Alarm::Ring)
+                .ok();
+        }
+    }
+
+    impl StepEachEvent<Vec<&'static str>, Alarm> for Clock {
+        fn step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<&'static str>,
+            scheduler: &mut EventScheduler<Alarm>,
+            _: Priority,
+            fired_event: Alarm,
+        ) {
+            match fired_event {
+                Alarm::Set => {
+                    self.counts.push(scheduler.count());
+                    if self.cancel {
+                        self.cancelled = self.ring.map_or(false, |id| scheduler.cancel(id));
+                    }
+                }
+                Alarm::Ring => recorder.push("ring"),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Tick;
+
+    impl Event for Tick {}
+
+    /// model which tick every frame and can clear own events at the second tick
+    #[derive(Debug, Default)]
+    struct Ticker {
+        clear: bool,
+        counts: Vec<usize>,
+    }
+
+    impl Model<Vec<&'static str>> for Ticker {
+        type ModelEvent = Tick;
+
+        fn initialize<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            _: &mut Vec<&'static str>,
+            scheduler: &mut EventScheduler<Tick>,
+        ) {
+            scheduler.everytime(rng, Priority::HIGH, Tick).unwrap();
+        }
+    }
+
+    impl StepEachEvent<Vec<&'static str>, Tick> for Ticker {
+        fn step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            _: &mut R,
+            recorder: &mut Vec<&'static str>,
+            scheduler: &mut EventScheduler<Tick>,
+            _: Priority,
+            _: Tick,
+        ) {
+            recorder.push("tick");
+            self.counts.push(scheduler.count());
+            if self.clear && self.counts.len() == 2 {
+                scheduler.clear();
+            }
+        }
+    }
+
+    #[test]
+    fn composed_model_cancel_and_count_own_events() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let clock = Clock {
+            cancel: true,
+            ..Clock::default()
+        };
+        let model = Composed::new(clock, Ticker::default());
+        let mut simulator = Simulator::create_from(&mut rng, model, vec![]);
+        simulator.run_n_each_event(&mut rng, 4_u64);
+
+        let model = simulator.get_model();
+        // the ring which is scheduled in initialize is seen and cancelled from the later step
+        assert_eq!(model.left.counts, vec![1]);
+        assert!(model.left.cancelled);
+        assert_eq!(model.right.counts, vec![1, 1, 1, 1]);
+        assert_eq!(simulator.get_recorder(), &vec!["tick"; 4]);
+    }
+
+    #[test]
+    fn composed_model_clear_keep_events_of_other_model() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let ticker = Ticker {
+            clear: true,
+            ..Ticker::default()
+        };
+        let model = Composed::new(Clock::default(), ticker);
+        let mut simulator = Simulator::create_from(&mut rng, model, vec![]);
+        simulator.run_n_each_event(&mut rng, 4_u64);
+
+        assert!(!simulator.get_model().left.cancelled);
+        assert_eq!(simulator.get_recorder(), &vec!["tick", "tick", "ring"]);
+        assert!(!simulator.get_scheduler().have_event());
+    }
+}
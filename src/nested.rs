@@ -0,0 +1,293 @@
+//! Child simulation which is run inside the step of the parent model
+
+use crate::closure::CallbackEvent;
+use crate::event::{Event, EventClass, EventScheduler, Priority};
+#[cfg(feature = "rayon")]
+use crate::model::ParStepEachEvent;
+use crate::model::{BulkEvents, MixedStep, Model, StepByPhase, StepByPriorityGroup, StepEachEvent};
+use crate::{owned_events, FrameIndex, Simulator};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// final state of the child simulation
+#[derive(Debug, Clone)]
+pub struct ChildResult<M, Rec> {
+    /// model at the end of the child simulation
+    pub model: M,
+    /// recorder at the end of the child simulation
+    pub recorder: Rec,
+    /// count of simulated frames
+    pub frames: FrameIndex,
+    /// true when the child simulation is stopped by the frame budget
+    pub exhausted: bool,
+    /// seed of the random number generator which is derived from the parent
+    pub seed: u64,
+}
+
+/// simulation which has own scheduler and the bounded frame budget.
+///
+/// the random number generator is seeded by the value drawn from the parent's one,
+/// so the child simulation is reproducible when the parent is reproducible.
+#[derive(Debug, Clone)]
+pub struct ChildSimulation<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    simulator: Simulator<M, E, Rec>,
+    rng: StdRng,
+    seed: u64,
+    budget: FrameIndex,
+    exhausted: bool,
+}
+
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    /// create child simulation with the random number generator derived from the parent's one
+    pub fn spawn<R: Rng + ?Sized>(
+        parent_rng: &mut R,
+        model: M,
+        recorder: Rec,
+        budget: FrameIndex,
+    ) -> Self {
+        let seed = parent_rng.gen();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let simulator = Simulator::create_from(&mut rng, model, recorder);
+        ChildSimulation {
+            simulator,
+            rng,
+            seed,
+            budget,
+            exhausted: false,
+        }
+    }
+
+    /// getter for simulator
+    pub fn get_simulator(&self) -> &Simulator<M, E, Rec> {
+        &self.simulator
+    }
+
+    /// getter for the seed of the random number generator
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// count of frames which can be simulated yet
+    pub fn remaining_frames(&self) -> FrameIndex {
+        self.budget.saturating_sub(self.simulator.frame_index())
+    }
+
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until<F, H>(&mut self, can_continue: F, handler: H) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(&mut StdRng, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_until_buffered(can_continue, owned_events(handler))
+    }
+
+    /// same as run_until, but the handler take the buffer of the fired events
+    pub fn run_until_buffered<F, H>(&mut self, mut can_continue: F, mut handler: H) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+        H: FnMut(&mut StdRng, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        loop {
+            let simulator = &self.simulator;
+            if !can_continue(
+                simulator.get_model(),
+                simulator.get_recorder(),
+                simulator.get_scheduler(),
+                simulator.frame_index(),
+            ) {
+                return true;
+            }
+            if self.remaining_frames() == 0 {
+                self.exhausted = true;
+                return false;
+            }
+
+            self.simulator
+                .run_step_buffered(&mut self.rng, &mut handler);
+        }
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all<H>(&mut self, handler: H)
+    where
+        H: FnMut(&mut StdRng, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.run_until(|_, _, _, _| true, handler);
+    }
+
+    /// same as run_all, but the handler take the buffer of the fired events
+    pub fn run_all_buffered<H>(&mut self, handler: H)
+    where
+        H: FnMut(&mut StdRng, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        self.run_until_buffered(|_, _, _, _| true, handler);
+    }
+
+    /// finish the child simulation and take the result
+    pub fn finish(self) -> ChildResult<M, Rec> {
+        ChildResult {
+            frames: self.simulator.frame,
+            model: self.simulator.model,
+            recorder: self.simulator.recorder,
+            exhausted: self.exhausted,
+            seed: self.seed,
+        }
+    }
+}
+
+/// simulate for fired event with calculate in bulk
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: BulkEvents<Rec, E>,
+    E: Event,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_in_bulk_event<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_in_bulk_event)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_in_bulk_event(&mut self) {
+        self.run_all_buffered(Simulator::handler_in_bulk_event)
+    }
+}
+
+/// simulate for fired event with calculate each event
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: StepEachEvent<Rec, E>,
+    E: Event,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_each_event<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_each_event)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_each_event(&mut self) {
+        self.run_all_buffered(Simulator::handler_each_event)
+    }
+}
+
+/// simulate for fired event with calculate each group of the same priority
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: StepByPriorityGroup<Rec, E>,
+    E: Event,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_by_priority<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_by_priority)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_by_priority(&mut self) {
+        self.run_all_buffered(Simulator::handler_by_priority)
+    }
+}
+
+/// simulate for fired event with calculate phase by phase
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: StepByPhase<Rec, E>,
+    E: Event,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_phased<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_phased)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_phased(&mut self) {
+        self.run_all_buffered(Simulator::handler_phased)
+    }
+}
+
+/// simulate for fired event with calculate each event or in bulk by the class of the event
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: MixedStep<Rec, E>,
+    E: EventClass,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_mixed<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_mixed)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_mixed(&mut self) {
+        self.run_all_buffered(Simulator::handler_mixed)
+    }
+}
+
+/// simulate for the closure events by calling the callbacks
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: CallbackEvent<M, Rec>,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_closure<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_closure)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_closure(&mut self) {
+        self.run_all_buffered(Simulator::handler_closure)
+    }
+}
+
+/// simulate for the disjoint parts of the model in parallel
+#[cfg(feature = "rayon")]
+impl<M, E, Rec> ChildSimulation<M, E, Rec>
+where
+    M: ParStepEachEvent<Rec, E>,
+    E: Event + Clone + Send,
+{
+    /// run simulation until condition is true or the frame budget is exhausted.
+    /// return false when the frame budget is exhausted.
+    pub fn run_until_par_each_event<F>(&mut self, can_continue: F) -> bool
+    where
+        F: FnMut(&M, &Rec, &EventScheduler<E>, FrameIndex) -> bool,
+    {
+        self.run_until_buffered(can_continue, Simulator::handler_par_each_event)
+    }
+
+    /// run simulation until the frame budget is exhausted
+    pub fn run_all_par_each_event(&mut self) {
+        self.run_all_buffered(Simulator::handler_par_each_event)
+    }
+}
@@ -0,0 +1,240 @@
+//! Pool of the reusable simulators for answering many simulation requests
+
+use crate::event::Event;
+use crate::model::Model;
+use crate::recorder::ResettableRecorder;
+use crate::Simulator;
+use rand::Rng;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+
+/// factory of the model and the recorder for the new simulator
+type SimulatorFactory<M, Rec> = Box<dyn Fn() -> (M, Rec) + Send + Sync>;
+
+/// counts of the checkouts of the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// count of the simulators which are created by the factory
+    pub created: usize,
+    /// count of the checkouts which reuse the idle simulator
+    pub reused: usize,
+    /// count of the simulators which are dropped at checkin because the pool is full
+    pub discarded: usize,
+}
+
+/// idle simulators and the counts
+struct PoolState<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    idle: Vec<Simulator<M, E, Rec>>,
+    stats: PoolStats,
+}
+
+/// pool which keep the idle simulators and reuse them for the next checkout.
+///
+/// the checked out simulator is reset with the random number generator of the checkout,
+/// so the run is same as the run of the new simulator. the allocated buffers of the scheduler and the recorder are reused.
+/// the simulator is returned to the pool when the checked out simulator is dropped.
+pub struct SimulatorPool<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    state: Mutex<PoolState<M, E, Rec>>,
+    factory: SimulatorFactory<M, Rec>,
+    max_idle: usize,
+}
+
+impl<M, E, Rec> SimulatorPool<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    Rec: ResettableRecorder,
+{
+    /// create pool which keep up to max_idle simulators
+    pub fn new<F>(max_idle: usize, factory: F) -> Self
+    where
+        F: Fn() -> (M, Rec) + Send + Sync + 'static,
+    {
+        SimulatorPool {
+            state: Mutex::new(PoolState {
+                idle: vec![],
+                stats: PoolStats::default(),
+            }),
+            factory: Box::new(factory),
+            max_idle,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState<M, E, Rec>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// create the idle simulators up to the count in advance
+    pub fn warm_up<R: Rng + ?Sized>(&self, rng: &mut R, count: usize) {
+        let count = count.min(self.max_idle);
+        let mut state = self.lock();
+        while state.idle.len() < count {
+            let (model, recorder) = (self.factory)();
+            state
+                .idle
+                .push(Simulator::create_from(rng, model, recorder));
+            state.stats.created += 1;
+        }
+    }
+
+    /// take the simulator which is initialized with the random number generator
+    pub fn checkout<R: Rng + ?Sized>(&self, rng: &mut R) -> PooledSimulator<'_, M, E, Rec> {
+        self.checkout_with(rng, |_| {})
+    }
+
+    /// take the simulator which is initialized with the random number generator
+    /// after the model is configured for the request (e.g. the parameters of the scenario)
+    pub fn checkout_with<R, F>(&self, rng: &mut R, configure: F) -> PooledSimulator<'_, M, E, Rec>
+    where
+        R: Rng + ?Sized,
+        F: FnOnce(&mut M),
+    {
+        let idle = self.lock().idle.pop();
+        let simulator = match idle {
+            Some(mut simulator) => {
+                configure(simulator.get_model_as_mut());
+                simulator.reset_with_recorder(rng);
+                self.lock().stats.reused += 1;
+                simulator
+            }
+            None => {
+                let (mut model, recorder) = (self.factory)();
+                configure(&mut model);
+                self.lock().stats.created += 1;
+                Simulator::create_from(rng, model, recorder)
+            }
+        };
+        PooledSimulator {
+            pool: self,
+            simulator: Some(simulator),
+        }
+    }
+
+    /// return the simulator to the pool. if the pool is full, the simulator is dropped.
+    pub fn checkin(&self, simulator: Simulator<M, E, Rec>) {
+        let mut state = self.lock();
+        if state.idle.len() < self.max_idle {
+            state.idle.push(simulator);
+        } else {
+            state.stats.discarded += 1;
+        }
+    }
+
+    /// count of the idle simulators
+    pub fn idle(&self) -> usize {
+        self.lock().idle.len()
+    }
+
+    /// limit of the count of the idle simulators
+    pub fn max_idle(&self) -> usize {
+        self.max_idle
+    }
+
+    /// counts of the checkouts
+    pub fn stats(&self) -> PoolStats {
+        self.lock().stats
+    }
+}
+
+impl<M, E, Rec> fmt::Debug for SimulatorPool<M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f.debug_struct("SimulatorPool")
+            .field("idle", &state.idle.len())
+            .field("max_idle", &self.max_idle)
+            .field("stats", &state.stats)
+            .finish()
+    }
+}
+
+/// simulator which is checked out from the pool and returned to the pool when dropped
+pub struct PooledSimulator<'a, M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    Rec: ResettableRecorder,
+{
+    pool: &'a SimulatorPool<M, E, Rec>,
+    // always Some until dropped or detached
+    simulator: Option<Simulator<M, E, Rec>>,
+}
+
+impl<'a, M, E, Rec> PooledSimulator<'a, M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    Rec: ResettableRecorder,
+{
+    /// take the simulator without returning to the pool
+    pub fn detach(mut self) -> Simulator<M, E, Rec> {
+        self.simulator.take().unwrap()
+    }
+}
+
+impl<'a, M, E, Rec> Deref for PooledSimulator<'a, M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    Rec: ResettableRecorder,
+{
+    type Target = Simulator<M, E, Rec>;
+
+    fn deref(&self) -> &Self::Target {
+        self.simulator.as_ref().unwrap()
+    }
+}
+
+impl<'a, M, E, Rec> DerefMut for PooledSimulator<'a, M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    Rec: ResettableRecorder,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.simulator.as_mut().unwrap()
+    }
+}
+
+impl<'a, M, E, Rec> fmt::Debug for PooledSimulator<'a, M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E> + fmt::Debug,
+    E: Event + fmt::Debug,
+    Rec: ResettableRecorder + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PooledSimulator")
+            .field(&self.simulator)
+            .finish()
+    }
+}
+
+impl<'a, M, E, Rec> Drop for PooledSimulator<'a, M, E, Rec>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    Rec: ResettableRecorder,
+{
+    fn drop(&mut self) {
+        if let Some(simulator) = self.simulator.take() {
+            self.pool.checkin(simulator);
+        }
+    }
+}
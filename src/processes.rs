@@ -0,0 +1,183 @@
+//! Stochastic processes which keep generating the events
+
+use crate::event::{
+    Event, EventId, EventScheduler, EventTimer, Priority, Schedule, ScheduleEventError, TimeSampler,
+};
+use rand::{Rng, RngCore};
+use std::fmt;
+
+/// Error for giving the rate of the arrivals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrivalRateError {
+    /// the rate is zero, negative or NaN
+    NotPositive(f64),
+    /// the rate is more than one arrival per frame, including the infinity
+    OverOnePerFrame(f64),
+}
+
+impl std::error::Error for ArrivalRateError {}
+
+impl fmt::Display for ArrivalRateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArrivalRateError::NotPositive(rate) => {
+                write!(f, "The arrival rate {} is not positive", rate)
+            }
+            ArrivalRateError::OverOnePerFrame(rate) => {
+                write!(f, "The arrival rate {} is over one arrival per frame", rate)
+            }
+        }
+    }
+}
+
+/// arrival process which generate the arrival event after each inter-arrival time.
+///
+/// the inter-arrival time is rounded to the frames, so at most one arrival per frame.
+#[derive(Debug, Clone)]
+pub struct ArrivalProcess {
+    inter_arrival: EventTimer,
+}
+
+impl ArrivalProcess {
+    /// Poisson arrivals with the mean count of the arrivals per frame, which is in (0, 1].
+    ///
+    /// the arrival fire in each frame with the probability of the rate independently,
+    /// which is the Poisson arrivals on the frames (Bernoulli arrivals) and keep the rate exactly.
+    /// the inter-arrival times are geometric instead of the rounded exponential times.
+    pub fn poisson(rate: f64) -> Result<Self, ArrivalRateError> {
+        if rate.is_nan() || rate <= 0.0 {
+            return Err(ArrivalRateError::NotPositive(rate));
+        }
+        if rate > 1.0 {
+            return Err(ArrivalRateError::OverOnePerFrame(rate));
+        }
+        // log of the probability of no arrival in the frame
+        let log_miss = (-rate).ln_1p();
+        Ok(Self::renewal(move |rng| {
+            ((1.0 - rng.gen::<f64>()).ln() / log_miss).floor() + 1.0
+        }))
+    }
+
+    /// Poisson arrivals whose mean inter-arrival time is the frames, which is at least 1.
+    /// this is same as the Poisson arrivals with the rate of the inverse.
+    pub fn exponential(mean: f64) -> Result<Self, ArrivalRateError> {
+        Self::poisson(1.0 / mean)
+    }
+
+    /// renewal arrivals whose inter-arrival times are sampled from the user distribution
+    pub fn renewal<F>(sampler: F) -> Self
+    where
+        F: Fn(&mut dyn RngCore) -> f64 + Send + Sync + 'static,
+    {
+        Self::from_timer(EventTimer::Sampled(TimeSampler::new(sampler)))
+    }
+
+    /// arrivals whose inter-arrival times are the timer
+    pub fn from_timer(inter_arrival: EventTimer) -> Self {
+        ArrivalProcess { inter_arrival }
+    }
+
+    /// getter for the timer of the inter-arrival time
+    pub fn inter_arrival(&self) -> &EventTimer {
+        &self.inter_arrival
+    }
+
+    /// start to generate the arrival event on the scheduler. the first arrival is after the inter-arrival time.
+    /// the arrivals continue until stopped by the handle.
+    pub fn install<E, R>(
+        &self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        priority: Priority,
+        event: E,
+    ) -> Result<Arrivals, ScheduleEventError>
+    where
+        E: Event + Clone,
+        R: Rng + ?Sized,
+    {
+        let schedule = Schedule::EveryInterval(self.inter_arrival.clone());
+        scheduler
+            .schedule(rng, schedule, priority, event)
+            .map(|id| Arrivals { id })
+    }
+}
+
+/// handle of the installed arrival process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Arrivals {
+    id: EventId,
+}
+
+impl Arrivals {
+    /// identifier of the scheduled arrival event
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// judge the arrivals continue
+    pub fn is_running<E: Event>(&self, scheduler: &EventScheduler<E>) -> bool {
+        scheduler.is_scheduled(self.id)
+    }
+
+    /// stop the arrivals. if already stopped, then return false.
+    pub fn stop<E: Event>(&self, scheduler: &mut EventScheduler<E>) -> bool {
+        scheduler.cancel(self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Arrive;
+
+    impl Event for Arrive {}
+
+    #[test]
+    fn poisson_keep_rate() {
+        for rate in [0.05, 0.5, 1.0] {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut scheduler = EventScheduler::new();
+            ArrivalProcess::poisson(rate)
+                .unwrap()
+                .install(&mut rng, &mut scheduler, Priority::NORMAL, Arrive)
+                .unwrap();
+            let mut fired = vec![];
+            for _ in 0..100_000 {
+                scheduler.next_time_and_fire(&mut rng, &mut fired);
+            }
+            let delivered = fired.len() as f64 / 100_000.0;
+            assert!(
+                (delivered - rate).abs() < rate * 0.05,
+                "rate {} is delivered as {}",
+                rate,
+                delivered
+            );
+        }
+    }
+
+    #[test]
+    fn poisson_reject_rate_out_of_frame() {
+        assert_eq!(
+            ArrivalProcess::poisson(0.0).unwrap_err(),
+            ArrivalRateError::NotPositive(0.0)
+        );
+        assert_eq!(
+            ArrivalProcess::poisson(-1.0).unwrap_err(),
+            ArrivalRateError::NotPositive(-1.0)
+        );
+        assert!(ArrivalProcess::poisson(f64::NAN).is_err());
+        assert_eq!(
+            ArrivalProcess::poisson(2.0).unwrap_err(),
+            ArrivalRateError::OverOnePerFrame(2.0)
+        );
+        assert_eq!(
+            ArrivalProcess::exponential(0.0).unwrap_err(),
+            ArrivalRateError::OverOnePerFrame(f64::INFINITY)
+        );
+        assert!(ArrivalProcess::exponential(4.0).is_ok());
+    }
+}
@@ -0,0 +1,90 @@
+//! Progress of the long run
+
+use crate::control::StopReason;
+use crate::FrameIndex;
+use std::time::Duration;
+
+/// progress of the run which is reported to the progress function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    completed: FrameIndex,
+    remaining: Option<FrameIndex>,
+    fired_events: u64,
+    elapsed: Duration,
+}
+
+impl Progress {
+    /// initializer
+    pub(crate) fn new(
+        completed: FrameIndex,
+        remaining: Option<FrameIndex>,
+        fired_events: u64,
+        elapsed: Duration,
+    ) -> Self {
+        Progress {
+            completed,
+            remaining,
+            fired_events,
+            elapsed,
+        }
+    }
+
+    /// count of the frames which are simulated in this run
+    pub fn completed(&self) -> FrameIndex {
+        self.completed
+    }
+
+    /// count of the frames which are not simulated yet. if the counter does not know, then None.
+    pub fn remaining(&self) -> Option<FrameIndex> {
+        self.remaining
+    }
+
+    /// count of the events which are fired in this run
+    pub fn fired_events(&self) -> u64 {
+        self.fired_events
+    }
+
+    /// elapsed time of the wall clock from the start of this run
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// ratio of the simulated frames in all frames. if the remaining is unknown, then None.
+    pub fn fraction(&self) -> Option<f64> {
+        self.remaining.map(|remaining| {
+            let total = self.completed + remaining;
+            if total == 0 {
+                1.0
+            } else {
+                self.completed as f64 / total as f64
+            }
+        })
+    }
+
+    /// estimated time remaining from the mean time of the simulated frames.
+    /// if the remaining is unknown or no frame is simulated, then None.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+        self.remaining.map(|remaining| {
+            self.elapsed
+                .mul_f64(remaining as f64 / self.completed as f64)
+        })
+    }
+}
+
+/// summary of the run which is returned by the run methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationReport {
+    /// count of the frames which are simulated in this run
+    pub frames_run: FrameIndex,
+    /// count of the events which are fired in this run
+    pub total_events_fired: u64,
+    /// count of the scheduled events at the end of this run. 0 when the scheduler ran dry.
+    pub events_remaining: usize,
+    /// elapsed time of the wall clock in this run
+    pub wall_time: Duration,
+    /// reason why this run stopped
+    pub stop_reason: StopReason,
+}
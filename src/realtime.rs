@@ -0,0 +1,108 @@
+//! Pacing of the simulation against the wall clock
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// policy for the frame which is started later than its deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatePolicy {
+    /// run next frames without waiting until the simulation catch up with the wall clock
+    CatchUp,
+    /// give up the lost time and pace next frames from now
+    Drop,
+}
+
+/// setting for pacing frames against the wall clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    /// wall time for one frame
+    pub frame_duration: Duration,
+    /// policy for slow frames
+    pub policy: LatePolicy,
+}
+
+impl Pacing {
+    /// create pacing with the policy which catch up with the wall clock
+    pub fn catch_up(frame_duration: Duration) -> Self {
+        Pacing {
+            frame_duration,
+            policy: LatePolicy::CatchUp,
+        }
+    }
+
+    /// create pacing with the policy which drop the lost time
+    pub fn drop_late(frame_duration: Duration) -> Self {
+        Pacing {
+            frame_duration,
+            policy: LatePolicy::Drop,
+        }
+    }
+}
+
+/// report of real time simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RealtimeReport {
+    /// count of simulated frames
+    pub frames_run: u64,
+    /// count of frames which started after the deadline
+    pub late_frames: u64,
+    /// the largest delay of starting frame from the deadline
+    pub max_lag: Duration,
+    /// total time which is given up by the drop policy
+    pub dropped: Duration,
+    /// difference between elapsed wall time and simulated time at the end of run
+    pub drift: Duration,
+}
+
+/// clock which wait for each frame's deadline
+#[derive(Debug, Clone)]
+pub(crate) struct Pacer {
+    pacing: Pacing,
+    started: Instant,
+    deadline: Instant,
+    report: RealtimeReport,
+}
+
+impl Pacer {
+    /// initializer
+    pub(crate) fn new(pacing: Pacing) -> Self {
+        let now = Instant::now();
+        Pacer {
+            pacing,
+            started: now,
+            deadline: now,
+            report: RealtimeReport::default(),
+        }
+    }
+
+    /// wait until the deadline of next frame
+    pub(crate) fn wait_frame(&mut self) {
+        let now = Instant::now();
+        if now < self.deadline {
+            sleep(self.deadline - now);
+        } else if self.report.frames_run > 0 {
+            let lag = now - self.deadline;
+            if lag > Duration::from_secs(0) {
+                self.report.late_frames += 1;
+                self.report.max_lag = self.report.max_lag.max(lag);
+            }
+            if self.pacing.policy == LatePolicy::Drop {
+                self.report.dropped += lag;
+                self.deadline = now;
+            }
+        }
+        self.deadline += self.pacing.frame_duration;
+        self.report.frames_run += 1;
+    }
+
+    /// finish pacing and get the report
+    pub(crate) fn finish(mut self) -> RealtimeReport {
+        let simulated = self
+            .pacing
+            .frame_duration
+            .mul_f64(self.report.frames_run as f64);
+        let elapsed = self.started.elapsed();
+        self.report.drift = elapsed.checked_sub(simulated).unwrap_or_default();
+        self.report
+    }
+}
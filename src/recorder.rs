@@ -0,0 +1,781 @@
+//! Simulator's recorder helpers
+
+use crate::event::{Event, Priority};
+use crate::progress::SimulationReport;
+use crate::FrameIndex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+/// recorder which can be reset for reuse in the next run
+pub trait ResettableRecorder {
+    /// remove the records with keeping the allocated buffers if possible
+    fn reset(&mut self);
+}
+
+impl<T> ResettableRecorder for Vec<T> {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+impl ResettableRecorder for String {
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+/// recorder which is notified by the simulator at the standard timings.
+///
+/// the simulator call the hooks when created by Simulator::create_recorded or after Simulator::enable_recorder_hooks,
+/// so the model need not forward everything to the recorder.
+pub trait Recorder<E: Event> {
+    #[allow(unused_variables)]
+    /// action before the model start the frame
+    fn on_frame_start(&mut self, frame: FrameIndex) {
+        // usually not use
+    }
+
+    #[allow(unused_variables)]
+    /// action for each fired event before the model handle it. the events which are not captured are not notified.
+    fn on_event_fired(&mut self, frame: FrameIndex, priority: Priority, event: &E) {
+        // usually not use
+    }
+
+    #[allow(unused_variables)]
+    /// action after the model finish the frame
+    fn on_frame_end(&mut self, frame: FrameIndex) {
+        // usually not use
+    }
+}
+
+/// recorder which is finalized at the end of each run, e.g. to flush the buffered writer.
+///
+/// the simulator call finalize after Simulator::enable_recorder_finalize when the run_n and run_until family methods return,
+/// including the early exits by the controller or the deadline and the error exits of the try_run methods.
+pub trait FinalizeRecorder {
+    /// action at the end of the run with the summary of the run
+    fn finalize(&mut self, report: &SimulationReport);
+}
+
+/// recorder which can be merged with the recorder of the other replication, e.g. to aggregate the results of the ensemble
+pub trait MergeRecorder {
+    /// merge the records of the other replication into this recorder
+    fn merge(&mut self, other: Self);
+}
+
+impl<T> MergeRecorder for Vec<T> {
+    fn merge(&mut self, mut other: Self) {
+        self.append(&mut other);
+    }
+}
+
+impl<K: Ord, V: MergeRecorder> MergeRecorder for BTreeMap<K, V> {
+    fn merge(&mut self, other: Self) {
+        for (key, value) in other.into_iter() {
+            match self.get_mut(&key) {
+                Some(current) => current.merge(value),
+                None => {
+                    self.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+impl<T: MergeRecorder> MergeRecorder for Option<T> {
+    fn merge(&mut self, other: Self) {
+        match (self.as_mut(), other) {
+            (Some(current), Some(other)) => current.merge(other),
+            (None, other) => *self = other,
+            (Some(_), None) => {}
+        }
+    }
+}
+
+/// hooks of the recorder which are kept in the simulator
+pub(crate) struct RecorderHooks<E, Rec> {
+    pub(crate) frame_start: fn(&mut Rec, FrameIndex),
+    pub(crate) event_fired: fn(&mut Rec, FrameIndex, Priority, &E),
+    pub(crate) frame_end: fn(&mut Rec, FrameIndex),
+}
+
+impl<E: Event, Rec: Recorder<E>> RecorderHooks<E, Rec> {
+    /// initializer
+    pub(crate) fn new() -> Self {
+        RecorderHooks {
+            frame_start: Rec::on_frame_start,
+            event_fired: Rec::on_event_fired,
+            frame_end: Rec::on_frame_end,
+        }
+    }
+}
+
+impl<E, Rec> Clone for RecorderHooks<E, Rec> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, Rec> Copy for RecorderHooks<E, Rec> {}
+
+/// recorder wrapper which keep the snapshots of the recorder indexed by the frame.
+///
+/// the model which opt into snapshotting call finish_frame at the end of each frame (e.g. in Model::finish_frame).
+/// the frame index is counted by the calls of finish_frame, so the first frame is 1 as same as the simulator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History<Rec> {
+    current: Rec,
+    frame: FrameIndex,
+    interval: FrameIndex,
+    snapshots: BTreeMap<FrameIndex, Rec>,
+}
+
+impl<Rec: Clone> History<Rec> {
+    /// create history which snapshot every frame
+    pub fn new(recorder: Rec) -> Self {
+        Self::with_interval(recorder, 1)
+    }
+
+    /// create history which snapshot every interval frames. interval 0 is treated as 1.
+    pub fn with_interval(recorder: Rec, interval: FrameIndex) -> Self {
+        History {
+            current: recorder,
+            frame: 0,
+            interval: interval.max(1),
+            snapshots: BTreeMap::new(),
+        }
+    }
+
+    /// getter for current recorder
+    pub fn recorder(&self) -> &Rec {
+        &self.current
+    }
+
+    /// getter for current recorder
+    pub fn recorder_as_mut(&mut self) -> &mut Rec {
+        &mut self.current
+    }
+
+    /// index of the last finished frame
+    pub fn frame_index(&self) -> FrameIndex {
+        self.frame
+    }
+
+    /// finish the frame and snapshot current recorder if the frame is on the interval
+    pub fn finish_frame(&mut self) {
+        self.frame += 1;
+        if self.frame % self.interval == 0 {
+            self.snapshots.insert(self.frame, self.current.clone());
+        }
+    }
+
+    /// snapshot current recorder at the last finished frame regardless of the interval
+    pub fn snapshot(&mut self) {
+        self.snapshots.insert(self.frame, self.current.clone());
+    }
+
+    /// snapshot of the recorder at the end of the frame
+    pub fn at(&self, frame: FrameIndex) -> Option<&Rec> {
+        self.snapshots.get(&frame)
+    }
+
+    /// latest snapshot which is taken at or before the frame with the frame index
+    pub fn latest_at(&self, frame: FrameIndex) -> Option<(FrameIndex, &Rec)> {
+        self.snapshots
+            .range(..=frame)
+            .next_back()
+            .map(|(index, snapshot)| (*index, snapshot))
+    }
+
+    /// snapshots in the frames
+    pub fn range<B>(&self, frames: B) -> impl Iterator<Item = (FrameIndex, &Rec)>
+    where
+        B: std::ops::RangeBounds<FrameIndex>,
+    {
+        self.snapshots
+            .range(frames)
+            .map(|(index, snapshot)| (*index, snapshot))
+    }
+
+    /// frame indexes which have the snapshot
+    pub fn frames(&self) -> Vec<FrameIndex> {
+        self.snapshots.keys().copied().collect()
+    }
+
+    /// count of snapshots
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// judge no snapshot
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// take current recorder and the snapshots
+    pub fn into_parts(self) -> (Rec, BTreeMap<FrameIndex, Rec>) {
+        (self.current, self.snapshots)
+    }
+}
+
+/// the snapshot is taken at the end of the frame, so the model need not call finish_frame.
+impl<E: Event, Rec: Recorder<E> + Clone> Recorder<E> for History<Rec> {
+    fn on_frame_start(&mut self, frame: FrameIndex) {
+        self.current.on_frame_start(frame);
+    }
+
+    fn on_event_fired(&mut self, frame: FrameIndex, priority: Priority, event: &E) {
+        self.current.on_event_fired(frame, priority, event);
+    }
+
+    fn on_frame_end(&mut self, frame: FrameIndex) {
+        self.current.on_frame_end(frame);
+        self.finish_frame();
+    }
+}
+
+impl<Rec: ResettableRecorder> ResettableRecorder for History<Rec> {
+    fn reset(&mut self) {
+        self.current.reset();
+        self.frame = 0;
+        self.snapshots.clear();
+    }
+}
+
+/// way to reduce the samples of the time series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Downsampling {
+    /// keep the sample of every k-th frame. k 0 is treated as 1.
+    EveryNth(FrameIndex),
+    /// keep min, max and mean of the samples in every width frames. width 0 is treated as 1.
+    Buckets(FrameIndex),
+}
+
+impl Downsampling {
+    /// frames which are reduced into one point
+    fn resolution(&self) -> FrameIndex {
+        match self {
+            Downsampling::EveryNth(k) | Downsampling::Buckets(k) => *k,
+        }
+    }
+
+    /// same way with the resolution
+    fn with_resolution(&self, resolution: FrameIndex) -> Self {
+        match self {
+            Downsampling::EveryNth(_) => Downsampling::EveryNth(resolution),
+            Downsampling::Buckets(_) => Downsampling::Buckets(resolution),
+        }
+    }
+}
+
+/// summary of the samples in the bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    /// first frame of the bucket
+    pub start: FrameIndex,
+    /// last frame which is sampled in the bucket
+    pub end: FrameIndex,
+    /// count of the samples
+    pub count: u64,
+    /// min of the samples
+    pub min: f64,
+    /// max of the samples
+    pub max: f64,
+    /// mean of the samples
+    pub mean: f64,
+}
+
+impl Bucket {
+    /// bucket which has only one sample
+    fn new(start: FrameIndex, frame: FrameIndex, value: f64) -> Self {
+        Bucket {
+            start,
+            end: frame,
+            count: 1,
+            min: value,
+            max: value,
+            mean: value,
+        }
+    }
+
+    /// add the sample
+    fn push(&mut self, frame: FrameIndex, value: f64) {
+        self.end = frame;
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.mean += (value - self.mean) / self.count as f64;
+    }
+
+    /// merge the next bucket into this bucket
+    fn merge(&mut self, next: &Bucket) {
+        let count = self.count + next.count;
+        self.mean = (self.mean * self.count as f64 + next.mean * next.count as f64) / count as f64;
+        self.end = next.end;
+        self.count = count;
+        self.min = self.min.min(next.min);
+        self.max = self.max.max(next.max);
+    }
+}
+
+/// recorder which sample the metric every frame and keep only the downsampled time series.
+///
+/// if the count of the points is limited, the resolution is doubled when the points are over the limit,
+/// so the memory is bounded even if the length of the run is unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeriesRecorder<T> {
+    /// way to downsample when created. the resolution is restored when reset.
+    initial: Downsampling,
+    downsampling: Downsampling,
+    max_points: Option<usize>,
+    frame: FrameIndex,
+    samples: Vec<(FrameIndex, T)>,
+    buckets: Vec<Bucket>,
+}
+
+impl<T: Copy + Into<f64>> TimeSeriesRecorder<T> {
+    /// create recorder which keep the sample of every k-th frame
+    pub fn every_nth(k: FrameIndex) -> Self {
+        Self::new(Downsampling::EveryNth(k))
+    }
+
+    /// create recorder which keep min, max and mean in every width frames
+    pub fn buckets(width: FrameIndex) -> Self {
+        Self::new(Downsampling::Buckets(width))
+    }
+
+    /// create recorder with the way to downsample
+    pub fn new(downsampling: Downsampling) -> Self {
+        let downsampling = downsampling.with_resolution(downsampling.resolution().max(1));
+        TimeSeriesRecorder {
+            initial: downsampling,
+            downsampling,
+            max_points: None,
+            frame: 0,
+            samples: vec![],
+            buckets: vec![],
+        }
+    }
+
+    /// limit the count of the points. the resolution is doubled when the points are over the limit.
+    /// the limit less than 2 is treated as 2.
+    pub fn max_points(mut self, max_points: usize) -> Self {
+        self.max_points = Some(max_points.max(2));
+        self
+    }
+
+    /// getter for the current way to downsample
+    pub fn downsampling(&self) -> Downsampling {
+        self.downsampling
+    }
+
+    /// index of the last sampled frame
+    pub fn frame_index(&self) -> FrameIndex {
+        self.frame
+    }
+
+    /// sample the metric at the next frame of the last sampled frame. the first frame is 1 as same as the simulator.
+    pub fn record(&mut self, value: T) {
+        self.record_at(self.frame + 1, value);
+    }
+
+    /// sample the metric at the frame. the frames must be sampled in order.
+    pub fn record_at(&mut self, frame: FrameIndex, value: T) {
+        self.frame = frame;
+        let resolution = self.downsampling.resolution();
+        match self.downsampling {
+            Downsampling::EveryNth(_) => {
+                if frame % resolution == 0 {
+                    self.samples.push((frame, value));
+                }
+            }
+            Downsampling::Buckets(_) => {
+                let start = frame - (frame.saturating_sub(1) % resolution);
+                match self.buckets.last_mut() {
+                    Some(bucket) if bucket.start == start => bucket.push(frame, value.into()),
+                    _ => self.buckets.push(Bucket::new(start, frame, value.into())),
+                }
+            }
+        }
+        if self
+            .max_points
+            .map_or(false, |max_points| self.len() > max_points)
+        {
+            self.coarsen();
+        }
+    }
+
+    /// double the resolution with merging the points
+    fn coarsen(&mut self) {
+        let resolution = self.downsampling.resolution().saturating_mul(2);
+        self.downsampling = self.downsampling.with_resolution(resolution);
+        match self.downsampling {
+            Downsampling::EveryNth(_) => {
+                self.samples.retain(|(frame, _)| frame % resolution == 0);
+            }
+            Downsampling::Buckets(_) => {
+                let mut merged: Vec<Bucket> = Vec::with_capacity(self.buckets.len() / 2 + 1);
+                for bucket in self.buckets.iter() {
+                    let start = bucket.start - (bucket.start.saturating_sub(1) % resolution);
+                    match merged.last_mut() {
+                        Some(last) if last.start == start => last.merge(bucket),
+                        _ => merged.push(Bucket { start, ..*bucket }),
+                    }
+                }
+                self.buckets = merged;
+            }
+        }
+    }
+
+    /// count of the kept points
+    pub fn len(&self) -> usize {
+        match self.downsampling {
+            Downsampling::EveryNth(_) => self.samples.len(),
+            Downsampling::Buckets(_) => self.buckets.len(),
+        }
+    }
+
+    /// judge no point is kept
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// kept samples with the frame. empty when downsample by the buckets.
+    pub fn samples(&self) -> &[(FrameIndex, T)] {
+        &self.samples
+    }
+
+    /// kept buckets. empty when downsample by every k-th frame.
+    pub fn bucket_summaries(&self) -> &[Bucket] {
+        &self.buckets
+    }
+
+    /// points of the frame and the value (or the mean of the bucket at the first frame), e.g. for report::ScenarioReport::plot
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        match self.downsampling {
+            Downsampling::EveryNth(_) => self
+                .samples
+                .iter()
+                .map(|(frame, value)| (*frame as f64, (*value).into()))
+                .collect(),
+            Downsampling::Buckets(_) => self
+                .buckets
+                .iter()
+                .map(|bucket| (bucket.start as f64, bucket.mean))
+                .collect(),
+        }
+    }
+}
+
+impl<T> ResettableRecorder for TimeSeriesRecorder<T> {
+    fn reset(&mut self) {
+        self.downsampling = self.initial;
+        self.frame = 0;
+        self.samples.clear();
+        self.buckets.clear();
+    }
+}
+
+/// message for background writer thread
+enum WriterMessage<T> {
+    /// write the record
+    Record(T),
+    /// flush the writer and notify finished
+    Flush(SyncSender<io::Result<()>>),
+}
+
+/// recorder which hand records to background thread and write there.
+///
+/// the channel between simulation and the thread is bounded,
+/// so recording is blocked while the thread is busy (backpressure).
+/// the records are flushed and the thread is joined when call finish or drop the recorder.
+///
+/// to flush when each run method return, use the recorder as FinalizeRecorder.
+/// the thread keep running for the next run, and the error of the flush is returned by finish.
+///
+/// ```ignore
+/// let recorder = AsyncWriterRecorder::new(file, 1024, |file, line: String| writeln!(file, "{}", line));
+/// let mut simulator = Simulator::create_from(&mut rng, model, recorder);
+/// simulator.enable_recorder_finalize();
+/// simulator.run_n_each_event(&mut rng, COUNT); // flushed here
+/// simulator.into_parts().1.finish()?;
+/// ```
+pub struct AsyncWriterRecorder<T: Send + 'static> {
+    sender: Option<SyncSender<WriterMessage<T>>>,
+    worker: Option<JoinHandle<io::Result<()>>>,
+    /// first error of the flush at the end of the run
+    finalize_error: Option<io::Error>,
+}
+
+impl<T: Send + 'static> AsyncWriterRecorder<T> {
+    /// create recorder which write records to the writer by write function in background thread.
+    ///
+    /// capacity is the count of records which can wait for writing.
+    pub fn new<W, F>(writer: W, capacity: usize, write_record: F) -> Self
+    where
+        W: Write + Send + 'static,
+        F: FnMut(&mut W, T) -> io::Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = sync_channel(capacity);
+        let worker = thread::spawn(move || Self::work(writer, receiver, write_record));
+        AsyncWriterRecorder {
+            sender: Some(sender),
+            worker: Some(worker),
+            finalize_error: None,
+        }
+    }
+
+    /// action in background thread
+    fn work<W, F>(
+        mut writer: W,
+        receiver: Receiver<WriterMessage<T>>,
+        mut write_record: F,
+    ) -> io::Result<()>
+    where
+        W: Write,
+        F: FnMut(&mut W, T) -> io::Result<()>,
+    {
+        for message in receiver.iter() {
+            match message {
+                WriterMessage::Record(record) => write_record(&mut writer, record)?,
+                WriterMessage::Flush(notifier) => {
+                    let _ = notifier.send(writer.flush());
+                }
+            }
+        }
+        writer.flush()
+    }
+
+    /// error when background thread already stopped
+    fn stopped_error() -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "background writer is stopped")
+    }
+
+    /// send the record to background thread.
+    /// if channel is full, then wait until the thread receive records.
+    pub fn record(&self, record: T) -> io::Result<()> {
+        match &self.sender {
+            Some(sender) => sender
+                .send(WriterMessage::Record(record))
+                .map_err(|_| Self::stopped_error()),
+            None => Err(Self::stopped_error()),
+        }
+    }
+
+    /// wait until all sent records are written and flush the writer
+    pub fn flush(&self) -> io::Result<()> {
+        let sender = match &self.sender {
+            Some(sender) => sender,
+            None => return Err(Self::stopped_error()),
+        };
+        let (notifier, waiter) = sync_channel(1);
+        sender
+            .send(WriterMessage::Flush(notifier))
+            .map_err(|_| Self::stopped_error())?;
+        waiter.recv().map_err(|_| Self::stopped_error())?
+    }
+
+    /// write all records and stop background thread.
+    /// return error which is occurred in background thread or in the flush at the end of the run.
+    pub fn finish(mut self) -> io::Result<()> {
+        let result = self.shutdown();
+        match self.finalize_error.take() {
+            Some(error) => Err(error),
+            None => result,
+        }
+    }
+
+    /// close channel and join background thread
+    fn shutdown(&mut self) -> io::Result<()> {
+        // thread finish to write after all senders are dropped
+        self.sender.take();
+        match self.worker.take() {
+            Some(worker) => worker.join().unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "background writer is panicked",
+                ))
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Send + 'static> fmt::Debug for AsyncWriterRecorder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncWriterRecorder")
+            .field("running", &self.worker.is_some())
+            .finish()
+    }
+}
+
+impl<T: Send + 'static> FinalizeRecorder for AsyncWriterRecorder<T> {
+    fn finalize(&mut self, _report: &SimulationReport) {
+        if let Err(error) = self.flush() {
+            self.finalize_error.get_or_insert(error);
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for AsyncWriterRecorder<T> {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// running statistics of the samples which can be merged without keeping the samples
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Statistic {
+    count: u64,
+    mean: f64,
+    // sum of the squared differences from the mean
+    m2: f64,
+}
+
+impl Statistic {
+    /// initializer without the sample
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add the sample
+    pub fn record(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// count of the samples
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// mean of the samples. if no sample, then NaN.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            f64::NAN
+        } else {
+            self.mean
+        }
+    }
+
+    /// sample variance. if no sample, then NaN, and if only one sample, then 0.
+    pub fn variance(&self) -> f64 {
+        match self.count {
+            0 => f64::NAN,
+            1 => 0.0,
+            count => self.m2 / (count - 1) as f64,
+        }
+    }
+
+    /// sample standard deviation
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// confidence interval of the mean with the quantile of normal distribution, e.g. 1.96 for 95%
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let mean = self.mean();
+        let half_width = match self.count {
+            0 => f64::NAN,
+            count => z * self.std_dev() / (count as f64).sqrt(),
+        };
+        (mean - half_width, mean + half_width)
+    }
+}
+
+impl MergeRecorder for Statistic {
+    fn merge(&mut self, other: Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 +=
+            other.m2 + delta * delta * (self.count as f64 * other.count as f64) / count as f64;
+        self.count = count;
+    }
+}
+
+impl std::iter::FromIterator<f64> for Statistic {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        let mut statistic = Statistic::new();
+        for value in iter {
+            statistic.record(value);
+        }
+        statistic
+    }
+}
+
+/// summary of the metric at the frame across the replications
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSummary {
+    /// index of the frame
+    pub frame: FrameIndex,
+    /// count of the replications which have the value at the frame
+    pub count: u64,
+    /// mean of the values
+    pub mean: f64,
+    /// sample variance of the values
+    pub variance: f64,
+    /// lower bound of the confidence interval of the mean
+    pub ci_low: f64,
+    /// upper bound of the confidence interval of the mean
+    pub ci_high: f64,
+}
+
+/// merge the recorders in order into the first recorder. if no recorder, then None.
+pub fn merge_all<Rec, I>(recorders: I) -> Option<Rec>
+where
+    Rec: MergeRecorder,
+    I: IntoIterator<Item = Rec>,
+{
+    let mut recorders = recorders.into_iter();
+    let mut merged = recorders.next()?;
+    for recorder in recorders {
+        merged.merge(recorder);
+    }
+    Some(merged)
+}
+
+/// summarize the per-frame metric which is extracted from each recorder, in order of the frame.
+///
+/// the confidence interval use the quantile of normal distribution z, e.g. 1.96 for 95%.
+/// the metric should give at most one value for each frame.
+/// the frame which is missing in some recorders is summarized only with the recorders which have it.
+pub fn summarize<'a, Rec, I, F>(recorders: I, z: f64, metric: F) -> Vec<FrameSummary>
+where
+    Rec: 'a,
+    I: IntoIterator<Item = &'a Rec>,
+    F: Fn(&Rec) -> Vec<(FrameIndex, f64)>,
+{
+    let mut statistics: BTreeMap<FrameIndex, Statistic> = BTreeMap::new();
+    for recorder in recorders {
+        for (frame, value) in metric(recorder) {
+            statistics.entry(frame).or_default().record(value);
+        }
+    }
+    statistics
+        .into_iter()
+        .map(|(frame, statistic)| {
+            let (ci_low, ci_high) = statistic.confidence_interval(z);
+            FrameSummary {
+                frame,
+                count: statistic.count(),
+                mean: statistic.mean(),
+                variance: statistic.variance(),
+                ci_low,
+                ci_high,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,432 @@
+//! Narrative report of the run which is shared without the separate analysis pipeline
+
+use crate::annotation::Annotation;
+use crate::experiment::CellSummary;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// format of the rendered report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Markdown document. the plots are drawn as sparklines.
+    Markdown,
+    /// self-contained HTML document. the plots are drawn as inline SVG.
+    Html,
+}
+
+/// key performance indicator of the run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kpi {
+    /// name of the indicator
+    pub name: String,
+    /// value of the indicator
+    pub value: f64,
+    /// standard deviation of the value over the replications if the value is mean
+    pub std_dev: Option<f64>,
+    /// unit of the value. empty if no unit.
+    pub unit: String,
+}
+
+/// line plot of the series
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    /// title of the plot
+    pub title: String,
+    /// points of the line in order of x
+    pub points: Vec<(f64, f64)>,
+}
+
+/// builder of the report which render the manifest, KPIs, termination reason, annotations and plots into the single document.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScenarioReport {
+    title: String,
+    description: Option<String>,
+    manifest: BTreeMap<String, String>,
+    kpis: Vec<Kpi>,
+    termination: Option<String>,
+    annotations: Vec<Annotation>,
+    plots: Vec<Plot>,
+}
+
+impl ScenarioReport {
+    /// create builder with the title of the report
+    pub fn new(title: &str) -> Self {
+        ScenarioReport {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// free text which describe the scenario
+    pub fn description(mut self, text: &str) -> Self {
+        self.description = Some(text.to_string());
+        self
+    }
+
+    /// add the information about the run. e.g. `Replication::manifest` or `TraceHeader::manifest`.
+    pub fn manifest(mut self, manifest: &BTreeMap<String, String>) -> Self {
+        self.manifest.extend(
+            manifest
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        self
+    }
+
+    /// add one information about the run
+    pub fn manifest_entry(mut self, key: &str, value: &str) -> Self {
+        self.manifest.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// add the indicator with the unit. the unit can be empty.
+    pub fn kpi(mut self, name: &str, value: f64, unit: &str) -> Self {
+        self.kpis.push(Kpi {
+            name: name.to_string(),
+            value,
+            std_dev: None,
+            unit: unit.to_string(),
+        });
+        self
+    }
+
+    /// add the mean of the metric in each cell of the sweep as the indicators
+    pub fn cell_summaries(mut self, name: &str, summaries: &[CellSummary], unit: &str) -> Self {
+        for summary in summaries.iter() {
+            self.kpis.push(Kpi {
+                name: format!("{} ({})", name, summary.params),
+                value: summary.mean,
+                std_dev: Some(summary.std_dev),
+                unit: unit.to_string(),
+            });
+        }
+        self
+    }
+
+    /// reason why the run stopped. e.g. `control::StopReason`.
+    pub fn termination<T: fmt::Display>(mut self, reason: T) -> Self {
+        self.termination = Some(reason.to_string());
+        self
+    }
+
+    /// add the annotations of the run. e.g. `Simulator::annotations`.
+    pub fn annotations(mut self, annotations: &[Annotation]) -> Self {
+        self.annotations.extend_from_slice(annotations);
+        self
+    }
+
+    /// add the line plot of the points
+    pub fn plot(mut self, title: &str, points: Vec<(f64, f64)>) -> Self {
+        self.plots.push(Plot {
+            title: title.to_string(),
+            points,
+        });
+        self
+    }
+
+    /// add the line plot of the values which is indexed by the frame from 1
+    pub fn plot_frames(self, title: &str, values: &[f64]) -> Self {
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| ((index + 1) as f64, *value))
+            .collect();
+        self.plot(title, points)
+    }
+
+    /// render the report in the format
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    /// render the report as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}", self.title);
+        if let Some(description) = &self.description {
+            let _ = writeln!(out, "\n{}", description);
+        }
+        if let Some(termination) = &self.termination {
+            let _ = writeln!(out, "\n**Termination:** {}", termination);
+        }
+        if !self.manifest.is_empty() {
+            out.push_str("\n## Manifest\n\n| key | value |\n| --- | --- |\n");
+            for (key, value) in self.manifest.iter() {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} |",
+                    escape_markdown(key),
+                    escape_markdown(value)
+                );
+            }
+        }
+        if !self.kpis.is_empty() {
+            out.push_str("\n## KPIs\n\n| name | value | unit |\n| --- | ---: | --- |\n");
+            for kpi in self.kpis.iter() {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    escape_markdown(&kpi.name),
+                    format_kpi_value(kpi),
+                    escape_markdown(&kpi.unit)
+                );
+            }
+        }
+        if !self.annotations.is_empty() {
+            out.push_str("\n## Annotations\n\n");
+            for annotation in self.annotations.iter() {
+                let _ = writeln!(out, "- frame {}: {}", annotation.frame, annotation.text);
+            }
+        }
+        if !self.plots.is_empty() {
+            out.push_str("\n## Plots\n");
+            for plot in self.plots.iter() {
+                let _ = writeln!(out, "\n### {}\n", plot.title);
+                match bounds(&plot.points) {
+                    Some(((_, _), (low, high))) => {
+                        let _ = writeln!(
+                            out,
+                            "`{}` min {} / max {} ({} points)",
+                            sparkline(&plot.points, low, high),
+                            format_number(low),
+                            format_number(high),
+                            plot.points.len()
+                        );
+                    }
+                    None => out.push_str("no data\n"),
+                }
+            }
+        }
+        out
+    }
+
+    /// render the report as the self-contained HTML
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(out, "<title>{}</title>", escape_html(&self.title));
+        out.push_str(
+            "<style>\
+             body{font-family:sans-serif;margin:2em auto;max-width:60em;color:#222}\
+             table{border-collapse:collapse;margin:1em 0}\
+             th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}\
+             td.num{text-align:right;font-variant-numeric:tabular-nums}\
+             svg{border:1px solid #ccc;background:#fafafa}\
+             </style>\n</head>\n<body>\n",
+        );
+        let _ = writeln!(out, "<h1>{}</h1>", escape_html(&self.title));
+        if let Some(description) = &self.description {
+            let _ = writeln!(out, "<p>{}</p>", escape_html(description));
+        }
+        if let Some(termination) = &self.termination {
+            let _ = writeln!(
+                out,
+                "<p><strong>Termination:</strong> {}</p>",
+                escape_html(termination)
+            );
+        }
+        if !self.manifest.is_empty() {
+            out.push_str("<h2>Manifest</h2>\n<table>\n<tr><th>key</th><th>value</th></tr>\n");
+            for (key, value) in self.manifest.iter() {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    escape_html(key),
+                    escape_html(value)
+                );
+            }
+            out.push_str("</table>\n");
+        }
+        if !self.kpis.is_empty() {
+            out.push_str(
+                "<h2>KPIs</h2>\n<table>\n<tr><th>name</th><th>value</th><th>unit</th></tr>\n",
+            );
+            for kpi in self.kpis.iter() {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+                    escape_html(&kpi.name),
+                    escape_html(&format_kpi_value(kpi)),
+                    escape_html(&kpi.unit)
+                );
+            }
+            out.push_str("</table>\n");
+        }
+        if !self.annotations.is_empty() {
+            out.push_str("<h2>Annotations</h2>\n<ul>\n");
+            for annotation in self.annotations.iter() {
+                let _ = writeln!(
+                    out,
+                    "<li>frame {}: {}</li>",
+                    annotation.frame,
+                    escape_html(&annotation.text)
+                );
+            }
+            out.push_str("</ul>\n");
+        }
+        if !self.plots.is_empty() {
+            out.push_str("<h2>Plots</h2>\n");
+            for plot in self.plots.iter() {
+                let _ = writeln!(out, "<h3>{}</h3>", escape_html(&plot.title));
+                out.push_str(&svg_plot(&plot.points));
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    /// write the report in the format. existing file is overwritten.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P, format: ReportFormat) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.render(format))
+    }
+}
+
+/// value of the indicator with the standard deviation
+fn format_kpi_value(kpi: &Kpi) -> String {
+    match kpi.std_dev {
+        Some(std_dev) => format!("{} ± {}", format_number(kpi.value), format_number(std_dev)),
+        None => format_number(kpi.value),
+    }
+}
+
+/// number with at most 4 decimal places
+fn format_number(value: f64) -> String {
+    if value.is_finite() && value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value)
+    } else if value.is_finite() {
+        let text = format!("{:.4}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// range of x and range of y in the finite points
+fn bounds(points: &[(f64, f64)]) -> Option<((f64, f64), (f64, f64))> {
+    points
+        .iter()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .fold(None, |acc, (x, y)| match acc {
+            None => Some(((*x, *x), (*y, *y))),
+            Some(((x_low, x_high), (y_low, y_high))) => Some((
+                (x_low.min(*x), x_high.max(*x)),
+                (y_low.min(*y), y_high.max(*y)),
+            )),
+        })
+}
+
+/// sparkline of the values. the points are sampled to at most 60 characters.
+fn sparkline(points: &[(f64, f64)], low: f64, high: f64) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const WIDTH: usize = 60;
+    let values: Vec<f64> = points
+        .iter()
+        .map(|(_, y)| *y)
+        .filter(|y| y.is_finite())
+        .collect();
+    let step = ((values.len() + WIDTH - 1) / WIDTH).max(1);
+    values
+        .iter()
+        .step_by(step)
+        .map(|value| {
+            if high > low {
+                let level = ((value - low) / (high - low) * 7.0).round() as usize;
+                BARS[level.min(7)]
+            } else {
+                BARS[3]
+            }
+        })
+        .collect()
+}
+
+/// inline SVG of the line plot with the labels of the ranges
+fn svg_plot(points: &[(f64, f64)]) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 200.0;
+    const MARGIN: f64 = 40.0;
+    let ((x_low, x_high), (y_low, y_high)) = match bounds(points) {
+        Some(bounds) => bounds,
+        None => return "<p>no data</p>\n".to_string(),
+    };
+    let scale = |value: f64, low: f64, high: f64, length: f64| {
+        if high > low {
+            (value - low) / (high - low) * length
+        } else {
+            length / 2.0
+        }
+    };
+    let mut polyline = String::new();
+    for (x, y) in points
+        .iter()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+    {
+        let px = MARGIN + scale(*x, x_low, x_high, WIDTH - 2.0 * MARGIN);
+        let py = HEIGHT - MARGIN - scale(*y, y_low, y_high, HEIGHT - 2.0 * MARGIN);
+        let _ = write!(polyline, "{:.1},{:.1} ", px, py);
+    }
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(
+        out,
+        "<polyline fill=\"none\" stroke=\"#36c\" stroke-width=\"1.5\" points=\"{}\"/>",
+        polyline.trim_end()
+    );
+    let labels = [
+        (MARGIN, HEIGHT - MARGIN / 2.0, "start", format_number(x_low)),
+        (
+            WIDTH - MARGIN,
+            HEIGHT - MARGIN / 2.0,
+            "end",
+            format_number(x_high),
+        ),
+        (MARGIN - 4.0, HEIGHT - MARGIN, "end", format_number(y_low)),
+        (MARGIN - 4.0, MARGIN, "end", format_number(y_high)),
+    ];
+    for (x, y, anchor, text) in labels.iter() {
+        let _ = writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\" text-anchor=\"{}\">{}</text>",
+            x,
+            y,
+            anchor,
+            escape_html(text)
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// escape the text in HTML
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// escape the text in the cell of Markdown table
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
@@ -0,0 +1,744 @@
+//! Resources and stores which are shared by the requesters with the limited capacity
+
+use crate::event::{Event, EventScheduler, EventTimer, Priority, ScheduleEventError};
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// counts of the requests in the queueing component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueStats {
+    /// count of the requests
+    pub requested: usize,
+    /// count of the requests which are granted
+    pub granted: usize,
+    /// count of the requests which wait in the queue
+    pub queued: usize,
+    /// count of the requests which refuse to join the queue
+    pub balked: usize,
+    /// count of the requests which leave the queue after waiting too long
+    pub reneged: usize,
+    /// count of the holders which are interrupted
+    pub preempted: usize,
+}
+
+/// result of the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestOutcome<T> {
+    /// the request is granted
+    Granted,
+    /// the unit is granted with interrupting the holder.
+    ///
+    /// args is the preempted holder.
+    Preempted(T),
+    /// the request wait in the queue
+    Queued,
+    /// the request refuse to join the queue because the queue is too long
+    Balked,
+}
+
+/// order of the waiting requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueueDiscipline {
+    /// from the highest priority and in requested order for the same priority
+    Priority,
+    /// in requested order regardless of the priority
+    Fifo,
+}
+
+impl Default for QueueDiscipline {
+    fn default() -> Self {
+        QueueDiscipline::Priority
+    }
+}
+
+/// request which wait in the queue
+trait Waiting {
+    type Requester: PartialEq;
+
+    fn requester(&self) -> &Self::Requester;
+
+    fn priority(&self) -> Priority;
+}
+
+/// queue of the waiting requests which is ordered by the discipline
+#[derive(Debug, Clone)]
+struct WaitQueue<W> {
+    requests: Vec<W>,
+    balk_limit: Option<usize>,
+    discipline: QueueDiscipline,
+}
+
+impl<W: Waiting> WaitQueue<W> {
+    fn new() -> Self {
+        WaitQueue {
+            requests: vec![],
+            balk_limit: None,
+            discipline: QueueDiscipline::Priority,
+        }
+    }
+
+    /// judge the new request refuse to join
+    fn is_balking(&self) -> bool {
+        self.balk_limit
+            .map_or(false, |limit| self.requests.len() >= limit)
+    }
+
+    /// insert the request after the requests which have the same or higher priority.
+    /// if the discipline is FIFO, insert at the last.
+    fn enqueue(&mut self, request: W) {
+        if self.discipline == QueueDiscipline::Fifo {
+            self.requests.push(request);
+            return;
+        }
+        let index = self
+            .requests
+            .iter()
+            .position(|waiting| waiting.priority() < request.priority())
+            .unwrap_or(self.requests.len());
+        self.requests.insert(index, request);
+    }
+
+    fn dequeue(&mut self) -> Option<W> {
+        if self.requests.is_empty() {
+            None
+        } else {
+            Some(self.requests.remove(0))
+        }
+    }
+
+    fn remove(&mut self, requester: &W::Requester) -> bool {
+        match self
+            .requests
+            .iter()
+            .position(|request| request.requester() == requester)
+        {
+            Some(index) => {
+                self.requests.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn contains(&self, requester: &W::Requester) -> bool {
+        self.requests
+            .iter()
+            .any(|request| request.requester() == requester)
+    }
+}
+
+/// schedule the reneging event of the request which start waiting
+fn schedule_renege<R: Rng + ?Sized, E: Event + Clone>(
+    rng: &mut R,
+    scheduler: &mut EventScheduler<E>,
+    priority: Priority,
+    renege: &Option<(EventTimer, E)>,
+) -> Result<(), ScheduleEventError> {
+    if let Some((timer, event)) = renege {
+        scheduler.timeout(rng, timer.clone(), priority, event.clone())?;
+    }
+    Ok(())
+}
+
+//
+// resource
+//
+
+/// request for the unit of the resource
+#[derive(Debug, Clone)]
+pub struct ResourceRequest<T, E: Event> {
+    requester: T,
+    priority: Priority,
+    on_granted: E,
+    on_preempted: Option<E>,
+    preemptive: bool,
+    renege: Option<(EventTimer, E)>,
+}
+
+impl<T, E: Event> ResourceRequest<T, E> {
+    /// create request which fire the event with the priority when the unit is granted
+    pub fn new(requester: T, priority: Priority, on_granted: E) -> Self {
+        ResourceRequest {
+            requester,
+            priority,
+            on_granted,
+            on_preempted: None,
+            preemptive: false,
+            renege: None,
+        }
+    }
+
+    /// interrupt the holder which has lower priority than this request when no unit is available
+    pub fn preemptive(mut self) -> Self {
+        self.preemptive = true;
+        self
+    }
+
+    /// event which is fired with the priority when this requester is preempted while holding
+    pub fn on_preempted(mut self, event: E) -> Self {
+        self.on_preempted = Some(event);
+        self
+    }
+
+    /// event which is fired after the timeout when the request start waiting.
+    /// handle the event with calling Resource::renege.
+    pub fn renege_after(mut self, timeout: EventTimer, event: E) -> Self {
+        self.renege = Some((timeout, event));
+        self
+    }
+
+    /// getter for requester
+    pub fn requester(&self) -> &T {
+        &self.requester
+    }
+
+    /// getter for priority
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl<T: PartialEq, E: Event> Waiting for ResourceRequest<T, E> {
+    type Requester = T;
+
+    fn requester(&self) -> &T {
+        &self.requester
+    }
+
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+/// resource which has the units with the capacity.
+///
+/// the waiting requests are granted from the highest priority and in requested order for the same priority,
+/// or in requested order with QueueDiscipline::Fifo.
+/// when the unit is granted, the granted event of the request is scheduled as immediate.
+#[derive(Debug, Clone)]
+pub struct Resource<T, E: Event> {
+    capacity: usize,
+    holders: Vec<ResourceRequest<T, E>>,
+    waiting: WaitQueue<ResourceRequest<T, E>>,
+    stats: QueueStats,
+}
+
+impl<T: PartialEq, E: Event + Clone> Resource<T, E> {
+    /// create resource with the count of units
+    pub fn new(capacity: usize) -> Self {
+        Resource {
+            capacity,
+            holders: vec![],
+            waiting: WaitQueue::new(),
+            stats: QueueStats::default(),
+        }
+    }
+
+    /// refuse the request to join the queue when the count of the waiting requests is the limit or more
+    pub fn with_balk_limit(mut self, limit: usize) -> Self {
+        self.waiting.balk_limit = Some(limit);
+        self
+    }
+
+    /// change the limit of the queue length for balking. None is no limit.
+    pub fn set_balk_limit(&mut self, limit: Option<usize>) {
+        self.waiting.balk_limit = limit;
+    }
+
+    /// order the waiting requests by the discipline. the requests which already wait are kept in the order.
+    pub fn with_discipline(mut self, discipline: QueueDiscipline) -> Self {
+        self.waiting.discipline = discipline;
+        self
+    }
+
+    /// getter for the order of the waiting requests
+    pub fn discipline(&self) -> QueueDiscipline {
+        self.waiting.discipline
+    }
+
+    /// count of units
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// count of units which are held
+    pub fn in_use(&self) -> usize {
+        self.holders.len()
+    }
+
+    /// count of units which are not held
+    pub fn available(&self) -> usize {
+        self.capacity.saturating_sub(self.holders.len())
+    }
+
+    /// count of waiting requests
+    pub fn queue_len(&self) -> usize {
+        self.waiting.requests.len()
+    }
+
+    /// count of preemptions which occurred
+    pub fn preemptions(&self) -> usize {
+        self.stats.preempted
+    }
+
+    /// counts of the requests
+    pub fn stats(&self) -> &QueueStats {
+        &self.stats
+    }
+
+    /// requesters which hold the unit in granted order
+    pub fn holders(&self) -> impl Iterator<Item = &T> {
+        self.holders.iter().map(|request| &request.requester)
+    }
+
+    /// requesters which wait in the granted order
+    pub fn waiting(&self) -> impl Iterator<Item = &T> {
+        self.waiting
+            .requests
+            .iter()
+            .map(|request| &request.requester)
+    }
+
+    /// judge the requester hold the unit
+    pub fn is_holding(&self, requester: &T) -> bool {
+        self.holders
+            .iter()
+            .any(|request| &request.requester == requester)
+    }
+
+    /// judge the requester wait in the queue
+    pub fn is_waiting(&self, requester: &T) -> bool {
+        self.waiting.contains(requester)
+    }
+
+    /// request the unit.
+    /// if no unit is available and the request is preemptive,
+    /// the holder which has the lowest priority lower than the request is interrupted.
+    /// the preempted holder is not queued again, and the preempted event of the holder is scheduled as immediate.
+    /// if the request wait and has the reneging event, the event is scheduled.
+    pub fn request<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        request: ResourceRequest<T, E>,
+    ) -> Result<RequestOutcome<T>, ScheduleEventError> {
+        self.stats.requested += 1;
+        if self.available() > 0 {
+            self.grant(rng, scheduler, request)?;
+            return Ok(RequestOutcome::Granted);
+        }
+
+        if request.preemptive {
+            // the last one is preempted in the holders of the lowest priority
+            let victim = self
+                .holders
+                .iter()
+                .enumerate()
+                .filter(|(_, holder)| holder.priority < request.priority)
+                .min_by(|(i1, h1), (i2, h2)| h1.priority.cmp(&h2.priority).then(i2.cmp(i1)))
+                .map(|(index, _)| index);
+            if let Some(index) = victim {
+                let victim = self.holders.remove(index);
+                self.stats.preempted += 1;
+                if let Some(event) = victim.on_preempted {
+                    scheduler.immediate(rng, victim.priority, event)?;
+                }
+                self.grant(rng, scheduler, request)?;
+                return Ok(RequestOutcome::Preempted(victim.requester));
+            }
+        }
+
+        if self.waiting.is_balking() {
+            self.stats.balked += 1;
+            return Ok(RequestOutcome::Balked);
+        }
+        schedule_renege(rng, scheduler, request.priority, &request.renege)?;
+        self.stats.queued += 1;
+        self.waiting.enqueue(request);
+        Ok(RequestOutcome::Queued)
+    }
+
+    /// release the unit which the requester hold and grant the unit to the waiting requests.
+    /// return false when the requester does not hold the unit.
+    pub fn release<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        requester: &T,
+    ) -> Result<bool, ScheduleEventError> {
+        match self
+            .holders
+            .iter()
+            .position(|request| &request.requester == requester)
+        {
+            Some(index) => {
+                self.holders.remove(index);
+                self.grant_waiting(rng, scheduler)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// remove the waiting request of the requester. return false when the requester does not wait.
+    pub fn cancel(&mut self, requester: &T) -> bool {
+        self.waiting.remove(requester)
+    }
+
+    /// leave the queue by the reneging event.
+    /// return false when the requester does not wait, e.g. already granted.
+    pub fn renege(&mut self, requester: &T) -> bool {
+        let reneged = self.waiting.remove(requester);
+        if reneged {
+            self.stats.reneged += 1;
+        }
+        reneged
+    }
+
+    /// change the count of units. the waiting requests are granted if units are added.
+    /// the holders are not interrupted even if units are removed.
+    pub fn set_capacity<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        capacity: usize,
+    ) -> Result<(), ScheduleEventError> {
+        self.capacity = capacity;
+        self.grant_waiting(rng, scheduler)
+    }
+
+    /// hold the unit and schedule the granted event
+    fn grant<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        request: ResourceRequest<T, E>,
+    ) -> Result<(), ScheduleEventError> {
+        scheduler.immediate(rng, request.priority, request.on_granted.clone())?;
+        self.stats.granted += 1;
+        self.holders.push(request);
+        Ok(())
+    }
+
+    /// grant the units to the waiting requests while units are available
+    fn grant_waiting<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+    ) -> Result<(), ScheduleEventError> {
+        while self.available() > 0 {
+            match self.waiting.dequeue() {
+                Some(request) => self.grant(rng, scheduler, request)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+//
+// store
+//
+
+/// request for the item in the store
+#[derive(Debug, Clone)]
+pub struct StoreRequest<G, E: Event> {
+    getter: G,
+    priority: Priority,
+    batch: usize,
+    renege: Option<(EventTimer, E)>,
+}
+
+impl<G, E: Event> StoreRequest<G, E> {
+    /// create request which receive the item with the priority
+    pub fn new(getter: G, priority: Priority) -> Self {
+        StoreRequest {
+            getter,
+            priority,
+            batch: 1,
+            renege: None,
+        }
+    }
+
+    /// receive up to the count of items at once.
+    /// the items are handed by the batch received event of the store.
+    /// if the store does not have the batch received event, one item is received.
+    pub fn batch(mut self, max: usize) -> Self {
+        self.batch = max.max(1);
+        self
+    }
+
+    /// event which is fired after the timeout when the request start waiting.
+    /// handle the event with calling Store::renege.
+    pub fn renege_after(mut self, timeout: EventTimer, event: E) -> Self {
+        self.renege = Some((timeout, event));
+        self
+    }
+
+    /// getter for getter
+    pub fn getter(&self) -> &G {
+        &self.getter
+    }
+
+    /// getter for priority
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl<G: PartialEq, E: Event> Waiting for StoreRequest<G, E> {
+    type Requester = G;
+
+    fn requester(&self) -> &G {
+        &self.getter
+    }
+
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+/// store which keep the items in put order and hand them to the getters.
+///
+/// when the item is handed, the event which is made from the getter and the item is scheduled as immediate.
+/// the waiting getters receive the items from the highest priority and in requested order for the same priority,
+/// or in requested order with QueueDiscipline::Fifo.
+#[derive(Debug, Clone)]
+pub struct Store<G, T, E: Event> {
+    capacity: Option<usize>,
+    items: VecDeque<T>,
+    waiting: WaitQueue<StoreRequest<G, E>>,
+    on_received: fn(G, T) -> E,
+    on_batch_received: Option<fn(G, Vec<T>) -> E>,
+    accepted: usize,
+    delivered: usize,
+    rejected: usize,
+    stats: QueueStats,
+}
+
+impl<G: PartialEq, T, E: Event + Clone> Store<G, T, E> {
+    /// create store without the limit of items
+    pub fn new(on_received: fn(G, T) -> E) -> Self {
+        Store {
+            capacity: None,
+            items: VecDeque::new(),
+            waiting: WaitQueue::new(),
+            on_received,
+            on_batch_received: None,
+            accepted: 0,
+            delivered: 0,
+            rejected: 0,
+            stats: QueueStats::default(),
+        }
+    }
+
+    /// create store which keep the items up to the capacity
+    pub fn with_capacity(capacity: usize, on_received: fn(G, T) -> E) -> Self {
+        let mut store = Self::new(on_received);
+        store.capacity = Some(capacity);
+        store
+    }
+
+    /// event which is made from the getter and the items for the batch request
+    pub fn with_batch_received(mut self, on_batch_received: fn(G, Vec<T>) -> E) -> Self {
+        self.on_batch_received = Some(on_batch_received);
+        self
+    }
+
+    /// refuse the request to join the queue when the count of the waiting requests is the limit or more
+    pub fn with_balk_limit(mut self, limit: usize) -> Self {
+        self.waiting.balk_limit = Some(limit);
+        self
+    }
+
+    /// change the limit of the queue length for balking. None is no limit.
+    pub fn set_balk_limit(&mut self, limit: Option<usize>) {
+        self.waiting.balk_limit = limit;
+    }
+
+    /// order the waiting requests by the discipline. the requests which already wait are kept in the order.
+    pub fn with_discipline(mut self, discipline: QueueDiscipline) -> Self {
+        self.waiting.discipline = discipline;
+        self
+    }
+
+    /// getter for the order of the waiting requests
+    pub fn discipline(&self) -> QueueDiscipline {
+        self.waiting.discipline
+    }
+
+    /// count of the stored items
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// judge no item is stored
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// stored items in put order
+    pub fn items(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
+    /// count of waiting requests
+    pub fn queue_len(&self) -> usize {
+        self.waiting.requests.len()
+    }
+
+    /// count of the items which are accepted
+    pub fn accepted(&self) -> usize {
+        self.accepted
+    }
+
+    /// count of the items which are handed to the getters
+    pub fn delivered(&self) -> usize {
+        self.delivered
+    }
+
+    /// count of the items which are rejected because the store is full
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// mean count of the items which are handed at once. if nothing is handed, then None.
+    pub fn mean_batch_size(&self) -> Option<f64> {
+        if self.stats.granted == 0 {
+            None
+        } else {
+            Some(self.delivered as f64 / self.stats.granted as f64)
+        }
+    }
+
+    /// counts of the requests for the items
+    pub fn stats(&self) -> &QueueStats {
+        &self.stats
+    }
+
+    /// judge the getter wait in the queue
+    pub fn is_waiting(&self, getter: &G) -> bool {
+        self.waiting.contains(getter)
+    }
+
+    /// put the item. if the getter wait, the item is handed to the getter.
+    /// return false when the store is full and the item is rejected.
+    pub fn put<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        item: T,
+    ) -> Result<bool, ScheduleEventError> {
+        self.put_batch(rng, scheduler, Some(item))
+            .map(|accepted| accepted == 1)
+    }
+
+    /// put the items which arrive at once. the waiting getters receive the items first,
+    /// and the items over the capacity are rejected from the last.
+    /// return the count of the accepted items.
+    pub fn put_batch<R: Rng + ?Sized, I>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        items: I,
+    ) -> Result<usize, ScheduleEventError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let count = self.items.len();
+        self.items.extend(items);
+        let arrived = self.items.len() - count;
+        self.dispatch(rng, scheduler)?;
+
+        let mut rejected = 0;
+        if let Some(capacity) = self.capacity {
+            while self.items.len() > capacity && rejected < arrived {
+                self.items.pop_back();
+                rejected += 1;
+            }
+        }
+        self.rejected += rejected;
+        self.accepted += arrived - rejected;
+        Ok(arrived - rejected)
+    }
+
+    /// request the items. if the item is stored, the items are handed to the getter.
+    /// if the request wait and has the reneging event, the event is scheduled.
+    pub fn get<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        request: StoreRequest<G, E>,
+    ) -> Result<RequestOutcome<G>, ScheduleEventError> {
+        self.stats.requested += 1;
+        if !self.items.is_empty() {
+            self.hand(rng, scheduler, request)?;
+            return Ok(RequestOutcome::Granted);
+        }
+
+        if self.waiting.is_balking() {
+            self.stats.balked += 1;
+            return Ok(RequestOutcome::Balked);
+        }
+        schedule_renege(rng, scheduler, request.priority, &request.renege)?;
+        self.stats.queued += 1;
+        self.waiting.enqueue(request);
+        Ok(RequestOutcome::Queued)
+    }
+
+    /// remove the waiting request of the getter. return false when the getter does not wait.
+    pub fn cancel(&mut self, getter: &G) -> bool {
+        self.waiting.remove(getter)
+    }
+
+    /// leave the queue by the reneging event.
+    /// return false when the getter does not wait, e.g. already received.
+    pub fn renege(&mut self, getter: &G) -> bool {
+        let reneged = self.waiting.remove(getter);
+        if reneged {
+            self.stats.reneged += 1;
+        }
+        reneged
+    }
+
+    /// hand the items to the waiting getters while the items are stored
+    fn dispatch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+    ) -> Result<(), ScheduleEventError> {
+        while !self.items.is_empty() {
+            match self.waiting.dequeue() {
+                Some(request) => self.hand(rng, scheduler, request)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// hand the first items to the getter with scheduling the received event
+    fn hand<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        scheduler: &mut EventScheduler<E>,
+        request: StoreRequest<G, E>,
+    ) -> Result<(), ScheduleEventError> {
+        let event = match self.on_batch_received {
+            Some(on_batch_received) if request.batch > 1 => {
+                let count = request.batch.min(self.items.len());
+                let items: Vec<T> = self.items.drain(..count).collect();
+                self.delivered += items.len();
+                on_batch_received(request.getter, items)
+            }
+            _ => {
+                // the caller check that the item is stored
+                let item = self.items.pop_front().unwrap();
+                self.delivered += 1;
+                (self.on_received)(request.getter, item)
+            }
+        };
+        scheduler.immediate(rng, request.priority, event)?;
+        self.stats.granted += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+//! Routing policies which connect the queueing components into the network
+
+use crate::event::{Event, Priority};
+use crate::resources::{Resource, Store};
+use rand::Rng;
+
+/// queueing component which tell the length for the shortest-queue routing
+pub trait QueueLength {
+    /// count of the entities in the component
+    fn queue_length(&self) -> usize;
+}
+
+impl<T: PartialEq, E: Event + Clone> QueueLength for Resource<T, E> {
+    /// count of the holders and the waiting requests
+    fn queue_length(&self) -> usize {
+        self.in_use() + self.queue_len()
+    }
+}
+
+impl<G: PartialEq, T, E: Event + Clone> QueueLength for Store<G, T, E> {
+    /// count of the stored items
+    fn queue_length(&self) -> usize {
+        self.len()
+    }
+}
+
+/// policy which select the route of the entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutingPolicy {
+    /// select the route at random in proportion to the weight of the route
+    Probabilistic,
+    /// select the route which has the shortest queue. the first declared route is selected for the same length.
+    ShortestQueue,
+    /// select the routes in declared order
+    RoundRobin,
+    /// select the route which has the class of the entity.
+    /// if no route has the class, the route without the class is selected.
+    PriorityClass,
+}
+
+/// route to the destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route<D> {
+    destination: D,
+    weight: f64,
+    class: Option<Priority>,
+    flow: usize,
+}
+
+impl<D> Route<D> {
+    /// create route to the destination with the weight 1
+    pub fn new(destination: D) -> Self {
+        Route {
+            destination,
+            weight: 1.0,
+            class: None,
+            flow: 0,
+        }
+    }
+
+    /// weight for the probabilistic routing. the negative weight is treated as 0.
+    pub fn weight(mut self, weight: f64) -> Self {
+        self.weight = weight.max(0.0);
+        self
+    }
+
+    /// class of the entities for the priority-class routing
+    pub fn class(mut self, class: Priority) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// getter for destination
+    pub fn get_destination(&self) -> &D {
+        &self.destination
+    }
+
+    /// count of the entities which are routed
+    pub fn flow(&self) -> usize {
+        self.flow
+    }
+}
+
+/// router which select one of the routes by the policy and count the flow of each route.
+///
+/// the destination is the identifier of the component (e.g. index or name of the resource),
+/// and the destination can be the other router for the network of the routers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Router<D> {
+    policy: RoutingPolicy,
+    routes: Vec<Route<D>>,
+    next: usize,
+    unrouted: usize,
+}
+
+impl<D> Router<D> {
+    /// create router without route
+    pub fn new(policy: RoutingPolicy) -> Self {
+        Router {
+            policy,
+            routes: vec![],
+            next: 0,
+            unrouted: 0,
+        }
+    }
+
+    /// add the route
+    pub fn route(mut self, route: Route<D>) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    /// getter for policy
+    pub fn get_policy(&self) -> RoutingPolicy {
+        self.policy
+    }
+
+    /// routes in declared order
+    pub fn routes(&self) -> &[Route<D>] {
+        &self.routes
+    }
+
+    /// count of the entities which are routed
+    pub fn routed(&self) -> usize {
+        self.routes.iter().map(|route| route.flow).sum()
+    }
+
+    /// count of the entities which are not routed because no route is selectable
+    pub fn unrouted(&self) -> usize {
+        self.unrouted
+    }
+
+    /// ratio of the flow of the route in the routed entities. if nothing is routed, then None.
+    pub fn share(&self, index: usize) -> Option<f64> {
+        let routed = self.routed();
+        if routed == 0 {
+            return None;
+        }
+        self.routes
+            .get(index)
+            .map(|route| route.flow as f64 / routed as f64)
+    }
+
+    /// reset the flows of the routes and the round robin position
+    pub fn reset_flows(&mut self) {
+        for route in self.routes.iter_mut() {
+            route.flow = 0;
+        }
+        self.next = 0;
+        self.unrouted = 0;
+    }
+
+    /// select the route of the entity which has the class and count the flow.
+    ///
+    /// queue_length tell the length of the destination for the shortest-queue routing.
+    /// return None when no route is selectable.
+    pub fn select<R, F>(&mut self, rng: &mut R, class: Priority, queue_length: F) -> Option<&D>
+    where
+        R: Rng + ?Sized,
+        F: Fn(&D) -> usize,
+    {
+        let selected = match self.policy {
+            RoutingPolicy::Probabilistic => self.select_probabilistic(rng),
+            RoutingPolicy::ShortestQueue => self
+                .routes
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, route)| queue_length(&route.destination))
+                .map(|(index, _)| index),
+            RoutingPolicy::RoundRobin => self.select_round_robin(),
+            RoutingPolicy::PriorityClass => self
+                .routes
+                .iter()
+                .position(|route| route.class == Some(class))
+                .or_else(|| self.routes.iter().position(|route| route.class.is_none())),
+        };
+
+        match selected {
+            Some(index) => {
+                let route = &mut self.routes[index];
+                route.flow += 1;
+                Some(&route.destination)
+            }
+            None => {
+                self.unrouted += 1;
+                None
+            }
+        }
+    }
+
+    /// select the route of the entity which is routed without the class and the queue length
+    pub fn select_simple<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<&D> {
+        self.select(rng, Priority::MIN, |_| 0)
+    }
+
+    fn select_probabilistic<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let total: f64 = self.routes.iter().map(|route| route.weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut point = rng.gen::<f64>() * total;
+        for (index, route) in self.routes.iter().enumerate() {
+            if route.weight <= 0.0 {
+                continue;
+            }
+            if point < route.weight {
+                return Some(index);
+            }
+            point -= route.weight;
+        }
+        // rounding error of the sum
+        self.routes.iter().rposition(|route| route.weight > 0.0)
+    }
+
+    fn select_round_robin(&mut self) -> Option<usize> {
+        if self.routes.is_empty() {
+            return None;
+        }
+        let index = self.next % self.routes.len();
+        self.next = (index + 1) % self.routes.len();
+        Some(index)
+    }
+}
@@ -0,0 +1,323 @@
+//! Generator of the starter project which use this library
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// way to handle the fired events in the generated model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handling {
+    /// implement StepEachEvent
+    EachEvent,
+    /// implement BulkEvents
+    InBulk,
+}
+
+/// generated file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldFile {
+    /// relative path from the project root
+    pub path: PathBuf,
+    /// contents of the file
+    pub contents: String,
+}
+
+/// builder of the starter project which wire model, event, recorder and replications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scaffold {
+    name: String,
+    dependency: String,
+    handling: Handling,
+    agents: bool,
+    metrics: bool,
+    replications: Option<usize>,
+    frames: u64,
+}
+
+impl Scaffold {
+    /// create builder for the project with the package name
+    pub fn new(name: &str) -> Self {
+        Scaffold {
+            name: name.to_string(),
+            dependency: format!("\"{}\"", env!("CARGO_PKG_VERSION")),
+            handling: Handling::EachEvent,
+            agents: false,
+            metrics: false,
+            replications: None,
+            frames: 100,
+        }
+    }
+
+    /// change the value of the dependency in Cargo.toml. e.g. `{ path = "../sim-by-fired-event" }`
+    pub fn dependency(mut self, value: &str) -> Self {
+        self.dependency = value.to_string();
+        self
+    }
+
+    /// way to handle the fired events. ignored when use agents.
+    pub fn handling(mut self, handling: Handling) -> Self {
+        self.handling = handling;
+        self
+    }
+
+    /// use agents::AgentSimulator instead of the model
+    pub fn agents(mut self, agents: bool) -> Self {
+        self.agents = agents;
+        self
+    }
+
+    /// recorder which count the fired events and print the summary
+    pub fn metrics(mut self, metrics: bool) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// run the replications with the count instead of the single run
+    pub fn replications(mut self, count: usize) -> Self {
+        self.replications = Some(count);
+        self
+    }
+
+    /// count of frames for each run
+    pub fn frames(mut self, frames: u64) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// generate the files of the project
+    pub fn files(&self) -> Vec<ScaffoldFile> {
+        vec![
+            ScaffoldFile {
+                path: PathBuf::from("Cargo.toml"),
+                contents: self.cargo_toml(),
+            },
+            ScaffoldFile {
+                path: PathBuf::from("src").join("main.rs"),
+                contents: if self.agents {
+                    self.agents_main()
+                } else {
+                    self.model_main()
+                },
+            },
+        ]
+    }
+
+    /// write the files of the project into the directory. existing files are overwritten.
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        for file in self.files() {
+            let path = dir.as_ref().join(&file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, file.contents)?;
+        }
+        Ok(())
+    }
+
+    fn cargo_toml(&self) -> String {
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[dependencies]\nrand = \"0.8\"\nsim-by-fired-event = {}\n",
+            self.name, self.dependency
+        )
+    }
+
+    fn recorder(&self) -> &'static str {
+        if self.metrics {
+            "/// recorder which count the fired events\n\
+             #[derive(Debug, Clone, Default)]\n\
+             struct Recorder {\n    fired: BTreeMap<String, u64>,\n}\n\n\
+             impl Recorder {\n    fn count(&mut self, label: &str) {\n        *self.fired.entry(label.to_string()).or_insert(0) += 1;\n    }\n\n    \
+             fn print_summary(&self) {\n        for (label, count) in self.fired.iter() {\n            println!(\"{}: {}\", label, count);\n        }\n    }\n}\n"
+        } else {
+            "/// recorder which keep the log of the simulation\n\
+             #[derive(Debug, Clone, Default)]\n\
+             struct Recorder {\n    log: Vec<String>,\n}\n"
+        }
+    }
+
+    fn record(&self, label: &str) -> String {
+        if self.metrics {
+            format!("recorder.count(\"{}\");", label)
+        } else {
+            format!("recorder.log.push(\"{}\".to_string());", label)
+        }
+    }
+
+    fn model_main(&self) -> String {
+        let mut out = String::new();
+        let step_trait = match self.handling {
+            Handling::EachEvent => "StepEachEvent",
+            Handling::InBulk => "BulkEvents",
+        };
+        let _ = writeln!(out, "use rand::Rng;");
+        if self.replications.is_some() {
+            let _ = writeln!(
+                out,
+                "use sim_by_fired_event::ensemble::{{run_replications, SeedStrategy}};"
+            );
+        } else {
+            let _ = writeln!(out, "use rand::thread_rng;");
+        }
+        let _ = writeln!(
+            out,
+            "use sim_by_fired_event::event::{{Event, EventScheduler, EventTimer, Priority}};"
+        );
+        let _ = writeln!(
+            out,
+            "use sim_by_fired_event::model::{{Model, {}}};",
+            step_trait
+        );
+        let _ = writeln!(out, "use sim_by_fired_event::Simulator;");
+        if self.metrics {
+            let _ = writeln!(out, "use std::collections::BTreeMap;");
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(out, "const FRAMES: u64 = {};", self.frames);
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "/// events of the model\n#[derive(Debug, Clone)]\nenum MyEvent {{\n    Arrive,\n    Depart,\n}}\n\nimpl Event for MyEvent {{}}\n"
+        );
+        let _ = writeln!(out, "{}", self.recorder());
+        let _ = writeln!(
+            out,
+            "/// state of the model\n#[derive(Debug, Clone, Default)]\nstruct MyModel {{\n    in_system: u64,\n}}\n"
+        );
+        let _ = writeln!(
+            out,
+            "impl Model<Recorder> for MyModel {{\n    type ModelEvent = MyEvent;\n\n    \
+             fn initialize<R: Rng + ?Sized>(\n        &mut self,\n        rng: &mut R,\n        _recorder: &mut Recorder,\n        scheduler: &mut EventScheduler<Self::ModelEvent>,\n    ) {{\n        \
+             scheduler\n            .every_interval(rng, EventTimer::Uniform(1, 5, true), Priority::NORMAL, MyEvent::Arrive)\n            .unwrap();\n    }}\n\n    \
+             fn start_frame(&mut self, _recorder: &mut Recorder) {{}}\n\n    \
+             fn finish_frame(&mut self, _recorder: &mut Recorder) {{}}\n}}\n"
+        );
+        let handle = format!(
+            "match fired_event {{\n{i}    MyEvent::Arrive => {{\n{i}        {arrive}\n{i}        self.in_system += 1;\n{i}        scheduler\n{i}            .timeout(rng, EventTimer::Uniform(1, 4, true), priority, MyEvent::Depart)\n{i}            .unwrap();\n{i}    }}\n{i}    MyEvent::Depart => {{\n{i}        {depart}\n{i}        self.in_system -= 1;\n{i}    }}\n{i}}}",
+            i = match self.handling {
+                Handling::EachEvent => "        ",
+                Handling::InBulk => "            ",
+            },
+            arrive = self.record("Arrive"),
+            depart = self.record("Depart"),
+        );
+        match self.handling {
+            Handling::EachEvent => {
+                let _ = writeln!(
+                    out,
+                    "impl StepEachEvent<Recorder, MyEvent> for MyModel {{\n    \
+                     fn step_each_event<R: Rng + ?Sized>(\n        &mut self,\n        rng: &mut R,\n        recorder: &mut Recorder,\n        scheduler: &mut EventScheduler<Self::ModelEvent>,\n        priority: Priority,\n        fired_event: Self::ModelEvent,\n    ) {{\n        {}\n    }}\n}}\n",
+                    handle
+                );
+            }
+            Handling::InBulk => {
+                let _ = writeln!(
+                    out,
+                    "impl BulkEvents<Recorder, MyEvent> for MyModel {{\n    \
+                     fn step_in_bulk<R: Rng + ?Sized>(\n        &mut self,\n        rng: &mut R,\n        recorder: &mut Recorder,\n        scheduler: &mut EventScheduler<Self::ModelEvent>,\n        fired_events: Vec<(Priority, Self::ModelEvent)>,\n    ) {{\n        \
+                     for (priority, fired_event) in fired_events {{\n            {}\n        }}\n    }}\n}}\n",
+                    handle
+                );
+            }
+        }
+        let run = match self.handling {
+            Handling::EachEvent => "run_n_each_event",
+            Handling::InBulk => "run_n_in_bulk_event",
+        };
+        let summary = if self.metrics {
+            "recorder.print_summary();"
+        } else {
+            "println!(\"{} records\", recorder.log.len());"
+        };
+        match self.replications {
+            Some(count) => {
+                let _ = writeln!(
+                    out,
+                    "fn main() {{\n    let replications = run_replications(\n        {},\n        SeedStrategy::Sequential(0),\n        |_| MyModel::default(),\n        |_| Recorder::default(),\n        |simulator: &mut Simulator<MyModel, MyEvent, Recorder>, rng| {{\n            simulator.{}(rng, FRAMES);\n        }},\n    )\n    .expect(\"sequential seeds are given for any replications\");\n    \
+                     for replication in replications.iter() {{\n        println!(\"replication {{}} (seed {{}}): in system {{}}\", replication.index, replication.seed, replication.model.in_system);\n        let recorder = &replication.recorder;\n        {}\n    }}\n}}",
+                    count, run, summary
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "fn main() {{\n    let mut rng = thread_rng();\n    let mut simulator = Simulator::create_from(&mut rng, MyModel::default(), Recorder::default());\n    simulator.{}(&mut rng, FRAMES);\n\n    \
+                     println!(\"in system: {{}}\", simulator.get_model().in_system);\n    let recorder = simulator.get_recorder();\n    {}\n}}",
+                    run, summary
+                );
+            }
+        }
+        out
+    }
+
+    fn agents_main(&self) -> String {
+        let mut out = String::new();
+        if self.replications.is_some() {
+            let _ = writeln!(out, "use rand::rngs::StdRng;");
+            let _ = writeln!(out, "use rand::SeedableRng;");
+            let _ = writeln!(out, "use sim_by_fired_event::ensemble::SeedStrategy;");
+        } else {
+            let _ = writeln!(out, "use rand::thread_rng;");
+        }
+        let _ = writeln!(
+            out,
+            "use sim_by_fired_event::agents::{{Address, Agent, AgentContext, AgentId, AgentSimulator}};"
+        );
+        let _ = writeln!(
+            out,
+            "use sim_by_fired_event::event::{{Event, EventTimer, Priority, Schedule}};"
+        );
+        if self.metrics {
+            let _ = writeln!(out, "use std::collections::BTreeMap;");
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(out, "const FRAMES: u64 = {};", self.frames);
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "/// messages between the agents\n#[derive(Debug, Clone)]\nenum Message {{\n    Ping,\n    Pong,\n}}\n\nimpl Event for Message {{}}\n"
+        );
+        let _ = writeln!(out, "{}", self.recorder());
+        let _ = writeln!(
+            out,
+            "/// agent which reply to the ping\nstruct Player;\n\n\
+             impl Agent<Message, Recorder> for Player {{\n    \
+             fn handle(\n        &mut self,\n        ctx: &mut AgentContext<'_, Message, Recorder>,\n        priority: Priority,\n        from: Option<AgentId>,\n        event: Message,\n    ) {{\n        \
+             // reply to the sender, or to all agents when sent from outside\n        let to = from.map(Address::Agent).unwrap_or(Address::Broadcast);\n        let recorder = ctx.recorder();\n        let reply = match event {{\n            Message::Ping => {{\n                {ping}\n                Message::Pong\n            }}\n            Message::Pong => {{\n                {pong}\n                Message::Ping\n            }}\n        }};\n        \
+             ctx.send(Schedule::Timeout(EventTimer::Uniform(1, 3, true)), priority, to, reply)\n            .unwrap();\n    }}\n}}\n",
+            ping = self.record("Ping"),
+            pong = self.record("Pong"),
+        );
+        let summary = if self.metrics {
+            "simulator.get_recorder().print_summary();"
+        } else {
+            "println!(\"{} records\", simulator.get_recorder().log.len());"
+        };
+        let body = format!(
+            "let mut simulator = AgentSimulator::new(Recorder::default());\n{i}let first = simulator.spawn(&mut rng, Box::new(Player));\n{i}simulator.spawn(&mut rng, Box::new(Player));\n{i}simulator.send(&mut rng, Schedule::Immediate, Priority::NORMAL, Address::Agent(first), Message::Ping).unwrap();\n{i}simulator.run_n(&mut rng, FRAMES);\n{i}{summary}",
+            i = if self.replications.is_some() {
+                "        "
+            } else {
+                "    "
+            },
+            summary = summary,
+        );
+        match self.replications {
+            Some(count) => {
+                let _ = writeln!(
+                    out,
+                    "fn main() {{\n    for (index, seed) in SeedStrategy::Sequential(0).seeds({}).expect(\"sequential seeds are given for any replications\").into_iter().enumerate() {{\n        let mut rng = StdRng::seed_from_u64(seed);\n        println!(\"replication {{}} (seed {{}})\", index, seed);\n        {}\n    }}\n}}",
+                    count, body
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "fn main() {{\n    let mut rng = thread_rng();\n    {}\n}}",
+                    body
+                );
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,161 @@
+//! Composable stop conditions for the simulation
+
+use crate::event::{Event, EventScheduler};
+use crate::FrameIndex;
+
+/// state of the simulator which the stop condition can see
+#[derive(Debug)]
+pub struct StopContext<'a, M, E: Event, Rec> {
+    pub(crate) model: &'a M,
+    pub(crate) recorder: &'a Rec,
+    pub(crate) scheduler: &'a EventScheduler<E>,
+    pub(crate) frame: FrameIndex,
+}
+
+impl<'a, M, E: Event, Rec> StopContext<'a, M, E, Rec> {
+    /// getter for model
+    pub fn model(&self) -> &M {
+        self.model
+    }
+
+    /// getter for recorder
+    pub fn recorder(&self) -> &Rec {
+        self.recorder
+    }
+
+    /// getter for scheduler
+    pub fn scheduler(&self) -> &EventScheduler<E> {
+        self.scheduler
+    }
+
+    /// index of the last simulated frame. if not simulated yet, then 0.
+    pub fn frame(&self) -> FrameIndex {
+        self.frame
+    }
+}
+
+/// condition to stop the simulation which is checked before each frame
+pub trait StopCondition<M, E: Event, Rec> {
+    /// judge the simulation should stop
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool;
+}
+
+/// combinators for the stop conditions.
+/// the user defined condition can use combinators with empty implementation of this trait.
+pub trait StopConditionExt: Sized {
+    /// stop when both conditions are satisfied. both conditions are always checked.
+    fn and<C>(self, other: C) -> And<Self, C> {
+        And(self, other)
+    }
+
+    /// stop when either condition is satisfied. both conditions are always checked.
+    fn or<C>(self, other: C) -> Or<Self, C> {
+        Or(self, other)
+    }
+
+    /// stop when the condition is not satisfied
+    fn not(self) -> Not<Self> {
+        Not(self)
+    }
+}
+
+/// condition which is satisfied when both conditions are satisfied
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<M, E: Event, Rec, A, B> StopCondition<M, E, Rec> for And<A, B>
+where
+    A: StopCondition<M, E, Rec>,
+    B: StopCondition<M, E, Rec>,
+{
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool {
+        // check both to update the state of the conditions
+        let a = self.0.should_stop(ctx);
+        let b = self.1.should_stop(ctx);
+        a && b
+    }
+}
+
+/// condition which is satisfied when either condition is satisfied
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<M, E: Event, Rec, A, B> StopCondition<M, E, Rec> for Or<A, B>
+where
+    A: StopCondition<M, E, Rec>,
+    B: StopCondition<M, E, Rec>,
+{
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool {
+        // check both to update the state of the conditions
+        let a = self.0.should_stop(ctx);
+        let b = self.1.should_stop(ctx);
+        a || b
+    }
+}
+
+/// condition which is satisfied when the condition is not satisfied
+#[derive(Debug, Clone)]
+pub struct Not<C>(C);
+
+impl<M, E: Event, Rec, C> StopCondition<M, E, Rec> for Not<C>
+where
+    C: StopCondition<M, E, Rec>,
+{
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool {
+        !self.0.should_stop(ctx)
+    }
+}
+
+impl<A, B> StopConditionExt for And<A, B> {}
+impl<A, B> StopConditionExt for Or<A, B> {}
+impl<C> StopConditionExt for Not<C> {}
+
+/// stop after the count of frames from the first check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxFrames {
+    max: FrameIndex,
+    start: Option<FrameIndex>,
+}
+
+impl MaxFrames {
+    /// initializer
+    pub fn new(max: FrameIndex) -> Self {
+        MaxFrames { max, start: None }
+    }
+}
+
+impl<M, E: Event, Rec> StopCondition<M, E, Rec> for MaxFrames {
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool {
+        let start = *self.start.get_or_insert(ctx.frame);
+        ctx.frame - start >= self.max
+    }
+}
+
+impl StopConditionExt for MaxFrames {}
+
+/// stop when no event is scheduled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SchedulerEmpty;
+
+impl<M, E: Event, Rec> StopCondition<M, E, Rec> for SchedulerEmpty {
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool {
+        !ctx.scheduler.have_event()
+    }
+}
+
+impl StopConditionExt for SchedulerEmpty {}
+
+/// stop when the predicate is true
+#[derive(Debug, Clone, Copy)]
+pub struct Predicate<F>(pub F);
+
+impl<M, E: Event, Rec, F> StopCondition<M, E, Rec> for Predicate<F>
+where
+    F: FnMut(&StopContext<'_, M, E, Rec>) -> bool,
+{
+    fn should_stop(&mut self, ctx: &StopContext<'_, M, E, Rec>) -> bool {
+        (self.0)(ctx)
+    }
+}
+
+impl<F> StopConditionExt for Predicate<F> {}
@@ -0,0 +1,84 @@
+//! Asynchronous stepping of the simulator
+
+use crate::event::{Event, EventScheduler, Priority};
+use crate::frame::{FrameReport, Frames};
+use crate::model::Model;
+use futures_core::Stream;
+use rand::Rng;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// stream which simulate one frame for each polled item.
+///
+/// this stream yield to the runtime between frames and never finish,
+/// so the next frame is simulated only when the consumer request it.
+pub struct FrameStream<'a, M, E, Rec, R, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+{
+    frames: Frames<'a, M, E, Rec, R, H>,
+    yielded: bool,
+}
+
+impl<'a, M, E, Rec, R, H> FrameStream<'a, M, E, Rec, R, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+{
+    /// initializer
+    pub(crate) fn new(frames: Frames<'a, M, E, Rec, R, H>) -> Self {
+        FrameStream {
+            frames,
+            yielded: false,
+        }
+    }
+}
+
+impl<'a, M, E, Rec, R, H> Stream for FrameStream<'a, M, E, Rec, R, H>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event + Clone,
+    R: Rng + ?Sized,
+    H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>) + Unpin,
+{
+    type Item = FrameReport<E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if !this.yielded {
+            this.yielded = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        this.yielded = false;
+        Poll::Ready(this.frames.next())
+    }
+}
+
+/// future which yield to the runtime only once
+#[derive(Debug, Default)]
+pub(crate) struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.yielded {
+            return Poll::Ready(());
+        }
+
+        this.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
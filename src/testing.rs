@@ -0,0 +1,573 @@
+//! Helpers for the behavioral tests of models
+
+use crate::describe::DescribeEvents;
+use crate::event::{
+    Event, EventId, EventScheduler, EventTimer, FrameActions, LocalEventTime, Priority, Schedule,
+    Tag,
+};
+use crate::frame::FrameReport;
+use crate::model::{Model, StepEachEvent};
+use crate::trace::TraceRecord;
+use crate::{FrameIndex, Simulator, StepActions};
+use rand::rngs::mock::StepRng;
+use rand::Rng;
+use std::convert::{Infallible, TryFrom};
+use std::fmt;
+
+/// recorded fired events in fired order
+pub trait FiredTrace {
+    /// type of the recorded event
+    type Fired;
+
+    /// fired events in fired order
+    fn fired_events(&self) -> Vec<&Self::Fired>;
+}
+
+impl<E: Event> FiredTrace for [FrameReport<E>] {
+    type Fired = E;
+
+    fn fired_events(&self) -> Vec<&E> {
+        self.iter()
+            .flat_map(|report| report.fired_events.iter().map(|(_, event)| event))
+            .collect()
+    }
+}
+
+impl<E: Event> FiredTrace for Vec<FrameReport<E>> {
+    type Fired = E;
+
+    fn fired_events(&self) -> Vec<&E> {
+        self.as_slice().fired_events()
+    }
+}
+
+impl<E: Event> FiredTrace for [(Priority, E)] {
+    type Fired = E;
+
+    fn fired_events(&self) -> Vec<&E> {
+        self.iter().map(|(_, event)| event).collect()
+    }
+}
+
+impl<E: Event> FiredTrace for Vec<(Priority, E)> {
+    type Fired = E;
+
+    fn fired_events(&self) -> Vec<&E> {
+        self.as_slice().fired_events()
+    }
+}
+
+impl FiredTrace for [TraceRecord] {
+    type Fired = TraceRecord;
+
+    fn fired_events(&self) -> Vec<&TraceRecord> {
+        self.iter().collect()
+    }
+}
+
+impl FiredTrace for Vec<TraceRecord> {
+    type Fired = TraceRecord;
+
+    fn fired_events(&self) -> Vec<&TraceRecord> {
+        self.iter().collect()
+    }
+}
+
+/// name of the recorded event which is compared with the variant name
+pub trait FiredName {
+    /// name of the fired event
+    fn fired_name(&self) -> &str;
+}
+
+impl<E: DescribeEvents> FiredName for E {
+    fn fired_name(&self) -> &str {
+        self.variant_name()
+    }
+}
+
+/// the label is used as the name
+impl FiredName for TraceRecord {
+    fn fired_name(&self) -> &str {
+        &self.label
+    }
+}
+
+/// assert the order of the fired events in the recorded trace.
+///
+/// the trace is the frame reports, the fired events with the priority or the trace records.
+/// if the expected events are the variant names, e.g. `assert_fired_order!(reports, [Spawn, Move, Move])`,
+/// the names are compared with the variant names of DescribeEvents or the labels of the trace records.
+/// otherwise the expected events are the patterns, e.g. `assert_fired_order!(reports, [Event::Spawn(..), Event::Move { x: 1, .. }])`.
+#[macro_export]
+macro_rules! assert_fired_order {
+    ($trace:expr, [$($variant:ident),* $(,)?]) => {{
+        use $crate::testing::{FiredName as _, FiredTrace as _};
+        // bind the trace to keep the temporary trace alive, e.g. the collected frame reports
+        let trace = &$trace;
+        let fired: ::std::vec::Vec<&str> = trace
+            .fired_events()
+            .into_iter()
+            .map(|event| event.fired_name())
+            .collect();
+        let expected: &[&str] = &[$(stringify!($variant)),*];
+        assert_eq!(fired, expected, "fired order is different");
+    }};
+    ($trace:expr, [$($pattern:pat),* $(,)?]) => {{
+        use $crate::testing::FiredTrace as _;
+        let trace = &$trace;
+        let fired = trace.fired_events();
+        let expected: &[&str] = &[$(stringify!($pattern)),*];
+        assert_eq!(
+            fired.len(),
+            expected.len(),
+            "count of fired events is different: fired {:?}, expected {:?}",
+            fired,
+            expected
+        );
+        let mut index = 0;
+        $(
+            assert!(
+                matches!(fired[index], $pattern),
+                "fired event at {} is {:?}, expected {}",
+                index,
+                fired[index],
+                stringify!($pattern)
+            );
+            index += 1;
+        )*
+        let _ = index;
+    }};
+}
+
+/// digest of the Debug text of the value in FNV-1a 64 bit hex.
+/// the digest is stable between runs and builds, so it can be pinned in the test.
+pub fn debug_digest<T: fmt::Debug + ?Sized>(value: &T) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in format!("{:?}", value).bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// scheduler for the unit tests of the step of the model, which fire the events frame by frame by the test.
+///
+/// the random timers are decided by the rng which always return 0, so the uniform timer take the low value
+/// and the weighted timer take the first item with the positive weight.
+#[derive(Debug)]
+pub struct ManualScheduler<E: Event> {
+    scheduler: EventScheduler<E>,
+    rng: StepRng,
+    frame: FrameIndex,
+}
+
+impl<E: Event> Default for ManualScheduler<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Event> ManualScheduler<E> {
+    /// initializer at the frame 0
+    pub fn new() -> Self {
+        ManualScheduler {
+            scheduler: EventScheduler::new(),
+            rng: StepRng::new(0, 0),
+            frame: 0,
+        }
+    }
+
+    /// index of the last fired frame
+    pub fn frame(&self) -> FrameIndex {
+        self.frame
+    }
+
+    /// getter for the scheduler
+    pub fn scheduler(&self) -> &EventScheduler<E> {
+        &self.scheduler
+    }
+
+    /// getter for the scheduler as mutable
+    pub fn scheduler_mut(&mut self) -> &mut EventScheduler<E> {
+        &mut self.scheduler
+    }
+
+    /// getter for the rng and the scheduler to call the step of the model directly
+    pub fn parts(&mut self) -> (&mut StepRng, &mut EventScheduler<E>) {
+        (&mut self.rng, &mut self.scheduler)
+    }
+
+    /// store the event which fire after the frames. 0 frame is treated as 1 frame.
+    pub fn enqueue(&mut self, frames: LocalEventTime, priority: Priority, event: E) -> EventId {
+        let schedule = Schedule::Timeout(EventTimer::Time(frames.max(1)));
+        self.scheduler
+            .schedule_once(&mut self.rng, schedule, priority, event)
+            // the fixed timer is always valid
+            .unwrap()
+    }
+
+    /// scheduled events with the frame which the event fire at in fire order.
+    /// the events which wait other event are not contained,
+    /// and the frame of the event in the paused or scaled domain is calculated from the local time of the domain.
+    pub fn scheduled(&self) -> Vec<(EventId, FrameIndex, Priority, &E)> {
+        let frame = self.frame;
+        self.scheduler
+            .scheduled_entries()
+            .map(|(id, timer, priority, event)| {
+                (id, frame + FrameIndex::from(timer.max(1)), priority, event)
+            })
+            .collect()
+    }
+
+    /// fire the events of the next frame in fired order.
+    /// the immediate events are fired in the next frame or in the cascade, and the expired events are dropped.
+    /// the batched events are returned after the other events at the end of the window.
+    pub fn advance(&mut self) -> Vec<(Priority, E)> {
+        self.frame += 1;
+        let mut collector = Collector {
+            fired: vec![],
+            batched: vec![],
+        };
+        let _ = self
+            .scheduler
+            .fire_frame(&mut self.rng, &mut vec![], &mut collector);
+        let mut fired_events = collector.fired;
+        fired_events.append(&mut collector.batched);
+        fired_events
+    }
+
+    /// fire the events of the frames until the frame with the frame which the event is fired at.
+    /// if the frame is already passed, then nothing is fired.
+    pub fn advance_to(&mut self, frame: FrameIndex) -> Vec<(FrameIndex, Priority, E)> {
+        let mut fired_events = vec![];
+        while self.frame < frame {
+            let fired = self.advance();
+            let frame = self.frame;
+            fired_events.extend(
+                fired
+                    .into_iter()
+                    .map(|(priority, event)| (frame, priority, event)),
+            );
+        }
+        fired_events
+    }
+
+    /// advance the frames until some events fire. if no event is scheduled, then None.
+    /// the events in the paused domain or with the suspended tag can keep the frames empty,
+    /// so the frames are advanced at most until the earliest scheduled event.
+    pub fn fire_next(&mut self) -> Option<(FrameIndex, Vec<(Priority, E)>)> {
+        let earliest = self
+            .scheduler
+            .scheduled_entries()
+            .map(|(_, timer, _, _)| timer.max(1))
+            .min()?;
+        for _ in 0..earliest {
+            let fired = self.advance();
+            if !fired.is_empty() {
+                return Some((self.frame, fired));
+            }
+        }
+        Some((self.frame, vec![]))
+    }
+
+    /// initialize the model with this scheduler
+    pub fn initialize<M, Rec>(&mut self, model: &mut M, recorder: &mut Rec)
+    where
+        M: Model<Rec, ModelEvent = E>,
+    {
+        model.initialize(&mut self.rng, recorder, &mut self.scheduler);
+    }
+
+    /// step the model for the event with this scheduler
+    pub fn step_each_event<M, Rec>(
+        &mut self,
+        model: &mut M,
+        recorder: &mut Rec,
+        priority: Priority,
+        event: E,
+    ) where
+        M: StepEachEvent<Rec, E>,
+    {
+        model.step_each_event(
+            &mut self.rng,
+            recorder,
+            &mut self.scheduler,
+            priority,
+            event,
+        );
+    }
+
+    /// fire the events of the next frame and step the model for each fired event as the simulator do,
+    /// with the hooks of the model for the expired events, the batches and the cascades. the frame is not finished.
+    /// return the count of the fired events.
+    pub fn step_frame<M, Rec>(&mut self, model: &mut M, recorder: &mut Rec) -> usize
+    where
+        M: StepEachEvent<Rec, E>,
+    {
+        self.frame += 1;
+        let mut fired_total = 0;
+        let mut actions = StepActions {
+            model,
+            recorder,
+            handler: |rng: &mut StepRng,
+                      model: &mut M,
+                      recorder: &mut Rec,
+                      scheduler: &mut EventScheduler<E>,
+                      fired_events: &mut Vec<(Priority, E)>| {
+                Simulator::<M, E, Rec>::handler_each_event(
+                    rng,
+                    model,
+                    recorder,
+                    scheduler,
+                    fired_events,
+                );
+                Ok::<(), Infallible>(())
+            },
+            frame: self.frame,
+            fired_total: &mut fired_total,
+            capture_filter: &None,
+            recorder_hooks: None,
+            history_labeler: &None,
+            fired_history: &mut vec![],
+        };
+        let _ = self
+            .scheduler
+            .fire_frame(&mut self.rng, &mut vec![], &mut actions);
+        usize::try_from(fired_total).unwrap_or(usize::MAX)
+    }
+}
+
+/// actions which collect the fired events of the frame
+struct Collector<E: Event> {
+    fired: Vec<(Priority, E)>,
+    batched: Vec<(Priority, E)>,
+}
+
+impl<E: Event, R: Rng + ?Sized> FrameActions<E, R> for Collector<E> {
+    type Error = Infallible;
+
+    fn batch(
+        &mut self,
+        _rng: &mut R,
+        _scheduler: &mut EventScheduler<E>,
+        _tag: Tag,
+        mut events: Vec<(Priority, E)>,
+    ) {
+        self.batched.append(&mut events);
+    }
+
+    fn handle(
+        &mut self,
+        _rng: &mut R,
+        _scheduler: &mut EventScheduler<E>,
+        fired_events: &mut Vec<(Priority, E)>,
+    ) -> Result<(), Infallible> {
+        self.fired.append(fired_events);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe_events;
+    use crate::Simulator;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Ev {
+        Arrive(u32),
+        Leave(u32),
+    }
+
+    impl Event for Ev {}
+
+    describe_events!(Ev { Arrive(u32), Leave(u32) });
+
+    /// shop which three customers arrive every frame and stay for two frames
+    #[derive(Debug, Default)]
+    struct Shop {
+        in_store: u32,
+    }
+
+    impl Model<Vec<u32>> for Shop {
+        type ModelEvent = Ev;
+
+        fn initialize<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            _: &mut Vec<u32>,
+            scheduler: &mut EventScheduler<Ev>,
+        ) {
+            scheduler
+                .timeout(rng, EventTimer::Time(1), Priority::NORMAL, Ev::Arrive(0))
+                .unwrap();
+        }
+    }
+
+    impl StepEachEvent<Vec<u32>, Ev> for Shop {
+        fn step_each_event<R: Rng + ?Sized>(
+            &mut self,
+            rng: &mut R,
+            recorder: &mut Vec<u32>,
+            scheduler: &mut EventScheduler<Ev>,
+            _: Priority,
+            fired_event: Ev,
+        ) {
+            match fired_event {
+                Ev::Arrive(customer) => {
+                    self.in_store += 1;
+                    scheduler
+                        .timeout(rng, EventTimer::Time(2), Priority::LOW, Ev::Leave(customer))
+                        .unwrap();
+                    if customer < 2 {
+                        scheduler
+                            .timeout(
+                                rng,
+                                EventTimer::Time(1),
+                                Priority::NORMAL,
+                                Ev::Arrive(customer + 1),
+                            )
+                            .unwrap();
+                    }
+                }
+                Ev::Leave(_) => self.in_store -= 1,
+            }
+            recorder.push(self.in_store);
+        }
+    }
+
+    fn reports() -> Vec<FrameReport<Ev>> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = Simulator::create_from(&mut rng, Shop::default(), vec![]);
+        simulator.frames_each_event(&mut rng).take(5).collect()
+    }
+
+    #[test]
+    fn assert_fired_order_by_variant_names_and_patterns() {
+        let reports = reports();
+        assert_fired_order!(reports, [Arrive, Arrive, Arrive, Leave, Leave, Leave]);
+        assert_fired_order!(
+            reports,
+            [
+                Ev::Arrive(0),
+                Ev::Arrive(1),
+                Ev::Arrive(2),
+                Ev::Leave(0),
+                Ev::Leave(1),
+                Ev::Leave(_)
+            ]
+        );
+        assert_fired_order!(reports[2].fired_events, [Arrive, Leave]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulator = Simulator::create_from(&mut rng, Shop::default(), vec![]);
+        simulator.keep_fired_history(|event: &Ev| event.variant_name().to_string());
+        simulator.run_n_each_event(&mut rng, 3_u64);
+        assert_fired_order!(simulator.fired_history(), [Arrive, Arrive, Arrive, Leave]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fired order is different")]
+    fn assert_fired_order_fail_by_variant_names() {
+        assert_fired_order!(reports(), [Arrive, Leave, Arrive, Leave, Arrive, Leave]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fired event at 3 is Leave(0), expected Ev::Leave(1)")]
+    fn assert_fired_order_fail_by_patterns() {
+        assert_fired_order!(
+            reports(),
+            [
+                Ev::Arrive(_),
+                Ev::Arrive(_),
+                Ev::Arrive(_),
+                Ev::Leave(1),
+                Ev::Leave(_),
+                Ev::Leave(_)
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_digest_is_stable() {
+        assert_eq!(debug_digest(&vec![1, 2, 3]), debug_digest(&vec![1, 2, 3]));
+        assert_ne!(debug_digest(&vec![1, 2, 3]), debug_digest(&vec![3, 2, 1]));
+    }
+
+    #[test]
+    fn manual_scheduler_step_frame_by_frame() {
+        let mut manual = ManualScheduler::new();
+        let mut shop = Shop::default();
+        let mut recorder = vec![];
+        manual.initialize(&mut shop, &mut recorder);
+        let scheduled: Vec<(FrameIndex, &Ev)> = manual
+            .scheduled()
+            .into_iter()
+            .map(|(_, frame, _, event)| (frame, event))
+            .collect();
+        assert_eq!(scheduled, vec![(1, &Ev::Arrive(0))]);
+
+        assert_eq!(manual.step_frame(&mut shop, &mut recorder), 1);
+        assert_eq!(manual.frame(), 1);
+        let scheduled: Vec<(FrameIndex, &Ev)> = manual
+            .scheduled()
+            .into_iter()
+            .map(|(_, frame, _, event)| (frame, event))
+            .collect();
+        assert_eq!(scheduled, vec![(2, &Ev::Arrive(1)), (3, &Ev::Leave(0))]);
+
+        manual.step_frame(&mut shop, &mut recorder);
+        assert_eq!(manual.step_frame(&mut shop, &mut recorder), 2);
+        assert_eq!(shop.in_store, 2);
+        assert_eq!(recorder, vec![1, 2, 3, 2]);
+    }
+
+    #[test]
+    fn manual_scheduler_fire_without_model() {
+        let mut manual = ManualScheduler::new();
+        manual.enqueue(3, Priority::LOW, Ev::Leave(0));
+        manual.enqueue(0, Priority::HIGH, Ev::Arrive(0));
+        // the rng always return 0, so the uniform timer take the low value
+        let (rng, scheduler) = manual.parts();
+        scheduler
+            .timeout(
+                rng,
+                EventTimer::Uniform(3, 9, true),
+                Priority::NORMAL,
+                Ev::Arrive(1),
+            )
+            .unwrap();
+
+        assert_eq!(
+            manual.fire_next(),
+            Some((1, vec![(Priority::HIGH, Ev::Arrive(0))]))
+        );
+        assert_eq!(
+            manual.advance_to(4),
+            vec![
+                (3, Priority::NORMAL, Ev::Arrive(1)),
+                (3, Priority::LOW, Ev::Leave(0))
+            ]
+        );
+        assert_eq!(manual.fire_next(), None);
+        assert!(manual.advance_to(2).is_empty());
+        assert!(!manual.scheduler().have_event());
+    }
+
+    #[test]
+    fn manual_scheduler_step_each_event_directly() {
+        let mut manual = ManualScheduler::new();
+        let mut shop = Shop::default();
+        let mut recorder = vec![];
+        manual.step_each_event(&mut shop, &mut recorder, Priority::NORMAL, Ev::Arrive(2));
+        assert_eq!(shop.in_store, 1);
+        // the last customer does not call the next customer
+        assert_eq!(manual.scheduler().count(), 1);
+        assert_eq!(
+            manual.fire_next(),
+            Some((2, vec![(Priority::LOW, Ev::Leave(2))]))
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Re-entrant stepping of the simulator for the host which drive one frame per callback (e.g. browser with wasm)
+
+use crate::closure::CallbackEvent;
+use crate::event::{Event, EventClass, EventScheduler, Priority};
+use crate::frame::FrameReport;
+use crate::json::write_string;
+#[cfg(feature = "rayon")]
+use crate::model::ParStepEachEvent;
+use crate::model::{BulkEvents, MixedStep, Model, StepByPhase, StepByPriorityGroup, StepEachEvent};
+use crate::{owned_events, FrameIndex, Simulator};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Write;
+
+/// simulator which own the random number generator and simulate one frame for each tick.
+///
+/// no borrow is kept between the ticks, so this can be held in the struct which is exported to the host
+/// and the frame report is returned by value.
+#[derive(Debug, Clone)]
+pub struct Ticker<M, E, Rec, R = StdRng>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    simulator: Simulator<M, E, Rec>,
+    rng: R,
+}
+
+impl<M, E, Rec> Ticker<M, E, Rec, StdRng>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+{
+    /// create ticker with the random number generator from the seed.
+    /// seeding does not need the entropy of the host.
+    pub fn from_seed(seed: u64, model: M, recorder: Rec) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let simulator = Simulator::create_from(&mut rng, model, recorder);
+        Ticker { simulator, rng }
+    }
+}
+
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: Event,
+    R: Rng,
+{
+    /// create ticker from the initialized simulator and the random number generator
+    pub fn new(simulator: Simulator<M, E, Rec>, rng: R) -> Self {
+        Ticker { simulator, rng }
+    }
+
+    /// index of the last finished frame
+    pub fn frame_index(&self) -> FrameIndex {
+        self.simulator.frame_index()
+    }
+
+    /// getter for simulator
+    pub fn get_simulator(&self) -> &Simulator<M, E, Rec> {
+        &self.simulator
+    }
+
+    /// getter for simulator
+    pub fn get_simulator_as_mut(&mut self) -> &mut Simulator<M, E, Rec> {
+        &mut self.simulator
+    }
+
+    /// getter for random number generator
+    pub fn get_rng(&self) -> &R {
+        &self.rng
+    }
+
+    /// take the simulator and the random number generator
+    pub fn into_parts(self) -> (Simulator<M, E, Rec>, R) {
+        (self.simulator, self.rng)
+    }
+
+    /// simulate one frame with the handler and report the frame
+    pub fn tick<H>(&mut self, handler: H) -> FrameReport<E>
+    where
+        E: Clone,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, Vec<(Priority, E)>),
+    {
+        self.tick_buffered(owned_events(handler))
+    }
+
+    /// same as tick, but the handler take the buffer of the fired events
+    pub fn tick_buffered<H>(&mut self, handler: H) -> FrameReport<E>
+    where
+        E: Clone,
+        H: FnMut(&mut R, &mut M, &mut Rec, &mut EventScheduler<E>, &mut Vec<(Priority, E)>),
+    {
+        // frames never finish
+        self.simulator
+            .frames_buffered(&mut self.rng, handler)
+            .next()
+            .unwrap()
+    }
+}
+
+/// tick for fired event with calculate in bulk
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: BulkEvents<Rec, E>,
+    E: Event + Clone,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_in_bulk_event(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_in_bulk_event)
+    }
+}
+
+/// tick for fired event with calculate each event
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: StepEachEvent<Rec, E>,
+    E: Event + Clone,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_each_event(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_each_event)
+    }
+}
+
+/// tick for fired event with calculate each group of the same priority
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: StepByPriorityGroup<Rec, E>,
+    E: Event + Clone,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_by_priority(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_by_priority)
+    }
+}
+
+/// tick for fired event with calculate phase by phase
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: StepByPhase<Rec, E>,
+    E: Event + Clone,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_phased(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_phased)
+    }
+}
+
+/// tick for fired event with calculate each event or in bulk by the class of the event
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: MixedStep<Rec, E>,
+    E: EventClass + Clone,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_mixed(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_mixed)
+    }
+}
+
+/// tick for the closure events by calling the callbacks
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: Model<Rec, ModelEvent = E>,
+    E: CallbackEvent<M, Rec> + Clone,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_closure(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_closure)
+    }
+}
+
+/// tick for the disjoint parts of the model in parallel
+#[cfg(feature = "rayon")]
+impl<M, E, Rec, R> Ticker<M, E, Rec, R>
+where
+    M: ParStepEachEvent<Rec, E>,
+    E: Event + Clone + Send,
+    R: Rng,
+{
+    /// simulate one frame and report the frame
+    pub fn tick_par_each_event(&mut self) -> FrameReport<E> {
+        self.tick_buffered(Simulator::handler_par_each_event)
+    }
+}
+
+/// serialize the frame report to the JSON text such as `{"frame":1,"events":[{"priority":3,"label":"Tweet"}]}`
+pub fn report_to_json<E, L>(report: &FrameReport<E>, mut labeler: L) -> String
+where
+    E: Event,
+    L: FnMut(&E) -> String,
+{
+    let mut out = String::new();
+    let _ = write!(out, "{{\"frame\":{},\"events\":[", report.index);
+    for (index, (priority, event)) in report.fired_events.iter().enumerate() {
+        if index != 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{{\"priority\":{},\"label\":", priority);
+        write_string(&mut out, &labeler(event));
+        out.push('}');
+    }
+    out.push_str("]}");
+    out
+}
@@ -0,0 +1,608 @@
+//! Trace of fired events and the tools for traces
+
+use crate::annotation::Annotation;
+use crate::describe::DescribeEvents;
+use crate::event::{Event, Priority};
+use crate::frame::FrameReport;
+use crate::json::{self, JsonValue};
+use crate::FrameIndex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// one fired event in trace
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraceRecord {
+    /// index of the frame which the event is fired at
+    pub frame: FrameIndex,
+    /// priority of fired event
+    pub priority: Priority,
+    /// label of fired event
+    pub label: String,
+}
+
+/// name of the trace format which is written in the header
+pub const TRACE_FORMAT: &str = "sim-by-fired-event/trace";
+
+/// version of the trace format which is written by TraceWriter.
+/// version 2 add the annotation lines.
+pub const TRACE_FORMAT_VERSION: u32 = 2;
+
+/// header of trace file
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceHeader {
+    /// version of the trace format. the trace without header is version 0.
+    pub version: u32,
+    /// information about the run which produced the trace
+    pub manifest: BTreeMap<String, String>,
+}
+
+impl TraceHeader {
+    /// header line of the format
+    fn to_json(&self) -> String {
+        let mut line = String::from("{\"format\":");
+        json::write_string(&mut line, TRACE_FORMAT);
+        line.push_str(&format!(",\"version\":{},\"manifest\":{{", self.version));
+        for (index, (key, value)) in self.manifest.iter().enumerate() {
+            if index != 0 {
+                line.push(',');
+            }
+            json::write_string(&mut line, key);
+            line.push(':');
+            json::write_string(&mut line, value);
+        }
+        line.push_str("}}");
+        line
+    }
+
+    /// parse header line
+    fn from_json(line: &str) -> Option<Self> {
+        let value = json::parse(line)?;
+        if value.get("format")?.as_str()? != TRACE_FORMAT {
+            return None;
+        }
+        let version = value.get("version")?.as_number()?;
+        let mut manifest = BTreeMap::new();
+        if let Some(members) = value.get("manifest").and_then(JsonValue::as_object) {
+            for (key, value) in members.iter() {
+                manifest.insert(key.clone(), value.as_str()?.to_string());
+            }
+        }
+        Some(TraceHeader { version, manifest })
+    }
+}
+
+/// writer which write fired events as trace.
+///
+/// the format of the trace is the header line and the record lines in JSON Lines.
+/// see README for the specification.
+#[derive(Debug)]
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    header: TraceHeader,
+    header_written: bool,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// initializer
+    pub fn new(writer: W) -> Self {
+        Self::with_manifest(writer, BTreeMap::new())
+    }
+
+    /// initializer with the manifest which is written in the header
+    pub fn with_manifest(writer: W, manifest: BTreeMap<String, String>) -> Self {
+        TraceWriter {
+            writer,
+            header: TraceHeader {
+                version: TRACE_FORMAT_VERSION,
+                manifest,
+            },
+            header_written: false,
+        }
+    }
+
+    /// getter for header
+    pub fn header(&self) -> &TraceHeader {
+        &self.header
+    }
+
+    /// add the information to the manifest. this is ignored after the header is written.
+    pub fn insert_manifest(&mut self, key: &str, value: &str) {
+        if !self.header_written {
+            self.header
+                .manifest
+                .insert(key.to_string(), value.to_string());
+        }
+    }
+
+    /// write fired events in the frame with labeling event
+    pub fn write_frame<E, L>(&mut self, report: &FrameReport<E>, mut labeler: L) -> io::Result<()>
+    where
+        E: Event,
+        L: FnMut(&E) -> String,
+    {
+        for (priority, event) in report.fired_events.iter() {
+            self.write_record(&TraceRecord {
+                frame: report.index,
+                priority: *priority,
+                label: labeler(event),
+            })?;
+        }
+        for text in report.annotations.iter() {
+            self.write_annotation(&Annotation {
+                frame: report.index,
+                text: text.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// write fired events in the frame with labeling event by the variant name
+    pub fn write_frame_described<E>(&mut self, report: &FrameReport<E>) -> io::Result<()>
+    where
+        E: DescribeEvents,
+    {
+        self.write_frame(report, |event| event.variant_name().to_string())
+    }
+
+    /// write header if not written yet
+    fn write_header(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            writeln!(self.writer, "{}", self.header.to_json())?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    /// write one record
+    pub fn write_record(&mut self, record: &TraceRecord) -> io::Result<()> {
+        self.write_header()?;
+        let mut line = format!(
+            "{{\"frame\":{},\"priority\":{},\"label\":",
+            record.frame, record.priority
+        );
+        json::write_string(&mut line, &record.label);
+        line.push('}');
+        writeln!(self.writer, "{}", line)
+    }
+
+    /// write one annotation
+    pub fn write_annotation(&mut self, annotation: &Annotation) -> io::Result<()> {
+        self.write_header()?;
+        let mut line = format!("{{\"frame\":{},\"annotation\":", annotation.frame);
+        json::write_string(&mut line, &annotation.text);
+        line.push('}');
+        writeln!(self.writer, "{}", line)
+    }
+
+    /// flush the writer with the header
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_header()?;
+        self.writer.flush()
+    }
+
+    /// get the writer back. the header is not written if nothing is written and not flushed.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// reader which read the trace line by line.
+///
+/// the trace in the tab separated format without header is also readable as version 0.
+/// the annotation lines are not yielded as the record, but kept in the reader.
+#[derive(Debug)]
+pub struct TraceReader<R: BufRead> {
+    lines: io::Lines<R>,
+    header: TraceHeader,
+    first_record: Option<String>,
+    line_index: usize,
+    annotations: Vec<Annotation>,
+}
+
+impl<R: BufRead> TraceReader<R> {
+    /// initializer which read the header
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        let mut line_index = 0;
+        let mut header = TraceHeader::default();
+        let mut first_record = None;
+        for line in lines.by_ref() {
+            let line = line?;
+            line_index += 1;
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('{') {
+                header = TraceHeader::from_json(&line).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid trace header")
+                })?;
+                if header.version > TRACE_FORMAT_VERSION {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unsupported trace version {}", header.version),
+                    ));
+                }
+            } else {
+                first_record = Some(line);
+            }
+            break;
+        }
+        Ok(TraceReader {
+            lines,
+            header,
+            first_record,
+            line_index,
+            annotations: vec![],
+        })
+    }
+
+    /// getter for header
+    pub fn header(&self) -> &TraceHeader {
+        &self.header
+    }
+
+    /// annotations which are read until now
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// take the annotations which are read until now
+    pub fn take_annotations(&mut self) -> Vec<Annotation> {
+        std::mem::take(&mut self.annotations)
+    }
+
+    /// keep the line as the annotation if the line is the annotation
+    fn read_annotation(&mut self, line: &str) -> bool {
+        if self.header.version < 2 {
+            return false;
+        }
+        match parse_annotation(line) {
+            Some(annotation) => {
+                self.annotations.push(annotation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// parse one line of trace
+    fn parse(&self, line: &str) -> io::Result<TraceRecord> {
+        let record = if self.header.version == 0 {
+            parse_legacy_record(line)
+        } else {
+            parse_record(line)
+        };
+        record.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid trace record at line {}", self.line_index),
+            )
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(line) = self.first_record.take() {
+            return Some(self.parse(&line));
+        }
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line_index += 1;
+            if !line.is_empty() && !self.read_annotation(&line) {
+                return Some(self.parse(&line));
+            }
+        }
+    }
+}
+
+/// header and records of trace file
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceFile {
+    /// header of the trace
+    pub header: TraceHeader,
+    /// fired events in written order
+    pub records: Vec<TraceRecord>,
+    /// annotations in written order
+    pub annotations: Vec<Annotation>,
+}
+
+impl TraceFile {
+    /// rebuild the fired events for each frame to replay with converting label to event.
+    /// the record which cannot be converted is skipped.
+    pub fn to_frame_reports<E, F>(&self, mut to_event: F) -> Vec<FrameReport<E>>
+    where
+        E: Event,
+        F: FnMut(&str) -> Option<E>,
+    {
+        let mut reports: Vec<FrameReport<E>> = vec![];
+        for record in self.records.iter() {
+            let event = match to_event(&record.label) {
+                Some(event) => event,
+                None => continue,
+            };
+            match reports.last_mut() {
+                Some(report) if report.index == record.frame => {
+                    report.fired_events.push((record.priority, event))
+                }
+                _ => reports.push(FrameReport {
+                    index: record.frame,
+                    fired_events: vec![(record.priority, event)],
+                    annotations: vec![],
+                }),
+            }
+        }
+        for annotation in self.annotations.iter() {
+            let index = reports.partition_point(|report| report.index < annotation.frame);
+            match reports.get_mut(index) {
+                Some(report) if report.index == annotation.frame => {
+                    report.annotations.push(annotation.text.clone())
+                }
+                _ => reports.insert(
+                    index,
+                    FrameReport {
+                        index: annotation.frame,
+                        fired_events: vec![],
+                        annotations: vec![annotation.text.clone()],
+                    },
+                ),
+            }
+        }
+        reports
+    }
+}
+
+/// read trace file with the header
+pub fn read_trace_file<P: AsRef<Path>>(path: P) -> io::Result<TraceFile> {
+    let mut reader = TraceReader::new(BufReader::new(File::open(path)?))?;
+    let header = reader.header().clone();
+    let records = reader.by_ref().collect::<io::Result<Vec<TraceRecord>>>()?;
+    Ok(TraceFile {
+        header,
+        records,
+        annotations: reader.take_annotations(),
+    })
+}
+
+/// read trace which is written by TraceWriter
+pub fn read_trace<P: AsRef<Path>>(path: P) -> io::Result<Vec<TraceRecord>> {
+    read_trace_file(path).map(|file| file.records)
+}
+
+/// parse one line of trace
+fn parse_record(line: &str) -> Option<TraceRecord> {
+    let value = json::parse(line)?;
+    Some(TraceRecord {
+        frame: value.get("frame")?.as_number()?,
+        priority: value.get("priority")?.as_number()?,
+        label: value.get("label")?.as_str()?.to_string(),
+    })
+}
+
+/// parse one annotation line of trace
+fn parse_annotation(line: &str) -> Option<Annotation> {
+    let value = json::parse(line)?;
+    Some(Annotation {
+        frame: value.get("frame")?.as_number()?,
+        text: value.get("annotation")?.as_str()?.to_string(),
+    })
+}
+
+/// parse one line of trace without header
+fn parse_legacy_record(line: &str) -> Option<TraceRecord> {
+    let mut items = line.splitn(3, '\t');
+    let frame = items.next()?.parse().ok()?;
+    let priority = items.next()?.parse().ok()?;
+    let label = items.next().unwrap_or("").to_string();
+    Some(TraceRecord {
+        frame,
+        priority,
+        label,
+    })
+}
+
+/// write the fired events and the scheduled events as Gantt chart in Mermaid.
+/// the frame of the scheduled event is the frame which the event will fire at.
+pub fn write_timeline<W: Write>(
+    writer: &mut W,
+    fired: &[TraceRecord],
+    scheduled: &[TraceRecord],
+) -> io::Result<()> {
+    writeln!(writer, "gantt")?;
+    writeln!(writer, "    dateFormat X")?;
+    writeln!(writer, "    axisFormat %s")?;
+    for (section, records, tag) in [("fired", fired, "done, "), ("scheduled", scheduled, "")] {
+        if records.is_empty() {
+            continue;
+        }
+        writeln!(writer, "    section {}", section)?;
+        for record in records.iter() {
+            writeln!(
+                writer,
+                "    {} P={} :{}{}, {}",
+                timeline_label(&record.label),
+                record.priority,
+                tag,
+                record.frame,
+                record.frame + 1
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// label which can be the name of the task in Mermaid
+fn timeline_label(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| match c {
+            ':' | ';' | '#' | '\n' | '\r' => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+/// events which are different at the frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDivergence {
+    /// index of the frame
+    pub frame: FrameIndex,
+    /// fired events which exist only in the trace a
+    pub only_in_a: Vec<(Priority, String)>,
+    /// fired events which exist only in the trace b
+    pub only_in_b: Vec<(Priority, String)>,
+    /// true when both traces have same events but the fired order is different
+    pub order_only: bool,
+}
+
+/// result of comparing two traces
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceDiff {
+    /// the first frame which fired events are different
+    pub first_divergence: Option<FrameDivergence>,
+    /// count of frames which is in the trace a or b
+    pub frames_compared: usize,
+    /// count of frames which fired events are different
+    pub diverging_frames: usize,
+    /// count of fired events in the trace a
+    pub events_in_a: usize,
+    /// count of fired events in the trace b
+    pub events_in_b: usize,
+    /// count of fired events which exist only in the trace a
+    pub events_only_in_a: usize,
+    /// count of fired events which exist only in the trace b
+    pub events_only_in_b: usize,
+}
+
+impl TraceDiff {
+    /// judge both traces are same
+    pub fn is_identical(&self) -> bool {
+        self.diverging_frames == 0
+    }
+}
+
+impl fmt::Display for TraceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.first_divergence {
+            None => write!(
+                f,
+                "traces are identical ({} frames, {} events)",
+                self.frames_compared, self.events_in_a
+            ),
+            Some(divergence) => {
+                writeln!(f, "first divergence at frame {}", divergence.frame)?;
+                if divergence.order_only {
+                    writeln!(f, "  same events are fired in different order")?;
+                }
+                for (priority, label) in divergence.only_in_a.iter() {
+                    writeln!(f, "  - P={} {}", priority, label)?;
+                }
+                for (priority, label) in divergence.only_in_b.iter() {
+                    writeln!(f, "  + P={} {}", priority, label)?;
+                }
+                write!(
+                    f,
+                    "{} of {} frames diverge, {} events only in a, {} events only in b",
+                    self.diverging_frames,
+                    self.frames_compared,
+                    self.events_only_in_a,
+                    self.events_only_in_b
+                )
+            }
+        }
+    }
+}
+
+/// compare two trace files structurally
+pub fn diff<P: AsRef<Path>, Q: AsRef<Path>>(path_a: P, path_b: Q) -> io::Result<TraceDiff> {
+    let a = read_trace(path_a)?;
+    let b = read_trace(path_b)?;
+    Ok(diff_records(&a, &b))
+}
+
+/// compare two traces structurally
+pub fn diff_records(a: &[TraceRecord], b: &[TraceRecord]) -> TraceDiff {
+    let frames_a = group_by_frame(a);
+    let frames_b = group_by_frame(b);
+    let mut frames: Vec<FrameIndex> = frames_a.keys().chain(frames_b.keys()).copied().collect();
+    frames.sort_unstable();
+    frames.dedup();
+
+    let mut result = TraceDiff {
+        frames_compared: frames.len(),
+        events_in_a: a.len(),
+        events_in_b: b.len(),
+        ..Default::default()
+    };
+    let empty = vec![];
+    for frame in frames {
+        let events_a = frames_a.get(&frame).unwrap_or(&empty);
+        let events_b = frames_b.get(&frame).unwrap_or(&empty);
+        let divergence = match frame_divergence(frame, events_a, events_b) {
+            Some(divergence) => divergence,
+            None => continue,
+        };
+
+        result.diverging_frames += 1;
+        result.events_only_in_a += divergence.only_in_a.len();
+        result.events_only_in_b += divergence.only_in_b.len();
+        if result.first_divergence.is_none() {
+            result.first_divergence = Some(divergence);
+        }
+    }
+    result
+}
+
+/// compare the fired events at the frame. if same, then None.
+pub(crate) fn frame_divergence(
+    frame: FrameIndex,
+    events_a: &[(Priority, String)],
+    events_b: &[(Priority, String)],
+) -> Option<FrameDivergence> {
+    if events_a == events_b {
+        return None;
+    }
+    let only_in_a = subtract(events_a, events_b);
+    let only_in_b = subtract(events_b, events_a);
+    Some(FrameDivergence {
+        frame,
+        order_only: only_in_a.is_empty() && only_in_b.is_empty(),
+        only_in_a,
+        only_in_b,
+    })
+}
+
+/// fired events for each frame with keeping fired order
+fn group_by_frame(records: &[TraceRecord]) -> BTreeMap<FrameIndex, Vec<(Priority, String)>> {
+    let mut frames: BTreeMap<FrameIndex, Vec<(Priority, String)>> = BTreeMap::new();
+    for record in records.iter() {
+        frames
+            .entry(record.frame)
+            .or_default()
+            .push((record.priority, record.label.clone()));
+    }
+    frames
+}
+
+/// events in left which are not matched with events in right as multiset
+fn subtract(left: &[(Priority, String)], right: &[(Priority, String)]) -> Vec<(Priority, String)> {
+    let mut rest: Vec<&(Priority, String)> = right.iter().collect();
+    let mut result = vec![];
+    for event in left.iter() {
+        match rest.iter().position(|other| *other == event) {
+            Some(index) => {
+                rest.remove(index);
+            }
+            None => result.push(event.clone()),
+        }
+    }
+    result
+}
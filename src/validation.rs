@@ -0,0 +1,249 @@
+//! Validation of replicated results against analytical baselines
+
+use std::fmt;
+
+/// acceptable difference between the result and the expected value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    /// difference in the unit of the value
+    Absolute(f64),
+    /// ratio of the difference to the expected value
+    Relative(f64),
+}
+
+impl Tolerance {
+    /// tolerance as absolute difference for the expected value
+    fn to_absolute(self, expected: f64) -> f64 {
+        match self {
+            Tolerance::Absolute(tolerance) => tolerance.abs(),
+            Tolerance::Relative(ratio) => (ratio * expected).abs(),
+        }
+    }
+}
+
+/// closed form expectation for the metric which is extracted from each result
+struct Baseline<T> {
+    name: String,
+    expected: f64,
+    tolerance: Tolerance,
+    extract: Box<dyn Fn(&T) -> f64>,
+}
+
+/// validator which check replicated results with registered baselines.
+///
+/// the baseline is failed when the mean of replications differ from the expected value
+/// more than the tolerance and the width of the confidence interval.
+pub struct Validator<T> {
+    baselines: Vec<Baseline<T>>,
+    z: f64,
+}
+
+impl<T> Default for Validator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Validator<T> {
+    /// create validator with 95% confidence
+    pub fn new() -> Self {
+        Validator {
+            baselines: vec![],
+            z: 1.96,
+        }
+    }
+
+    /// change the quantile of normal distribution for the confidence interval. default is 1.96.
+    pub fn with_z(mut self, z: f64) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// register the expectation for the metric
+    pub fn expect<F>(mut self, name: &str, expected: f64, tolerance: Tolerance, extract: F) -> Self
+    where
+        F: Fn(&T) -> f64 + 'static,
+    {
+        self.baselines.push(Baseline {
+            name: name.to_string(),
+            expected,
+            tolerance,
+            extract: Box::new(extract),
+        });
+        self
+    }
+
+    /// check the results of replications with all baselines
+    pub fn validate(&self, results: &[T]) -> ValidationReport {
+        let checks = self
+            .baselines
+            .iter()
+            .map(|baseline| {
+                let values: Vec<f64> = results.iter().map(|r| (baseline.extract)(r)).collect();
+                let (mean, std_dev) = mean_and_std_dev(&values);
+                let std_error = if values.is_empty() {
+                    0.0
+                } else {
+                    std_dev / (values.len() as f64).sqrt()
+                };
+                let half_width = self.z * std_error;
+                let tolerance = baseline.tolerance.to_absolute(baseline.expected);
+                BaselineCheck {
+                    name: baseline.name.clone(),
+                    expected: baseline.expected,
+                    replications: values.len(),
+                    mean,
+                    std_dev,
+                    ci_low: mean - half_width,
+                    ci_high: mean + half_width,
+                    passed: !values.is_empty()
+                        && (mean - baseline.expected).abs() <= tolerance + half_width,
+                }
+            })
+            .collect();
+        ValidationReport { checks }
+    }
+}
+
+impl<T> fmt::Debug for Validator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field(
+                "baselines",
+                &self
+                    .baselines
+                    .iter()
+                    .map(|b| (&b.name, b.expected, b.tolerance))
+                    .collect::<Vec<_>>(),
+            )
+            .field("z", &self.z)
+            .finish()
+    }
+}
+
+/// sample mean and sample standard deviation
+pub(crate) fn mean_and_std_dev(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+/// result of checking one baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineCheck {
+    /// name of the baseline
+    pub name: String,
+    /// expected value
+    pub expected: f64,
+    /// count of replications
+    pub replications: usize,
+    /// mean of the replications
+    pub mean: f64,
+    /// sample standard deviation of the replications
+    pub std_dev: f64,
+    /// lower bound of the confidence interval of the mean
+    pub ci_low: f64,
+    /// upper bound of the confidence interval of the mean
+    pub ci_high: f64,
+    /// false when the result deviate significantly
+    pub passed: bool,
+}
+
+/// result of validation for all baselines
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    /// result of each baseline in registered order
+    pub checks: Vec<BaselineCheck>,
+}
+
+impl ValidationReport {
+    /// judge all baselines are passed
+    pub fn is_valid(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// baselines which are failed
+    pub fn failures(&self) -> Vec<&BaselineCheck> {
+        self.checks.iter().filter(|check| !check.passed).collect()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in self.checks.iter() {
+            writeln!(
+                f,
+                "[{}] {}: expected {} mean {} ({}..{}, n={})",
+                if check.passed { "ok" } else { "NG" },
+                check.name,
+                check.expected,
+                check.mean,
+                check.ci_low,
+                check.ci_high,
+                check.replications
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//
+// closed form expectations
+//
+
+/// utilization of M/M/1 queue. if the queue is unstable then None.
+pub fn mm1_utilization(arrival_rate: f64, service_rate: f64) -> Option<f64> {
+    let rho = arrival_rate / service_rate;
+    if rho < 1.0 {
+        Some(rho)
+    } else {
+        None
+    }
+}
+
+/// mean number of customers in M/M/1 system
+pub fn mm1_mean_number_in_system(arrival_rate: f64, service_rate: f64) -> Option<f64> {
+    mm1_utilization(arrival_rate, service_rate).map(|rho| rho / (1.0 - rho))
+}
+
+/// mean waiting time in the queue of M/M/1 system
+pub fn mm1_mean_wait(arrival_rate: f64, service_rate: f64) -> Option<f64> {
+    mm1_utilization(arrival_rate, service_rate).map(|rho| rho / (service_rate - arrival_rate))
+}
+
+/// mean time in M/M/1 system
+pub fn mm1_mean_time_in_system(arrival_rate: f64, service_rate: f64) -> Option<f64> {
+    mm1_utilization(arrival_rate, service_rate).map(|_| 1.0 / (service_rate - arrival_rate))
+}
+
+/// probability of waiting in M/M/c queue (Erlang C formula)
+pub fn mmc_wait_probability(servers: u32, arrival_rate: f64, service_rate: f64) -> Option<f64> {
+    let c = servers as f64;
+    let a = arrival_rate / service_rate;
+    let rho = a / c;
+    if servers == 0 || rho >= 1.0 {
+        return None;
+    }
+
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 1..servers {
+        term *= a / k as f64;
+        sum += term;
+    }
+    let last = term * a / c / (1.0 - rho);
+    Some(last / (sum + last))
+}
+
+/// mean waiting time in the queue of M/M/c system
+pub fn mmc_mean_wait(servers: u32, arrival_rate: f64, service_rate: f64) -> Option<f64> {
+    mmc_wait_probability(servers, arrival_rate, service_rate)
+        .map(|p| p / (servers as f64 * service_rate - arrival_rate))
+}
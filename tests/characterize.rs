@@ -0,0 +1,80 @@
+//! the characterization test which is generated by CharacterizationTest is compiled and run as it is
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sim_by_fired_event::characterize::CharacterizationTest;
+use sim_by_fired_event::event::{Event, EventScheduler, EventTimer, Priority};
+use sim_by_fired_event::model::{Model, StepEachEvent};
+use sim_by_fired_event::testing::debug_digest;
+use sim_by_fired_event::Simulator;
+
+// the generated source is compared as it is, so it is not formatted
+#[rustfmt::skip]
+#[path = "characterize/pinger_is_stable.rs"]
+mod pinger_is_stable;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ping(u32);
+
+impl Event for Ping {}
+
+/// model which ping three times at the random interval
+#[derive(Debug, Default)]
+pub struct Pinger;
+
+impl Model<Vec<u32>> for Pinger {
+    type ModelEvent = Ping;
+
+    fn initialize<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        _: &mut Vec<u32>,
+        scheduler: &mut EventScheduler<Ping>,
+    ) {
+        scheduler
+            .repeat(
+                rng,
+                3,
+                EventTimer::Uniform(1, 3, true),
+                Priority::NORMAL,
+                Ping(0),
+            )
+            .unwrap();
+    }
+}
+
+impl StepEachEvent<Vec<u32>, Ping> for Pinger {
+    fn step_each_event<R: Rng + ?Sized>(
+        &mut self,
+        _: &mut R,
+        recorder: &mut Vec<u32>,
+        _: &mut EventScheduler<Ping>,
+        _: Priority,
+        fired_event: Ping,
+    ) {
+        recorder.push(fired_event.0);
+    }
+}
+
+#[test]
+fn generated_source_is_up_to_date() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut simulator = Simulator::create_from(&mut rng, Pinger, vec![]);
+    simulator.keep_fired_history(|event: &Ping| format!("{:?}", event));
+    simulator.run_n_each_event(&mut rng, 10_u64);
+
+    let source = CharacterizationTest::from_trace("pinger_is_stable", 3, simulator.fired_history())
+        .frames(simulator.frame_index())
+        .import("crate::Pinger")
+        .setup("Simulator::create_from(&mut rng, Pinger, vec![])")
+        .digest(
+            "simulator.get_recorder()",
+            &debug_digest(simulator.get_recorder()),
+        )
+        .source();
+    assert_eq!(
+        include_str!("characterize/pinger_is_stable.rs"),
+        source,
+        "regenerate tests/characterize/pinger_is_stable.rs by CharacterizationTest"
+    );
+}
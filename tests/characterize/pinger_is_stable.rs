@@ -0,0 +1,40 @@
+// characterization test which is generated from the recorded run.
+// regenerate this test when the change of the behavior is intended.
+
+use rand::SeedableRng;
+use sim_by_fired_event::Simulator;
+use crate::Pinger;
+
+#[test]
+fn pinger_is_stable() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    let mut simulator = Simulator::create_from(&mut rng, Pinger, vec![]);
+    #[allow(unused_mut)]
+    let mut labeler = |event: &_| format!("{:?}", event);
+    let mut fired: Vec<(u64, u8, String)> = Vec::new();
+    for report in simulator.frames_each_event(&mut rng).take(10) {
+        for (priority, event) in report.fired_events.iter() {
+            fired.push((report.index, u8::from(*priority), labeler(event)));
+        }
+    }
+
+    let expected: &[(u64, u8, &str)] = &[
+        (2, 128, "Ping(0)"),
+        (3, 128, "Ping(0)"),
+        (6, 128, "Ping(0)"),
+    ];
+    assert_eq!(fired.len(), expected.len(), "count of fired events is different");
+    for (index, (actual, expected)) in fired.iter().zip(expected.iter()).enumerate() {
+        assert_eq!(
+            (actual.0, actual.1, actual.2.as_str()),
+            *expected,
+            "fired event at {} is different",
+            index
+        );
+    }
+    assert_eq!(
+        sim_by_fired_event::testing::debug_digest(simulator.get_recorder()),
+        "b69884490b212729",
+        "final digest is different"
+    );
+}